@@ -0,0 +1,15 @@
+#![no_main]
+
+use {
+    libfuzzer_sys::fuzz_target,
+    platform_mem::{testing, Global, TempFile},
+    std::sync::Once,
+};
+
+fuzz_target!(|data: &[u8]| {
+    static HOOK: Once = Once::new();
+    HOOK.call_once(testing::silence_injected_panics);
+
+    testing::run_ops(&mut Global::<u64>::new(), data).unwrap();
+    testing::run_ops(&mut TempFile::<u8>::new().unwrap(), data).unwrap();
+});
@@ -1,20 +1,28 @@
 use {
     crate::{
-        utils,
-        Error::{AllocError, CapacityOverflow},
-        RawMem, RawPlace, Result,
+        observer::Observer,
+        registry::Tracker,
+        trace, utils, watchdog,
+        Error::{self, AllocError, CapacityOverflow},
+        MemObserver, MemoryUsage, RawMem, RawPlace, Reservation, Result, StableMem,
     },
     std::{
         alloc::{Allocator, Layout},
         fmt::{self, Debug, Formatter},
         mem::{self, MaybeUninit},
-        ptr,
+        ptr::{self, NonNull},
+        sync::Arc,
     },
 };
 
 pub struct Alloc<T, A: Allocator> {
     buf: RawPlace<T>,
     alloc: A,
+    observer: Observer,
+    tracker: Tracker,
+    label: Option<Arc<str>>,
+    max_slack: usize,
+    huge_pages: Option<usize>,
 }
 
 impl<T, A: Allocator> Alloc<T, A> {
@@ -26,48 +34,275 @@ impl<T, A: Allocator> Alloc<T, A> {
     /// static ALLOC: Global<()> = Global::new();
     /// ```
     pub const fn new(alloc: A) -> Self {
-        Self { buf: RawPlace::dangling(), alloc }
+        Self {
+            buf: RawPlace::dangling(),
+            alloc,
+            observer: Observer::none(),
+            tracker: Tracker::none(),
+            label: None,
+            max_slack: 0,
+            huge_pages: None,
+        }
     }
-}
 
-impl<T, A: Allocator> RawMem for Alloc<T, A> {
-    type Item = T;
+    /// Replaces the observer of grows, shrinks and their errors
+    pub fn set_observer(&mut self, observer: impl MemObserver + 'static) {
+        self.observer.set(observer);
+    }
 
-    fn allocated(&self) -> &[Self::Item] {
-        unsafe { self.buf.as_slice() }
+    pub fn take_observer(&mut self) -> Option<Box<dyn MemObserver>> {
+        self.observer.take()
     }
 
-    fn allocated_mut(&mut self) -> &mut [Self::Item] {
-        unsafe { self.buf.as_slice_mut() }
+    /// Names the memory in `Debug` output, errors, tracing spans and the registry
+    /// ```
+    /// # #![feature(allocator_api)]
+    /// use platform_mem::{Global, RawMem};
+    ///
+    /// let mut mem = Global::<u64>::new().with_label("links");
+    /// let err = mem.grow_filled(usize::MAX, 0).unwrap_err();
+    /// assert_eq!(err.to_string(), "`links`: exceeding the capacity maximum");
+    /// assert!(format!("{mem:?}").contains(r#"label: "links""#));
+    /// ```
+    pub fn with_label(mut self, label: &str) -> Self {
+        let label = Arc::from(label);
+        self.tracker.label(&label);
+        self.label = Some(label);
+        self
     }
 
-    unsafe fn grow(
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// Makes shrinks only drop the removed items and keep their place for the next grows,
+    /// until more than `max_slack` items are kept or [`compact`](Self::compact) is called.
+    /// Like any allocation, the kept place is grown as uninitialized, so
+    /// [`grow_assumed`](RawMem::grow_assumed) must not be used over it.
+    /// ```
+    /// # #![feature(allocator_api)]
+    /// use platform_mem::{Global, RawMem};
+    ///
+    /// let mut mem = Global::<u64>::new().with_deferred_shrink(100);
+    /// mem.grow_filled(100, 0)?;
+    /// mem.shrink(60)?;
+    /// assert_eq!(mem.memory_usage().capacity, 800);
+    ///
+    /// mem.grow_filled(10, 1)?; // reuses the kept place
+    /// mem.compact()?;
+    /// assert_eq!(mem.memory_usage().capacity, 400);
+    /// # Ok::<_, platform_mem::Error>(())
+    /// ```
+    pub fn with_deferred_shrink(mut self, max_slack: usize) -> Self {
+        self.max_slack = max_slack;
+        self
+    }
+
+    /// Makes shrinks and drop of the memory skip dropping of items,
+    /// as if they were [`ManuallyDrop`](mem::ManuallyDrop)
+    /// ```
+    /// # #![feature(allocator_api)]
+    /// use {platform_mem::{Global, RawMem}, std::rc::Rc};
+    ///
+    /// let rc = Rc::new(());
+    /// let mut mem = Global::new().with_manual_drop();
+    /// mem.grow_filled(3, rc.clone())?;
+    /// mem.shrink(2)?;
+    /// drop(mem);
+    /// assert_eq!(Rc::strong_count(&rc), 4); // clones are leaked
+    /// # Ok::<_, platform_mem::Error>(())
+    /// ```
+    pub fn with_manual_drop(mut self) -> Self {
+        self.buf.set_manual_drop(true);
+        self
+    }
+
+    /// Rounds capacity of allocations from `threshold` bytes up to whole 2 MiB pages
+    /// and advises the kernel to back them by transparent huge pages (on Linux),
+    /// so large in-RAM memories get fewer TLB misses without reserved huge pages
+    /// ```
+    /// # #![feature(allocator_api)]
+    /// use platform_mem::{Global, RawMem};
+    ///
+    /// let mut mem = Global::<u8>::new().with_huge_pages(1 << 20);
+    /// mem.grow_filled(1000, 0)?;
+    /// assert_eq!(mem.memory_usage().capacity, 1000); // below the threshold
+    ///
+    /// mem.grow_filled(3 << 20, 0)?;
+    /// assert_eq!(mem.memory_usage().capacity, 4 << 20);
+    /// # Ok::<_, platform_mem::Error>(())
+    /// ```
+    pub fn with_huge_pages(mut self, threshold: usize) -> Self {
+        self.huge_pages = Some(threshold);
+        self
+    }
+
+    /// Capacity for `cap` items rounded up to huge pages if they are enabled
+    fn huge_cap(&self, cap: usize) -> usize {
+        let size = mem::size_of::<T>();
+        match self.huge_pages {
+            Some(threshold) if size != 0 && cap.saturating_mul(size) >= threshold => cap
+                .checked_mul(size)
+                .and_then(|bytes| bytes.checked_next_multiple_of(utils::HUGE_PAGE))
+                .map_or(cap, |bytes| bytes / size),
+            _ => cap,
+        }
+    }
+
+    /// Releases the place kept by deferred shrinks
+    pub fn compact(&mut self) -> Result<()> {
+        if self.buf.len() == self.buf.cap() {
+            return Ok(());
+        }
+        trace::timed!(
+            "compact",
+            mem = "Alloc",
+            label = self.label(),
+            len = self.buf.len(),
+            cap = self.buf.cap()
+        );
+
+        let result = self.release(self.buf.len()).map_err(|err| err.labeled(self.label()));
+        watchdog::verify!(self, "compact");
+        result
+    }
+
+    /// Gives whole unused pages of the place kept by deferred shrinks or reservations
+    /// back to the OS without changing the capacity, and returns their size in bytes
+    pub fn trim(&mut self) -> Result<usize> {
+        let (len, cap) = (self.buf.len(), self.buf.cap());
+        if len == cap {
+            return Ok(0);
+        }
+        trace::timed!("trim", mem = "Alloc", label = self.label(), len, cap);
+
+        let size = mem::size_of::<T>();
+        let tail = unsafe { self.buf.ptr().as_ptr().add(len).cast::<u8>() };
+        // SAFETY: unused place is uninit and owned only by this memory
+        unsafe { utils::decommit(ptr::slice_from_raw_parts_mut(tail, (cap - len) * size)) }
+            .map_err(|err| Error::from(err).labeled(self.label()))
+    }
+
+    /// Allocates place for `addition` items after the initialized ones
+    /// and reports if the items become visible by [`commit`](Self::commit)
+    /// ```
+    /// # #![feature(allocator_api)]
+    /// use platform_mem::{Global, RawMem};
+    ///
+    /// let mut mem = Global::<u64>::new();
+    /// mem.grow_from_slice(&[1, 2])?;
+    ///
+    /// let reservation = mem.begin_grow(3)?;
+    /// assert_eq!(mem.allocated(), [1, 2]); // still readable at the old length
+    /// unsafe {
+    ///     mem.commit(reservation, |_, (_, uninit)| {
+    ///         uninit.iter_mut().for_each(|item| _ = item.write(3));
+    ///     })?;
+    /// }
+    /// assert_eq!(mem.allocated(), [1, 2, 3, 3, 3]);
+    /// # Ok::<_, platform_mem::Error>(())
+    /// ```
+    pub fn begin_grow(&mut self, addition: usize) -> Result<Reservation> {
+        let result = self.reserve_unobserved(addition).map_err(|err| err.labeled(self.label()));
+        watchdog::verify!(self, "begin_grow");
+        result
+    }
+
+    /// Initializes reserved place by `fill` like [`RawMem::grow`]
+    ///
+    /// # Safety
+    /// Same as [`RawMem::grow`], `reservation` must come from this memory
+    pub unsafe fn commit(
         &mut self,
-        addition: usize,
+        reservation: Reservation,
         fill: impl FnOnce(usize, (&mut [T], &mut [MaybeUninit<T>])),
     ) -> Result<&mut [T]> {
-        let cap = self.buf.cap().checked_add(addition).ok_or(CapacityOverflow)?;
-        let new_layout = Layout::array::<T>(cap).map_err(|_| CapacityOverflow)?;
+        self.observed_grow(|this| this.commit_unobserved(reservation, fill))
+    }
 
-        let ptr = if let Some((ptr, old_layout)) = self.buf.current_memory() {
-            self.alloc.grow(ptr, old_layout, new_layout)
-        } else {
-            self.alloc.allocate(new_layout)
-        }
-        .map_err(|_| AllocError { layout: new_layout, non_exhaustive: () })?
-        .cast();
+    fn reserve_unobserved(&mut self, addition: usize) -> Result<Reservation> {
+        trace::timed!(
+            "reserve",
+            mem = "Alloc",
+            label = self.label(),
+            len = self.buf.len(),
+            addition
+        );
+
+        let cap = self.buf.len().checked_add(addition).ok_or(CapacityOverflow)?;
+        // place may be kept by a deferred shrink
+        if cap > self.buf.cap() {
+            let cap = self.huge_cap(cap);
+            let new_layout = Layout::array::<T>(cap).map_err(|_| CapacityOverflow)?;
 
+            unsafe {
+                let ptr = if RawPlace::<T>::IS_ZST {
+                    NonNull::dangling()
+                } else {
+                    if let Some((ptr, old_layout)) = self.buf.current_memory() {
+                        self.alloc.grow(ptr, old_layout, new_layout)
+                    } else {
+                        self.alloc.allocate(new_layout)
+                    }
+                    .map_err(|_| AllocError { layout: new_layout, non_exhaustive: () })?
+                    .cast()
+                };
+
+                // nothing is filled yet, the place is only owned
+                self.buf.handle_fill((ptr, cap), 0, 0, |_, _| {});
+
+                if self.huge_pages.is_some_and(|threshold| new_layout.size() >= threshold) {
+                    // it's only a hint, e.g. huge pages may be disabled
+                    let bytes =
+                        ptr::slice_from_raw_parts_mut(ptr.as_ptr().cast(), new_layout.size());
+                    let _ = utils::advise_huge(bytes);
+                }
+            }
+        }
         // allocator always provide uninit memory
-        Ok(self.buf.handle_fill((ptr, cap), 0, fill))
+        Ok(Reservation { addition, inited: 0, generation: self.buf.generation() })
     }
 
-    fn shrink(&mut self, cap: usize) -> Result<()> {
-        let cap = self.buf.cap().checked_sub(cap).expect("Tried to shrink to a larger capacity");
+    unsafe fn commit_unobserved(
+        &mut self,
+        reservation: Reservation,
+        fill: impl FnOnce(usize, (&mut [T], &mut [MaybeUninit<T>])),
+    ) -> Result<()> {
+        let Reservation { addition, inited, generation } = reservation;
+        let reserved = generation == self.buf.generation()
+            && self.buf.len().checked_add(addition).is_some_and(|len| len <= self.buf.cap());
+        let inited = if reserved { inited } else { self.reserve_unobserved(addition)?.inited };
+
+        trace::timed!("grow", mem = "Alloc", label = self.label(), len = self.buf.len(), addition);
+        self.buf.handle_fill((self.buf.ptr(), self.buf.cap()), addition, inited, fill);
+        Ok(())
+    }
 
-        let Some((ptr, layout)) = self.buf.current_memory() else {
+    fn shrink_unobserved(&mut self, cap: usize) -> Result<()> {
+        trace::timed!(
+            "shrink",
+            mem = "Alloc",
+            label = self.label(),
+            len = self.buf.len(),
+            removal = cap
+        );
+
+        let cap = self.buf.len().checked_sub(cap).expect("Tried to shrink to a larger capacity");
+        if self.buf.cap() - cap <= self.max_slack {
+            self.buf.truncate(cap);
             return Ok(());
-        };
+        }
+        self.release(cap)
+    }
+
+    /// Shrinks allocation to `cap` items, dropping items after it
+    fn release(&mut self, cap: usize) -> Result<()> {
+        let memory = self.buf.current_memory();
         self.buf.shrink_to(cap);
+        // zero-sized items are never allocated
+        let Some((ptr, layout)) = memory else {
+            return Ok(());
+        };
 
         let ptr = unsafe {
             // `Layout::array` cannot overflow here because it would have
@@ -86,19 +321,93 @@ impl<T, A: Allocator> RawMem for Alloc<T, A> {
     }
 }
 
+impl<T, A: Allocator> Alloc<T, A> {
+    fn observed_grow(&mut self, grow: impl FnOnce(&mut Self) -> Result<()>) -> Result<&mut [T]> {
+        let old = self.buf.len();
+        let result = grow(self).map_err(|err| err.labeled(self.label()));
+        watchdog::verify!(self, "grow");
+        self.observer.grown(old, self.buf.len(), result)?;
+        self.tracker.resized::<T>("Alloc", self.buf.len(), true);
+        Ok(unsafe { &mut self.buf.as_slice_mut()[old..] })
+    }
+
+    #[cfg(all(debug_assertions, feature = "watchdog"))]
+    fn verify(&self, op: &'static str) {
+        let (ptr, len, cap) = (self.buf.ptr(), self.buf.len(), self.buf.cap());
+        let mut report = watchdog::Report::new("Alloc", op)
+            .state("label", self.label())
+            .state("ptr", ptr)
+            .state("len", len)
+            .state("cap", cap);
+
+        report.ensure(len <= cap, || format!("length {len} exceeds capacity {cap}"));
+        report.ensure(ptr.is_aligned(), || {
+            format!("{ptr:?} is not aligned to {}", mem::align_of::<T>())
+        });
+        report.ensure(Layout::array::<T>(cap).is_ok(), || {
+            format!("capacity {cap} overflows the layout")
+        });
+        report.finish();
+    }
+}
+
+// SAFETY: items are allocated apart from the memory
+unsafe impl<T, A: Allocator> StableMem for Alloc<T, A> {}
+
+impl<T, A: Allocator> RawMem for Alloc<T, A> {
+    type Item = T;
+
+    fn allocated(&self) -> &[Self::Item] {
+        unsafe { self.buf.as_slice() }
+    }
+
+    fn allocated_mut(&mut self) -> &mut [Self::Item] {
+        unsafe { self.buf.as_slice_mut() }
+    }
+
+    unsafe fn grow(
+        &mut self,
+        addition: usize,
+        fill: impl FnOnce(usize, (&mut [T], &mut [MaybeUninit<T>])),
+    ) -> Result<&mut [T]> {
+        self.observed_grow(|this| {
+            let reservation = this.reserve_unobserved(addition)?;
+            this.commit_unobserved(reservation, fill)
+        })
+    }
+
+    fn shrink(&mut self, cap: usize) -> Result<()> {
+        let old = self.buf.len();
+        let result = self.shrink_unobserved(cap).map_err(|err| err.labeled(self.label()));
+        watchdog::verify!(self, "shrink");
+        self.observer.shrunk(old, self.buf.len(), result)?;
+        self.tracker.resized::<T>("Alloc", self.buf.len(), false);
+        Ok(())
+    }
+
+    fn generation(&self) -> u64 {
+        self.buf.generation()
+    }
+
+    fn memory_usage(&self) -> MemoryUsage {
+        let size = mem::size_of::<T>();
+        MemoryUsage::new(self.buf.len() * size, self.buf.cap() * size, None)
+    }
+}
+
 impl<T, A: Allocator> Drop for Alloc<T, A> {
     fn drop(&mut self) {
         unsafe {
+            self.buf.drop_items();
             if let Some((ptr, layout)) = self.buf.current_memory() {
-                ptr::drop_in_place(self.buf.as_slice_mut());
                 self.alloc.deallocate(ptr, layout);
             }
         }
     }
 }
 
-impl<T, A: Allocator + Debug> Debug for Alloc<T, A> {
+impl<T: Debug, A: Allocator + Debug> Debug for Alloc<T, A> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        utils::debug_mem(f, &self.buf, "Alloc")?.field("alloc", &self.alloc).finish()
+        utils::debug_mem(f, &self.buf, "Alloc", self.label())?.field("alloc", &self.alloc).finish()
     }
 }
@@ -0,0 +1,249 @@
+use {
+    crate::{Error::CapacityOverflow, RawMem, Result, StableMem},
+    bytemuck::Pod,
+    std::{
+        fmt::{self, Formatter},
+        ptr::NonNull,
+        slice,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, OnceLock,
+        },
+    },
+};
+
+// segment `k` has `first << k` items, so this many of them hold any index
+const SEGMENTS: usize = usize::BITS as usize;
+
+/// Memory of one segment, which is grown only once when it's created
+struct Segment<M: RawMem> {
+    ptr: NonNull<M::Item>,
+    _mem: M,
+}
+
+struct Inner<M: RawMem> {
+    segments: [OnceLock<Segment<M>>; SEGMENTS],
+    /// Length of the first segment
+    first: usize,
+    len: AtomicUsize, // only the `Appender` stores it
+}
+
+// SAFETY: readers only get `&T` to published items, which are never mutated,
+// unpublished items are accessed only by the writer, `M` is only kept to be dropped
+unsafe impl<M: StableMem + Send> Send for Inner<M> where M::Item: Send + Sync {}
+unsafe impl<M: StableMem + Send> Sync for Inner<M> where M::Item: Send + Sync {}
+
+impl<M: StableMem> Inner<M> {
+    /// Segment of the item at `index` and the index within it
+    fn locate(&self, index: usize) -> (usize, usize) {
+        let rank = index / self.first + 1;
+        let segment = rank.ilog2() as usize;
+        (segment, index - self.first * ((1 << segment) - 1))
+    }
+
+    fn segment_len(&self, segment: usize) -> usize {
+        self.first << segment
+    }
+
+    fn published(&self) -> usize {
+        self.len.load(Ordering::Acquire)
+    }
+
+    fn get(&self, index: usize) -> Option<&M::Item> {
+        if index >= self.published() {
+            return None;
+        }
+        let (segment, offset) = self.locate(index);
+        let segment = self.segments[segment].get()?;
+        // SAFETY: published items are initialized and immutable
+        Some(unsafe { segment.ptr.add(offset).as_ref() })
+    }
+
+    fn slices(&self) -> impl Iterator<Item = &[M::Item]> {
+        let mut rest = self.published();
+        self.segments.iter().enumerate().map_while(move |(i, segment)| {
+            let len = rest.min(self.segment_len(i));
+            rest -= len;
+            let segment = segment.get().filter(|_| len > 0)?;
+            // SAFETY: first `len` items of the segment are published
+            Some(unsafe { slice::from_raw_parts(segment.ptr.as_ptr(), len) })
+        })
+    }
+}
+
+/// Append-only memory with wait-free reads, built of segments of [`StableMem`]s.
+///
+/// Segments are created by the [`Appender`] when the previous ones are full, each
+/// one twice as long as the previous and grown only once, so items never move
+/// and readers can hold references while new items are published through
+/// the atomic length. Items are spread over segments, so readers get them
+/// by [`get`](Self::get), [`iter`](Self::iter) or slices of [`segments`](Self::segments).
+///
+/// Segments of [`FileMapped`](crate::FileMapped)s keep the log in files,
+/// which are published again by [`open`](Self::open).
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use platform_mem::{AppendMem, Global};
+///
+/// let (mut writer, reader) = AppendMem::new(2, Global::<u64>::new);
+/// writer.push(1)?;
+///
+/// let first = reader.get(0).unwrap();
+/// writer.extend_from_slice(&[2, 3])?; // the next segment is created
+///
+/// assert_eq!(*first, 1);
+/// assert_eq!(reader.iter().copied().collect::<Vec<_>>(), [1, 2, 3]);
+/// assert_eq!(reader.segments().collect::<Vec<_>>(), [&[1, 2][..], &[3]]);
+/// # Ok::<_, platform_mem::Error>(())
+/// ```
+pub struct AppendMem<M: RawMem> {
+    inner: Arc<Inner<M>>,
+}
+
+/// Unique writer of an [`AppendMem`]
+pub struct Appender<M: RawMem> {
+    inner: Arc<Inner<M>>,
+    segment: Box<dyn FnMut() -> M + Send>,
+}
+
+impl<M: StableMem> AppendMem<M>
+where
+    M::Item: Pod,
+{
+    /// Memory whose first segment has `first` items, `segment` creates empty memories
+    /// of segments, which are grown by zeroed items.
+    ///
+    /// # Panics
+    /// Panics if `first` is zero.
+    pub fn new(
+        first: usize,
+        segment: impl FnMut() -> M + Send + 'static,
+    ) -> (Appender<M>, AppendMem<M>) {
+        assert!(first > 0, "segment must contain at least one item");
+        let segments = [const { OnceLock::new() }; SEGMENTS];
+        let inner = Arc::new(Inner { segments, first, len: AtomicUsize::new(0) });
+        (Appender { inner: Arc::clone(&inner), segment: Box::new(segment) }, AppendMem { inner })
+    }
+
+    /// Like [`new`](Self::new), but the first `len` items are already stored
+    /// in the memories created by `segment` (e.g. reopened files of segments in order),
+    /// they are kept and published.
+    pub fn open(
+        first: usize,
+        len: usize,
+        segment: impl FnMut() -> M + Send + 'static,
+    ) -> Result<(Appender<M>, AppendMem<M>)> {
+        let (mut writer, reader) = Self::new(first, segment);
+        if let Some(last) = len.checked_sub(1) {
+            for segment in 0..=writer.inner.locate(last).0 {
+                writer.create(segment)?;
+            }
+            writer.inner.len.store(len, Ordering::Release);
+        }
+        Ok((writer, reader))
+    }
+}
+
+impl<M: StableMem> AppendMem<M> {
+    /// Count of items published at the moment of the call
+    pub fn len(&self) -> usize {
+        self.inner.published()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<&M::Item> {
+        self.inner.get(index)
+    }
+
+    /// Items published at the moment of the call
+    pub fn iter(&self) -> impl Iterator<Item = &M::Item> {
+        self.segments().flatten()
+    }
+
+    /// Published items of each segment, only the last one may be partial
+    pub fn segments(&self) -> impl Iterator<Item = &[M::Item]> {
+        self.inner.slices()
+    }
+}
+
+impl<M: StableMem> Appender<M>
+where
+    M::Item: Pod,
+{
+    /// Creates one more reader of this memory
+    pub fn reader(&self) -> AppendMem<M> {
+        AppendMem { inner: Arc::clone(&self.inner) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.published()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn push(&mut self, value: M::Item) -> Result<()> {
+        self.extend_from_slice(&[value])
+    }
+
+    /// Publishes all items of `src` at once, or nothing if a segment can't be created
+    pub fn extend_from_slice(&mut self, src: &[M::Item]) -> Result<()> {
+        let len = self.inner.len.load(Ordering::Relaxed);
+        let end = len.checked_add(src.len()).ok_or(CapacityOverflow)?;
+        if src.is_empty() {
+            return Ok(());
+        }
+
+        let (last, _) = self.inner.locate(end - 1);
+        for segment in self.inner.locate(len).0..=last {
+            self.create(segment)?;
+        }
+        for (index, &item) in (len..end).zip(src) {
+            let (segment, offset) = self.inner.locate(index);
+            let Some(segment) = self.inner.segments[segment].get() else {
+                unreachable!("segments of all items are created above")
+            };
+            // SAFETY: item is not published yet, so only the writer accesses it
+            unsafe { segment.ptr.add(offset).write(item) };
+        }
+        self.inner.len.store(end, Ordering::Release);
+        Ok(())
+    }
+
+    fn create(&mut self, segment: usize) -> Result<()> {
+        if self.inner.segments[segment].get().is_some() {
+            return Ok(());
+        }
+        let len = self.inner.first.checked_mul(1 << segment).ok_or(CapacityOverflow)?;
+
+        let mut mem = (self.segment)();
+        // SAFETY: `Pod` items are valid zeroed or as they are stored (e.g. in a reopened file),
+        // `M: StableMem` keeps them in place when `mem` is moved into the segment
+        let ptr = NonNull::from(unsafe { mem.grow_zeroed_exact(len)? }).cast();
+        let _ = self.inner.segments[segment].set(Segment { ptr, _mem: mem });
+        Ok(())
+    }
+}
+
+impl<M: RawMem> Clone for AppendMem<M> {
+    fn clone(&self) -> Self {
+        Self { inner: Arc::clone(&self.inner) }
+    }
+}
+
+impl<M: RawMem> fmt::Debug for AppendMem<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AppendMem").field("len", &self.inner.len).finish()
+    }
+}
+
+impl<M: RawMem> fmt::Debug for Appender<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Appender").field("len", &self.inner.len).finish()
+    }
+}
@@ -0,0 +1,98 @@
+//! Zero-copy persistence through [`rkyv`].
+//!
+//! [`archive_into`] replaces the memory with an archived value and
+//! [`access`] validates the bytes and returns the archived root in place,
+//! so a [`FileMapped<u8>`] can be reopened without deserialization.
+//!
+//! The archive is prefixed by its length, so the padding of mapped files
+//! after it does not matter. Archived data must be aligned, it's always true
+//! for [`FileMapped`] which is mapped at the page boundary,
+//! but allocations of `u8` have no such guarantee.
+//!
+//! ```
+//! use platform_mem::{archive, FileMapped, RawMem};
+//! use rkyv::{Archive, Serialize};
+//!
+//! #[derive(Archive, Serialize)]
+//! struct Doc {
+//!     name: String,
+//!     links: Vec<u64>,
+//! }
+//!
+//! let file = tempfile::tempfile()?;
+//! let mut mem = FileMapped::<u8>::new(file.try_clone()?)?;
+//! archive::archive_into(&mut mem, &Doc { name: "links".into(), links: vec![1, 2, 3] })?;
+//! drop(mem);
+//!
+//! let len = file.metadata()?.len() as usize;
+//! let mut mem = FileMapped::<u8>::new(file)?;
+//! unsafe { mem.grow_assumed(len)? };
+//! let doc = archive::access::<Doc, _>(&mem)?;
+//! assert_eq!(doc.name, "links");
+//! assert_eq!(doc.links, [1, 2, 3]);
+//! # Ok::<_, platform_mem::Error>(())
+//! ```
+//!
+//! [`FileMapped<u8>`]: crate::FileMapped
+//! [`FileMapped`]: crate::FileMapped
+
+use {
+    crate::{RawMem, Result},
+    rkyv::{
+        api::high::{HighSerializer, HighValidator},
+        bytecheck::CheckBytes,
+        rancor,
+        ser::allocator::ArenaHandle,
+        util::AlignedVec,
+        Archive, Portable, Serialize,
+    },
+    std::io,
+};
+
+// archive length and padding to keep the archive aligned to 16
+const HEADER: usize = 16;
+
+fn invalid<E: Into<Box<dyn std::error::Error + Send + Sync>>>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+/// Replaces allocated memory with the archived `value`, returns the archive size in bytes
+pub fn archive_into<T, M>(mem: &mut M, value: &T) -> Result<usize>
+where
+    T: for<'a> Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, rancor::Error>>,
+    M: RawMem<Item = u8> + ?Sized,
+{
+    let archive = rkyv::to_bytes::<rancor::Error>(value).map_err(invalid)?;
+    let mut bytes = Vec::with_capacity(HEADER + archive.len());
+    bytes.extend_from_slice(&(archive.len() as u64).to_le_bytes());
+    bytes.resize(HEADER, 0);
+    bytes.extend_from_slice(&archive);
+
+    // reuse already allocated bytes to avoid extra remaps
+    let len = mem.allocated().len();
+    if len > bytes.len() {
+        mem.shrink(len - bytes.len())?;
+    }
+    let len = mem.allocated().len();
+    mem.allocated_mut().copy_from_slice(&bytes[..len]);
+    mem.grow_from_slice(&bytes[len..])?;
+
+    Ok(archive.len())
+}
+
+/// Validates allocated memory and returns the archived root of `T`
+pub fn access<T, M>(mem: &M) -> Result<&T::Archived>
+where
+    T: Archive,
+    T::Archived: Portable + for<'a> CheckBytes<HighValidator<'a, rancor::Error>>,
+    M: RawMem<Item = u8> + ?Sized,
+{
+    let bytes = mem.allocated();
+    let header = bytes.get(..8).ok_or_else(|| invalid("memory is too short for an archive"))?;
+    let len = u64::from_le_bytes(header.try_into().map_err(invalid)?);
+    let archive = usize::try_from(len)
+        .ok()
+        .and_then(|len| bytes.get(HEADER..HEADER.checked_add(len)?))
+        .ok_or_else(|| invalid("archive length is out of allocated bounds"))?;
+    Ok(rkyv::access::<T::Archived, rancor::Error>(archive).map_err(invalid)?)
+}
@@ -0,0 +1,130 @@
+use {
+    crate::{checkpoint, RawMem, Result},
+    bytemuck::Pod,
+    std::{
+        fs, io,
+        path::{Component, Path, PathBuf},
+    },
+};
+
+/// Rotated set of checkpoint files in one directory.
+///
+/// Backups are named `{prefix}.{seq}` with increasing `seq`, the file
+/// `{prefix}.latest` points to the newest one, and only `keep` newest
+/// backups are left after each [`backup`](Self::backup).
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use platform_mem::{BackupSet, Global, RawMem};
+///
+/// let dir = tempfile::tempdir()?;
+/// let backups = BackupSet::new(dir.path(), "links", 2)?;
+///
+/// let mut mem = Global::<u64>::new();
+/// for i in 0..5 {
+///     mem.grow_filled(1, i)?;
+///     backups.backup(&mem)?;
+/// }
+/// assert_eq!(backups.list()?.len(), 2);
+///
+/// let mut restored = Global::<u64>::new();
+/// backups.restore_latest(&mut restored)?;
+/// assert_eq!(restored.allocated(), [0, 1, 2, 3, 4]);
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct BackupSet {
+    dir: PathBuf,
+    prefix: String,
+    keep: usize,
+}
+
+impl BackupSet {
+    /// Creates `dir` if it does not exist, `keep` is at least one
+    pub fn new<P: AsRef<Path>>(dir: P, prefix: &str, keep: usize) -> io::Result<Self> {
+        fs::create_dir_all(dir.as_ref())?;
+        Ok(Self { dir: dir.as_ref().to_owned(), prefix: prefix.to_owned(), keep: keep.max(1) })
+    }
+
+    fn pointer(&self) -> PathBuf {
+        self.dir.join(format!("{}.latest", self.prefix))
+    }
+
+    fn seq_of(&self, name: &str) -> Option<u64> {
+        name.strip_prefix(&self.prefix)?.strip_prefix('.')?.parse().ok()
+    }
+
+    /// Backups from the oldest to the newest
+    pub fn list(&self) -> io::Result<Vec<PathBuf>> {
+        let mut backups = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            if let Some(seq) = entry.file_name().to_str().and_then(|name| self.seq_of(name)) {
+                backups.push((seq, entry.path()));
+            }
+        }
+        backups.sort_unstable();
+        Ok(backups.into_iter().map(|(_, path)| path).collect())
+    }
+
+    /// The newest backup by the pointer file, or by listing if the pointer is missing.
+    ///
+    /// Fails with [`io::ErrorKind::InvalidData`] if the pointer names anything but
+    /// a backup of this set in its directory, e.g. an absolute path or `..`.
+    pub fn latest(&self) -> io::Result<Option<PathBuf>> {
+        match fs::read_to_string(self.pointer()) {
+            Ok(name) => {
+                let name = name.trim();
+                let mut components = Path::new(name).components();
+                let plain = matches!(
+                    (components.next(), components.next()),
+                    (Some(Component::Normal(_)), None)
+                );
+                if !plain || self.seq_of(name).is_none() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("backup pointer names `{name}` instead of a backup of this set"),
+                    ));
+                }
+                Ok(Some(self.dir.join(name)))
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(self.list()?.pop()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Writes a new checkpoint of `mem`, moves the pointer and prunes old backups
+    pub fn backup<M: RawMem + ?Sized>(&self, mem: &M) -> Result<PathBuf>
+    where
+        M::Item: Pod,
+    {
+        let backups = self.list()?;
+        let next = backups
+            .last()
+            .and_then(|path| self.seq_of(path.file_name()?.to_str()?))
+            .map_or(0, |seq| seq + 1);
+
+        let name = format!("{}.{next:010}", self.prefix);
+        let path = self.dir.join(&name);
+        mem.checkpoint_to_path(&path)?;
+        checkpoint::write_atomic(&self.pointer(), name.as_bytes())?;
+
+        // the new backup is not in the list yet
+        let stale = (backups.len() + 1).saturating_sub(self.keep);
+        for old in &backups[..stale] {
+            fs::remove_file(old)?;
+        }
+        Ok(path)
+    }
+
+    /// Restores `mem` from the newest backup, returns `false` if there are no backups
+    pub fn restore_latest<M: RawMem + ?Sized>(&self, mem: &mut M) -> Result<bool>
+    where
+        M::Item: Pod,
+    {
+        match self.latest()? {
+            Some(path) => mem.restore_from_path(path).map(|_| true),
+            None => Ok(false),
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use {
+    crate::{RawMem, Result},
+    std::fmt::{self, Formatter},
+};
+
+const BITS: usize = u64::BITS as usize;
+
+/// Bitset over a memory of words, which grows on demand.
+///
+/// Bits out of allocated words are unset, so only [`set`](Self::set) grows it.
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use platform_mem::{BitMem, Global};
+///
+/// let mut free = BitMem::new(Global::new());
+/// free.set(3)?;
+/// free.set(130)?;
+/// assert!(free.get(130) && !free.get(1000));
+///
+/// assert_eq!(free.count_ones(), 2);
+/// assert_eq!(free.rank(100), 1); // set bits before 100
+/// assert_eq!(free.select(1), Some(130)); // position of the second set bit
+/// # Ok::<_, platform_mem::Error>(())
+/// ```
+pub struct BitMem<M> {
+    mem: M,
+}
+
+impl<M: RawMem<Item = u64>> BitMem<M> {
+    pub fn new(mem: M) -> Self {
+        Self { mem }
+    }
+
+    fn words(&self) -> &[u64] {
+        self.mem.allocated()
+    }
+
+    /// Count of bits in allocated words
+    pub fn capacity(&self) -> usize {
+        self.words().len() * BITS
+    }
+
+    pub fn get(&self, index: usize) -> bool {
+        self.words().get(index / BITS).is_some_and(|word| word & (1 << (index % BITS)) != 0)
+    }
+
+    /// Sets the bit, growing the memory by zeroed words to fit it
+    pub fn set(&mut self, index: usize) -> Result<()> {
+        let word = index / BITS;
+        if let Some(addition) = (word + 1).checked_sub(self.words().len()) {
+            self.mem.grow_filled(addition, 0)?;
+        }
+        self.mem.allocated_mut()[word] |= 1 << (index % BITS);
+        Ok(())
+    }
+
+    /// Unsets the bit, it never grows the memory
+    pub fn unset(&mut self, index: usize) {
+        if let Some(word) = self.mem.allocated_mut().get_mut(index / BITS) {
+            *word &= !(1 << (index % BITS));
+        }
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.words().iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// Count of set bits before `index`
+    pub fn rank(&self, index: usize) -> usize {
+        let (full, rest) = (index / BITS, index % BITS);
+        let words = self.words();
+        let ones: usize = words.iter().take(full).map(|word| word.count_ones() as usize).sum();
+        match words.get(full) {
+            Some(word) if rest != 0 => ones + (word & ((1 << rest) - 1)).count_ones() as usize,
+            _ => ones,
+        }
+    }
+
+    /// Position of the set bit with `rank`, i.e. of the `rank + 1`-th one
+    pub fn select(&self, mut rank: usize) -> Option<usize> {
+        for (i, &word) in self.words().iter().enumerate() {
+            let ones = word.count_ones() as usize;
+            if rank < ones {
+                let mut word = word;
+                for _ in 0..rank {
+                    word &= word - 1; // drop the lowest set bit
+                }
+                return Some(i * BITS + word.trailing_zeros() as usize);
+            }
+            rank -= ones;
+        }
+        None
+    }
+
+    /// Positions of set bits in ascending order
+    pub fn ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words().iter().enumerate().flat_map(|(i, &word)| {
+            let mut word = word;
+            std::iter::from_fn(move || {
+                let bit = (word != 0).then(|| word.trailing_zeros() as usize)?;
+                word &= word - 1;
+                Some(i * BITS + bit)
+            })
+        })
+    }
+
+    pub fn inner(&self) -> &M {
+        &self.mem
+    }
+
+    pub fn into_inner(self) -> M {
+        self.mem
+    }
+}
+
+impl<M: RawMem<Item = u64>> fmt::Debug for BitMem<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BitMem")
+            .field("capacity", &self.capacity())
+            .field("ones", &self.count_ones())
+            .finish()
+    }
+}
@@ -0,0 +1,111 @@
+use {
+    crate::{free_ranges::FreeRanges, RawMem, Result},
+    std::fmt::{self, Formatter},
+};
+
+/// Place of a blob in [`BlobArena`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BlobOffset(pub u64);
+
+/// Variable-length byte blobs in a single growing memory.
+///
+/// Freed blobs are reused by the next allocations of fitting size (first fit),
+/// and a free tail shrinks the memory. Free ranges are known only to this arena,
+/// so reopening a file forgets them.
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use platform_mem::{BlobArena, Global};
+///
+/// let mut arena = BlobArena::new(Global::new());
+/// let hello = arena.alloc(b"hello")?;
+/// let world = arena.alloc(b"world")?;
+/// assert_eq!(arena.get(world, 5), Some(&b"world"[..]));
+///
+/// arena.free(hello, 5);
+/// assert_eq!(arena.alloc(b"hi")?, hello); // reuses freed place
+/// assert_eq!(arena.free_bytes(), 3);
+/// # Ok::<_, platform_mem::Error>(())
+/// ```
+pub struct BlobArena<M> {
+    mem: M,
+    free: FreeRanges,
+}
+
+impl<M: RawMem<Item = u8>> BlobArena<M> {
+    /// Allocated bytes of `mem` are considered used
+    pub fn new(mem: M) -> Self {
+        Self { mem, free: FreeRanges::default() }
+    }
+
+    pub fn alloc(&mut self, blob: &[u8]) -> Result<BlobOffset> {
+        if blob.is_empty() {
+            return Ok(BlobOffset(self.mem.allocated().len() as u64));
+        }
+        let at = match self.free.take(blob.len(), false) {
+            Some(at) => {
+                self.mem.allocated_mut()[at..at + blob.len()].copy_from_slice(blob);
+                at
+            }
+            None => {
+                let at = self.mem.allocated().len();
+                self.mem.grow_from_slice(blob)?;
+                at
+            }
+        };
+        Ok(BlobOffset(at as u64))
+    }
+
+    fn range(&self, offset: BlobOffset, len: usize) -> Option<(usize, usize)> {
+        let start = usize::try_from(offset.0).ok()?;
+        let end = start.checked_add(len)?;
+        (end <= self.mem.allocated().len()).then_some((start, end))
+    }
+
+    pub fn get(&self, offset: BlobOffset, len: usize) -> Option<&[u8]> {
+        let (start, end) = self.range(offset, len)?;
+        Some(&self.mem.allocated()[start..end])
+    }
+
+    pub fn get_mut(&mut self, offset: BlobOffset, len: usize) -> Option<&mut [u8]> {
+        let (start, end) = self.range(offset, len)?;
+        Some(&mut self.mem.allocated_mut()[start..end])
+    }
+
+    /// Makes place of the blob reusable, `len` must be its length
+    ///
+    /// # Panics
+    /// Panics if the blob is out of bounds or it's already freed.
+    pub fn free(&mut self, offset: BlobOffset, len: usize) {
+        let (start, _) = self.range(offset, len).expect("blob is out of bounds");
+        if len == 0 {
+            return;
+        }
+        let (start, len) = self.free.give(start, len);
+        if start + len == self.mem.allocated().len() && self.mem.shrink(len).is_ok() {
+            self.free.remove(start);
+        }
+    }
+
+    /// Bytes of freed blobs which are not reused yet
+    pub fn free_bytes(&self) -> usize {
+        self.free.free()
+    }
+
+    pub fn inner(&self) -> &M {
+        &self.mem
+    }
+
+    pub fn into_inner(self) -> M {
+        self.mem
+    }
+}
+
+impl<M: fmt::Debug> fmt::Debug for BlobArena<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlobArena")
+            .field("mem", &self.mem)
+            .field("free_bytes", &self.free.free())
+            .finish()
+    }
+}
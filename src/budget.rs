@@ -0,0 +1,178 @@
+use {
+    crate::{Error::BudgetExceeded, RawMem, Result},
+    std::{
+        fmt::{self, Formatter},
+        mem::{self, ManuallyDrop},
+        ptr,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    },
+};
+
+/// Byte quota shared by all memories [attached](Budget::attach) to it.
+///
+/// Grows that would exceed the limit fail with [`Error::BudgetExceeded`]
+/// and leave the memory untouched, shrinks and drops give bytes back.
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use platform_mem::{Budget, Error, Global, RawMem};
+///
+/// let budget = Budget::new(1024);
+/// let mut links = budget.attach(Global::<u64>::new());
+/// let mut names = budget.attach(Global::<u8>::new());
+///
+/// links.grow_filled(100, 0)?;
+/// assert!(matches!(names.grow_filled(500, 0), Err(Error::BudgetExceeded { .. })));
+/// names.grow_filled(224, 0)?;
+/// assert_eq!(budget.available(), 0);
+///
+/// drop(links);
+/// assert_eq!(budget.used(), 224);
+/// # Ok::<_, platform_mem::Error>(())
+/// ```
+///
+/// [`Error::BudgetExceeded`]: crate::Error::BudgetExceeded
+#[derive(Clone)]
+pub struct Budget {
+    limit: usize,
+    used: Arc<AtomicUsize>,
+}
+
+impl Budget {
+    pub fn new(limit: usize) -> Self {
+        Self { limit, used: Arc::new(AtomicUsize::new(0)) }
+    }
+
+    /// Charges `mem` for its already allocated bytes, even above the limit
+    pub fn attach<M: RawMem>(&self, mem: M) -> Budgeted<M> {
+        let bytes = mem::size_of_val(mem.allocated());
+        self.used.fetch_add(bytes, Ordering::Relaxed);
+        Budgeted { mem, budget: self.clone(), bytes }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Bytes allocated by all attached memories
+    pub fn used(&self) -> usize {
+        self.used.load(Ordering::Relaxed)
+    }
+
+    pub fn available(&self) -> usize {
+        self.limit.saturating_sub(self.used())
+    }
+
+    fn reserve(&self, bytes: usize) -> Result<()> {
+        self.used
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                used.checked_add(bytes).filter(|&used| used <= self.limit)
+            })
+            .map(drop)
+            .map_err(|used| BudgetExceeded {
+                requested: bytes,
+                available: self.limit.saturating_sub(used),
+            })
+    }
+
+    fn release(&self, bytes: usize) {
+        self.used.fetch_sub(bytes, Ordering::Relaxed);
+    }
+}
+
+impl fmt::Debug for Budget {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Budget").field("limit", &self.limit).field("used", &self.used()).finish()
+    }
+}
+
+/// Memory attached to a [`Budget`]
+pub struct Budgeted<M> {
+    mem: M,
+    budget: Budget,
+    /// Bytes charged to the budget
+    bytes: usize,
+}
+
+impl<M: RawMem> Budgeted<M> {
+    pub fn budget(&self) -> &Budget {
+        &self.budget
+    }
+
+    /// Detaches the memory and gives its bytes back to the budget
+    pub fn into_inner(self) -> M {
+        let this = ManuallyDrop::new(self);
+        this.budget.release(this.bytes);
+        // SAFETY: `this` is never used or dropped after moving out its fields
+        unsafe {
+            drop(ptr::read(&this.budget));
+            ptr::read(&this.mem)
+        }
+    }
+
+    fn recharge(&mut self) {
+        let bytes = mem::size_of_val(self.mem.allocated());
+        if bytes > self.bytes {
+            self.budget.used.fetch_add(bytes - self.bytes, Ordering::Relaxed);
+        } else {
+            self.budget.release(self.bytes - bytes);
+        }
+        self.bytes = bytes;
+    }
+}
+
+forward_mem! {
+    impl<M> RawMem for Budgeted<M> {
+        unsafe fn grow(&mut self, addition, fill) {
+            // overflowing size is left to the inner memory to report
+            let requested = addition.saturating_mul(mem::size_of::<M::Item>());
+            self.budget.reserve(requested)?;
+            // given back if `fill` panics, the memory isn't grown then
+            let reserved = Reserved { budget: &self.budget, bytes: requested };
+            let result = self.mem.grow(addition, fill).map(drop);
+            mem::forget(reserved);
+
+            self.bytes += requested;
+            // the reservation is corrected by the real size, whatever happened
+            self.recharge();
+            result?;
+        }
+
+        fn shrink(&mut self, cap) {
+            let result = self.mem.shrink(cap);
+            self.recharge();
+            result
+        }
+    }
+}
+
+/// Bytes reserved in the budget until the grow returns
+struct Reserved<'a> {
+    budget: &'a Budget,
+    bytes: usize,
+}
+
+impl Drop for Reserved<'_> {
+    fn drop(&mut self) {
+        self.budget.release(self.bytes);
+    }
+}
+
+impl<M> Drop for Budgeted<M> {
+    fn drop(&mut self) {
+        self.budget.release(self.bytes);
+    }
+}
+
+impl<M: fmt::Debug> fmt::Debug for Budgeted<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Budgeted")
+            .field("mem", &self.mem)
+            .field("bytes", &self.bytes)
+            .field("budget", &self.budget)
+            .finish()
+    }
+}
@@ -0,0 +1,226 @@
+use {
+    crate::{MemoryUsage, RawMem, Result},
+    std::{
+        fmt::{self, Formatter},
+        mem::{ManuallyDrop, MaybeUninit},
+        ptr,
+    },
+};
+
+/// When [`Cached`] copies items of the slow memory into the fast one
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FillPolicy {
+    /// At construction
+    #[default]
+    Eager,
+    /// At the first grow, shrink or [`Cached::load`],
+    /// until then memory is the slow one as is
+    OnDemand,
+}
+
+/// How changes of [`Cached`] reach the slow memory
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WritePolicy {
+    /// Grows and shrinks are applied to both memories at once, changed items
+    /// are copied at the next grow, shrink or [`Cached::flush`]
+    WriteThrough,
+    /// Slow memory is updated by [`Cached::flush`] and on drop
+    #[default]
+    WriteBack,
+    /// Slow memory is never changed, changes live only in the cache
+    Discard,
+}
+
+/// Memory serving items from a `fast` memory (e.g. [`Global`]),
+/// which is filled from and written back to a `slow` one (e.g. [`FileMapped`]).
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use platform_mem::{Cached, FillPolicy, Global, RawMem, TempFile, WritePolicy};
+///
+/// let mut file = TempFile::new()?;
+/// file.grow_from_slice(&[1, 2, 3])?;
+///
+/// let mut mem = Cached::new(Global::new(), file, FillPolicy::Eager, WritePolicy::WriteBack)?;
+/// mem.allocated_mut()[0] = 10;
+/// mem.grow_from_slice(&[4])?;
+/// assert_eq!(mem.slow().allocated(), [1, 2, 3]);
+///
+/// mem.flush()?;
+/// assert_eq!(mem.slow().allocated(), [10, 2, 3, 4]);
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+///
+/// [`Global`]: crate::Global
+/// [`FileMapped`]: crate::FileMapped
+pub struct Cached<F: RawMem, S: RawMem<Item = F::Item>>
+where
+    F::Item: Clone,
+{
+    fast: F,
+    slow: S,
+    writes: WritePolicy,
+    /// Generation offset of `fast`, if it's filled
+    loaded: Option<u64>,
+    /// Items may differ from the slow memory
+    dirty: bool,
+}
+
+impl<F: RawMem, S: RawMem<Item = F::Item>> Cached<F, S>
+where
+    F::Item: Clone,
+{
+    /// `fast` is expected to be empty, its items are kept in front otherwise
+    pub fn new(fast: F, slow: S, fill: FillPolicy, writes: WritePolicy) -> Result<Self> {
+        let mut mem = Self { fast, slow, writes, loaded: None, dirty: false };
+        if fill == FillPolicy::Eager {
+            mem.load()?;
+        }
+        Ok(mem)
+    }
+
+    /// Fills the fast memory if it's not yet
+    pub fn load(&mut self) -> Result<()> {
+        if self.loaded.is_none() {
+            self.fast.grow_from_slice(self.slow.allocated())?;
+            self.loaded = Some(self.slow.generation() + 1);
+        }
+        Ok(())
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.loaded.is_some()
+    }
+
+    /// Makes the slow memory equal to the cached items, unless writes are discarded
+    pub fn flush(&mut self) -> Result<()> {
+        if self.loaded.is_none() || self.writes == WritePolicy::Discard {
+            return Ok(());
+        }
+        let (fast, slow) = (self.fast.allocated().len(), self.slow.allocated().len());
+        if slow > fast {
+            self.slow.shrink(slow - fast)?;
+        }
+        if self.dirty || slow != fast {
+            let common = slow.min(fast);
+            self.slow.allocated_mut().clone_from_slice(&self.fast.allocated()[..common]);
+            self.slow.grow_from_slice(&self.fast.allocated()[common..])?;
+        }
+        self.dirty = false;
+        Ok(())
+    }
+
+    pub fn fast(&self) -> &F {
+        &self.fast
+    }
+
+    pub fn slow(&self) -> &S {
+        &self.slow
+    }
+
+    /// Returns both memories without flushing
+    pub fn into_inner(self) -> (F, S) {
+        let mut this = ManuallyDrop::new(self);
+        this.loaded = None;
+        // SAFETY: `this` is never used or dropped after moving out its fields
+        unsafe { (ptr::read(&this.fast), ptr::read(&this.slow)) }
+    }
+}
+
+impl<F: RawMem, S: RawMem<Item = F::Item>> RawMem for Cached<F, S>
+where
+    F::Item: Clone,
+{
+    type Item = F::Item;
+
+    fn allocated(&self) -> &[Self::Item] {
+        match self.loaded {
+            Some(_) => self.fast.allocated(),
+            None => self.slow.allocated(),
+        }
+    }
+
+    fn allocated_mut(&mut self) -> &mut [Self::Item] {
+        match self.loaded {
+            Some(_) => {
+                self.dirty = true;
+                self.fast.allocated_mut()
+            }
+            None => self.slow.allocated_mut(),
+        }
+    }
+
+    unsafe fn grow(
+        &mut self,
+        addition: usize,
+        fill: impl FnOnce(usize, (&mut [Self::Item], &mut [MaybeUninit<Self::Item>])),
+    ) -> Result<&mut [Self::Item]> {
+        self.load()?;
+        if self.writes == WritePolicy::WriteThrough {
+            self.flush()?;
+        }
+        self.fast.grow(addition, fill)?;
+
+        let len = self.fast.allocated().len();
+        if self.writes == WritePolicy::WriteThrough {
+            if let Err(err) = self.slow.grow_from_slice(&self.fast.allocated()[len - addition..]) {
+                self.fast.shrink(addition)?;
+                return Err(err);
+            }
+        }
+        Ok(&mut self.fast.allocated_mut()[len - addition..])
+    }
+
+    fn shrink(&mut self, cap: usize) -> Result<()> {
+        self.load()?;
+        if self.writes == WritePolicy::WriteThrough {
+            self.flush()?;
+            self.slow.shrink(cap)?;
+        }
+        self.fast.shrink(cap)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.slow.size_hint()
+    }
+
+    fn generation(&self) -> u64 {
+        match self.loaded {
+            Some(offset) => offset + self.fast.generation(),
+            None => self.slow.generation(),
+        }
+    }
+
+    fn memory_usage(&self) -> MemoryUsage {
+        match self.loaded {
+            Some(_) => self.fast.memory_usage(),
+            None => self.slow.memory_usage(),
+        }
+    }
+}
+
+impl<F: RawMem, S: RawMem<Item = F::Item>> Drop for Cached<F, S>
+where
+    F::Item: Clone,
+{
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+impl<F, S> fmt::Debug for Cached<F, S>
+where
+    F: RawMem + fmt::Debug,
+    S: RawMem<Item = F::Item> + fmt::Debug,
+    F::Item: Clone,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cached")
+            .field("fast", &self.fast)
+            .field("slow", &self.slow)
+            .field("writes", &self.writes)
+            .field("loaded", &self.loaded.is_some())
+            .field("dirty", &self.dirty)
+            .finish()
+    }
+}
@@ -0,0 +1,145 @@
+use {
+    crate::{RawMem, Result},
+    std::{
+        fmt::{self, Formatter},
+        iter,
+        ops::{Index, IndexMut},
+        slice,
+    },
+};
+
+/// Two memories seen as their concatenation: `head` items go first, then `tail` ones.
+///
+/// Reads and writes are routed by index and grows go to `tail`, e.g. an immutable
+/// base file with a growable overlay. It's not a [`RawMem`] itself, because
+/// the items are not contiguous, use [`as_slices`] to get both parts at once.
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use platform_mem::{Chain, Global, RawMem};
+///
+/// let mut base = Global::new();
+/// base.grow_from_slice(&[1, 2, 3])?;
+///
+/// let mut chain = Chain::new(base, Global::new());
+/// chain.grow_from_slice(&[4, 5])?;
+/// chain[1] = 20;
+///
+/// assert_eq!(chain.len(), 5);
+/// assert_eq!(chain.iter().copied().collect::<Vec<_>>(), [1, 20, 3, 4, 5]);
+/// assert_eq!(chain.as_slices(), (&[1, 20, 3][..], &[4, 5][..]));
+/// # Ok::<_, platform_mem::Error>(())
+/// ```
+///
+/// [`as_slices`]: Self::as_slices
+pub struct Chain<A, B> {
+    head: A,
+    tail: B,
+}
+
+impl<A: RawMem, B: RawMem<Item = A::Item>> Chain<A, B> {
+    pub fn new(head: A, tail: B) -> Self {
+        Self { head, tail }
+    }
+
+    pub fn len(&self) -> usize {
+        self.head.allocated().len() + self.tail.allocated().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<&A::Item> {
+        let head = self.head.allocated();
+        match index.checked_sub(head.len()) {
+            Some(index) => self.tail.allocated().get(index),
+            None => head.get(index),
+        }
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut A::Item> {
+        let len = self.head.allocated().len();
+        match index.checked_sub(len) {
+            Some(index) => self.tail.allocated_mut().get_mut(index),
+            None => self.head.allocated_mut().get_mut(index),
+        }
+    }
+
+    pub fn as_slices(&self) -> (&[A::Item], &[A::Item]) {
+        (self.head.allocated(), self.tail.allocated())
+    }
+
+    pub fn as_mut_slices(&mut self) -> (&mut [A::Item], &mut [A::Item]) {
+        (self.head.allocated_mut(), self.tail.allocated_mut())
+    }
+
+    pub fn iter(&self) -> iter::Chain<slice::Iter<'_, A::Item>, slice::Iter<'_, A::Item>> {
+        self.head.allocated().iter().chain(self.tail.allocated())
+    }
+
+    /// Grows the tail by copies of `value`
+    pub fn grow_filled(&mut self, addition: usize, value: A::Item) -> Result<&mut [A::Item]>
+    where
+        A::Item: Clone,
+    {
+        self.tail.grow_filled(addition, value)
+    }
+
+    pub fn grow_from_slice(&mut self, src: &[A::Item]) -> Result<&mut [A::Item]>
+    where
+        A::Item: Clone,
+    {
+        self.tail.grow_from_slice(src)
+    }
+
+    /// Removes `cap` last items, from the head too if the tail is shorter
+    ///
+    /// # Panics
+    /// Panics if `cap` is larger than the length, before any memory is shrunk.
+    pub fn shrink(&mut self, cap: usize) -> Result<()> {
+        assert!(cap <= self.len(), "Tried to shrink to a larger capacity");
+        let tail = self.tail.allocated().len();
+        if cap <= tail {
+            return self.tail.shrink(cap);
+        }
+        self.tail.shrink(tail)?;
+        self.head.shrink(cap - tail)
+    }
+
+    pub fn head(&self) -> &A {
+        &self.head
+    }
+
+    /// Tail memory, all its `RawMem` methods are available through it
+    pub fn tail_mut(&mut self) -> &mut B {
+        &mut self.tail
+    }
+
+    pub fn into_inner(self) -> (A, B) {
+        (self.head, self.tail)
+    }
+}
+
+impl<A: RawMem, B: RawMem<Item = A::Item>> Index<usize> for Chain<A, B> {
+    type Output = A::Item;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        let len = self.len();
+        self.get(index).unwrap_or_else(|| panic!("index {index} is out of chain of {len} items"))
+    }
+}
+
+impl<A: RawMem, B: RawMem<Item = A::Item>> IndexMut<usize> for Chain<A, B> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        let len = self.len();
+        self.get_mut(index)
+            .unwrap_or_else(|| panic!("index {index} is out of chain of {len} items"))
+    }
+}
+
+impl<A: fmt::Debug, B: fmt::Debug> fmt::Debug for Chain<A, B> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Chain").field("head", &self.head).field("tail", &self.tail).finish()
+    }
+}
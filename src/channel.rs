@@ -0,0 +1,193 @@
+use {
+    crate::{signal::futex, Error::CapacityOverflow, RawMem, Result},
+    bytemuck::Pod,
+    std::{
+        fmt::{self, Formatter},
+        io,
+        marker::PhantomData,
+        mem::{self, MaybeUninit},
+        result,
+        sync::atomic::{AtomicU32, AtomicU64, Ordering},
+    },
+};
+
+// head, tail, waiters and capacity live on separate cache lines,
+// so words written by one side don't invalidate the line of the other side
+const HEAD: usize = 0;
+const TAIL: usize = 8;
+// sender-side word for blocking receivers, only its first 32 bits are used
+// as the futex word, so a wait never misses a change of the counter
+const EVENT: usize = 9;
+// receiver-side word for blocking receivers
+const WAITERS: usize = 16;
+const CAP: usize = 24;
+const HEADER: usize = 32;
+
+/// Single-producer single-consumer ring buffer inside a memory.
+///
+/// Header keeps the capacity and monotonic head/tail counters, so two channels
+/// over mappings of the same file (e.g. [`FileMapped`] in different processes)
+/// form a cross-process queue: one side only sends, the other only receives.
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use platform_mem::{Global, ShmChannel};
+///
+/// let mut chan = ShmChannel::<u32, _>::new(Global::new(), 2)?;
+/// assert_eq!(chan.try_send(1), Ok(()));
+/// assert_eq!(chan.try_send(2), Ok(()));
+/// assert_eq!(chan.try_send(3), Err(3)); // full
+///
+/// assert_eq!(chan.try_recv(), Some(1));
+/// assert_eq!(chan.try_recv(), Some(2));
+/// assert_eq!(chan.try_recv(), None);
+/// # Ok::<_, platform_mem::Error>(())
+/// ```
+///
+/// [`FileMapped`]: crate::FileMapped
+pub struct ShmChannel<T, M> {
+    mem: M,
+    cap: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Pod, M: RawMem<Item = u64>> ShmChannel<T, M> {
+    const STRIDE: usize = mem::size_of::<T>().next_multiple_of(mem::size_of::<u64>());
+
+    /// Grows `mem` to fit `cap` elements. If the header already stores
+    /// a capacity (channel was created by the other side), it's used instead of `cap`.
+    ///
+    /// Fails with [`io::ErrorKind::InvalidInput`] if the capacity is zero.
+    pub fn new(mut mem: M, cap: usize) -> Result<Self> {
+        const { assert!(mem::align_of::<T>() <= mem::align_of::<u64>()) };
+
+        if mem.allocated().len() < HEADER {
+            let addition = HEADER - mem.allocated().len();
+            unsafe { zeroed(&mut mem, addition)? };
+        }
+
+        // SAFETY: header words are accessed only atomically
+        let stored = unsafe { AtomicU64::from_ptr(mem.allocated_mut().as_mut_ptr().add(CAP)) };
+        let cap = match stored.load(Ordering::Acquire) {
+            0 => cap,
+            stored => stored as usize,
+        };
+        if cap == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "channel capacity must not be zero",
+            )
+            .into());
+        }
+        let words =
+            cap.checked_mul(Self::STRIDE / mem::size_of::<u64>()).ok_or(CapacityOverflow)?;
+        let words = words.checked_add(HEADER).ok_or(CapacityOverflow)?;
+        if let Some(addition) = words.checked_sub(mem.allocated().len()) {
+            unsafe { zeroed(&mut mem, addition)? };
+        }
+
+        let mut chan = Self { mem, cap, _marker: PhantomData };
+        chan.atomic(CAP).store(cap as u64, Ordering::Release);
+        Ok(chan)
+    }
+
+    fn atomic(&mut self, word: usize) -> &AtomicU64 {
+        // SAFETY: header words are accessed only atomically (also from other processes)
+        unsafe { AtomicU64::from_ptr(self.mem.allocated_mut().as_mut_ptr().add(word)) }
+    }
+
+    fn event(&mut self) -> &AtomicU32 {
+        // SAFETY: event word is accessed only atomically, it's aligned for `u32`
+        unsafe {
+            AtomicU32::from_ptr(self.mem.allocated_mut().as_mut_ptr().add(EVENT).cast::<u32>())
+        }
+    }
+
+    fn load(&self, word: usize) -> u64 {
+        // SAFETY: header words are accessed only atomically, this one is only read
+        unsafe { AtomicU64::from_ptr(self.mem.allocated().as_ptr().add(word).cast_mut()) }
+            .load(Ordering::Acquire)
+    }
+
+    fn slot(&mut self, index: u64) -> *mut T {
+        let offset = (index % self.cap as u64) as usize * Self::STRIDE;
+        unsafe { self.mem.allocated_mut().as_mut_ptr().add(HEADER).cast::<u8>().add(offset).cast() }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    /// Count of elements that are sent but not received yet
+    pub fn len(&self) -> usize {
+        let tail = self.load(TAIL);
+        let head = self.load(HEAD);
+        tail.wrapping_sub(head) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Sends `value` or returns it back if the channel is full
+    pub fn try_send(&mut self, value: T) -> result::Result<(), T> {
+        let tail = self.atomic(TAIL).load(Ordering::Relaxed);
+        let head = self.atomic(HEAD).load(Ordering::Acquire);
+        if tail.wrapping_sub(head) >= self.cap as u64 {
+            return Err(value);
+        }
+
+        unsafe { self.slot(tail).write_volatile(value) };
+        self.atomic(TAIL).store(tail.wrapping_add(1), Ordering::Release);
+
+        self.event().fetch_add(1, Ordering::SeqCst);
+        if self.atomic(WAITERS).load(Ordering::SeqCst) != 0 {
+            futex::wake(self.event());
+        }
+        Ok(())
+    }
+
+    /// Receives the next value, sleeping until the sender publishes it
+    /// (it works across processes through the shared header)
+    pub fn recv(&mut self) -> T {
+        loop {
+            self.atomic(WAITERS).store(1, Ordering::SeqCst);
+            let event = self.event().load(Ordering::SeqCst);
+
+            if let Some(value) = self.try_recv() {
+                self.atomic(WAITERS).store(0, Ordering::Relaxed);
+                return value;
+            }
+            futex::wait(self.event(), event);
+        }
+    }
+
+    pub fn try_recv(&mut self) -> Option<T> {
+        let head = self.atomic(HEAD).load(Ordering::Relaxed);
+        let tail = self.atomic(TAIL).load(Ordering::Acquire);
+        if head == tail {
+            return None;
+        }
+
+        let value = unsafe { self.slot(head).read_volatile() };
+        self.atomic(HEAD).store(head.wrapping_add(1), Ordering::Release);
+        Some(value)
+    }
+
+    pub fn into_inner(self) -> M {
+        self.mem
+    }
+}
+
+unsafe fn zeroed<M: RawMem<Item = u64>>(mem: &mut M, addition: usize) -> Result<()> {
+    mem.grow(addition, |inited, (_, uninit)| {
+        uninit[inited..].fill(MaybeUninit::new(0));
+    })
+    .map(drop)
+}
+
+impl<T, M: fmt::Debug> fmt::Debug for ShmChannel<T, M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShmChannel").field("mem", &self.mem).field("cap", &self.cap).finish()
+    }
+}
@@ -0,0 +1,38 @@
+use {
+    crate::utils,
+    std::{
+        io::{self, Seek, SeekFrom, Write},
+        path::Path,
+    },
+    tempfile::NamedTempFile,
+};
+
+/// Zeroed blocks are skipped instead of written, so they become holes of the file
+const BLOCK: usize = 4096;
+
+/// Writes `bytes` into a temporary file next to `path`, syncs it and renames over `path`,
+/// so readers observe either old or new file, but never a torn one
+pub(crate) fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    let mut temp = NamedTempFile::new_in(dir)?;
+    for block in bytes.chunks(BLOCK) {
+        if utils::is_zeroed(block) {
+            temp.seek(SeekFrom::Current(block.len() as i64))?;
+        } else {
+            temp.write_all(block)?;
+        }
+    }
+    temp.as_file().set_len(bytes.len() as u64)?;
+    temp.as_file().sync_all()?;
+    temp.persist(path).map_err(|err| err.error)?;
+
+    // rename itself is durable only after its directory is synced
+    #[cfg(unix)]
+    std::fs::File::open(dir)?.sync_all()?;
+
+    Ok(())
+}
@@ -0,0 +1,183 @@
+use {
+    crate::{RawMem, Result},
+    std::fmt::{self, Formatter},
+};
+
+/// Tuple of memories which are used as columns of [`Columns`]
+pub trait ColumnSet {
+    /// Tuple of items of all columns
+    type Row;
+    type Slices<'a>
+    where
+        Self: 'a;
+    type SlicesMut<'a>
+    where
+        Self: 'a;
+
+    /// Length of the first column
+    fn rows(&self) -> usize;
+    /// All columns have the same length
+    fn in_sync(&self) -> bool;
+
+    fn slices(&self) -> Self::Slices<'_>;
+    fn slices_mut(&mut self) -> Self::SlicesMut<'_>;
+
+    /// Grows every column by its item of `row`, grown columns are shrunk back on failure
+    fn push_row(&mut self, row: Self::Row) -> Result<()>;
+    /// Like [`push_row`](Self::push_row), but grows by `count` clones of `row`
+    fn grow_rows_filled(&mut self, count: usize, row: Self::Row) -> Result<()>
+    where
+        Self::Row: Clone;
+    fn shrink_rows(&mut self, count: usize) -> Result<()>;
+}
+
+fn cut<M: RawMem>(mem: &mut M, rows: usize) {
+    let len = mem.allocated().len();
+    if len > rows {
+        let _ = mem.shrink(len - rows);
+    }
+}
+
+macro_rules! impl_column_set {
+    ($($mem:ident $idx:tt)+) => {
+        impl<$($mem: RawMem),+> ColumnSet for ($($mem,)+) {
+            type Row = ($($mem::Item,)+);
+            type Slices<'a> = ($(&'a [$mem::Item],)+) where Self: 'a;
+            type SlicesMut<'a> = ($(&'a mut [$mem::Item],)+) where Self: 'a;
+
+            fn rows(&self) -> usize {
+                self.0.allocated().len()
+            }
+
+            fn in_sync(&self) -> bool {
+                let rows = self.rows();
+                true $(&& self.$idx.allocated().len() == rows)+
+            }
+
+            fn slices(&self) -> Self::Slices<'_> {
+                ($(self.$idx.allocated(),)+)
+            }
+
+            fn slices_mut(&mut self) -> Self::SlicesMut<'_> {
+                ($(self.$idx.allocated_mut(),)+)
+            }
+
+            fn push_row(&mut self, row: Self::Row) -> Result<()> {
+                let rows = self.rows();
+                let result = (|| {
+                    $(
+                        let mut item = Some(row.$idx);
+                        self.$idx.grow_with(1, || item.take().expect("only one row is grown"))?;
+                    )+
+                    Ok(())
+                })();
+                if result.is_err() {
+                    $(cut(&mut self.$idx, rows);)+
+                }
+                result
+            }
+
+            fn grow_rows_filled(&mut self, count: usize, row: Self::Row) -> Result<()>
+            where
+                Self::Row: Clone,
+            {
+                let rows = self.rows();
+                let result = (|| {
+                    $(self.$idx.grow_with(count, || row.clone().$idx)?;)+
+                    Ok(())
+                })();
+                if result.is_err() {
+                    $(cut(&mut self.$idx, rows);)+
+                }
+                result
+            }
+
+            fn shrink_rows(&mut self, count: usize) -> Result<()> {
+                $(self.$idx.shrink(count)?;)+
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_column_set! { A 0 }
+impl_column_set! { A 0 B 1 }
+impl_column_set! { A 0 B 1 C 2 }
+impl_column_set! { A 0 B 1 C 2 D 3 }
+impl_column_set! { A 0 B 1 C 2 D 3 E 4 }
+impl_column_set! { A 0 B 1 C 2 D 3 E 4 F 5 }
+
+/// Structure of arrays: a tuple of memories (of any backends) which are grown
+/// and shrunk by whole rows, so all columns always have the same length.
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use platform_mem::{Columns, Global, TempFile};
+///
+/// let mut columns = Columns::new((Global::<u64>::new(), TempFile::<u8>::new()?));
+/// columns.push_row((1, b'a'))?;
+/// columns.grow_rows_filled(2, (0, b'z'))?;
+/// columns.slices_mut().0[2] = 3;
+///
+/// assert_eq!(columns.rows(), 3);
+/// assert_eq!(columns.slices(), (&[1, 0, 3][..], &b"azz"[..]));
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+pub struct Columns<C> {
+    columns: C,
+}
+
+impl<C: ColumnSet> Columns<C> {
+    /// # Panics
+    /// Panics if allocated columns have different lengths.
+    pub fn new(columns: C) -> Self {
+        assert!(columns.in_sync(), "columns have different lengths");
+        Self { columns }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.columns.rows()
+    }
+
+    pub fn slices(&self) -> C::Slices<'_> {
+        self.columns.slices()
+    }
+
+    pub fn slices_mut(&mut self) -> C::SlicesMut<'_> {
+        self.columns.slices_mut()
+    }
+
+    pub fn push_row(&mut self, row: C::Row) -> Result<()> {
+        self.columns.push_row(row)
+    }
+
+    pub fn grow_rows_filled(&mut self, count: usize, row: C::Row) -> Result<()>
+    where
+        C::Row: Clone,
+    {
+        self.columns.grow_rows_filled(count, row)
+    }
+
+    /// Removes last `count` rows
+    ///
+    /// # Panics
+    /// Panics if there are fewer rows.
+    pub fn shrink_rows(&mut self, count: usize) -> Result<()> {
+        assert!(count <= self.rows(), "Tried to shrink to a larger capacity");
+        self.columns.shrink_rows(count)
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.columns
+    }
+
+    pub fn into_inner(self) -> C {
+        self.columns
+    }
+}
+
+impl<C: fmt::Debug> fmt::Debug for Columns<C> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Columns").field(&self.columns).finish()
+    }
+}
@@ -0,0 +1,209 @@
+use {
+    crate::{RawMem, Result},
+    std::{
+        fmt::{self, Formatter},
+        sync::Arc,
+    },
+};
+
+type Page<T> = Arc<[T]>;
+
+/// Memory with MVCC-like read snapshots in pages, layered over any [`RawMem`].
+///
+/// Live items stay in the inner memory, [`snapshot`] copies to RAM only pages
+/// which are changed since the previous one and shares the rest with it, so
+/// each snapshot stays unchanged forever and writes never copy anything.
+/// It allows long-running readers (e.g. backups) next to ongoing mutation.
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use platform_mem::{CowMem, Global};
+///
+/// let mut mem = CowMem::with_page_len(Global::new(), 4);
+/// mem.extend_from_slice(&[1, 2, 3, 4, 5, 6])?;
+///
+/// let snapshot = mem.snapshot();
+/// mem.set(0, 10);
+/// mem.push(7)?;
+///
+/// assert_eq!(snapshot.iter().copied().collect::<Vec<_>>(), [1, 2, 3, 4, 5, 6]);
+/// assert_eq!(mem.allocated(), [10, 2, 3, 4, 5, 6, 7]);
+/// # Ok::<_, platform_mem::Error>(())
+/// ```
+///
+/// [`snapshot`]: Self::snapshot
+pub struct CowMem<M: RawMem> {
+    mem: M,
+    /// Copies of pages which are not changed since they are taken by a snapshot
+    frozen: Vec<Option<Page<M::Item>>>,
+    page_len: usize,
+    version: u64,
+}
+
+/// Immutable version of [`CowMem`]
+pub struct CowSnapshot<T> {
+    pages: Vec<Page<T>>,
+    page_len: usize,
+    len: usize,
+    version: u64,
+}
+
+// usual page size for `u64` items
+const PAGE_LEN: usize = 512;
+
+impl<M: RawMem> CowMem<M>
+where
+    M::Item: Clone,
+{
+    pub fn new(mem: M) -> Self {
+        Self::with_page_len(mem, PAGE_LEN)
+    }
+
+    /// Items which are already allocated in `mem` become the first version
+    ///
+    /// # Panics
+    /// Panics if `page_len` is zero.
+    pub fn with_page_len(mem: M, page_len: usize) -> Self {
+        assert!(page_len > 0, "page must contain at least one element");
+        let frozen = vec![None; mem.allocated().len().div_ceil(page_len)];
+        Self { mem, frozen, page_len, version: 0 }
+    }
+
+    pub fn allocated(&self) -> &[M::Item] {
+        self.mem.allocated()
+    }
+
+    pub fn len(&self) -> usize {
+        self.mem.allocated().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Incremented by every mutation
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn get(&self, index: usize) -> Option<&M::Item> {
+        self.mem.allocated().get(index)
+    }
+
+    /// Pages of `start..end` are changed, so snapshots must not reuse their copies
+    fn touch(&mut self, start: usize, end: usize) {
+        let pages = end.div_ceil(self.page_len);
+        self.frozen.resize(pages, None);
+        self.frozen[start / self.page_len..].fill(None);
+        self.version += 1;
+    }
+
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn set(&mut self, index: usize, value: M::Item) {
+        let len = self.len();
+        match self.mem.allocated_mut().get_mut(index) {
+            Some(place) => *place = value,
+            None => panic!("index {index} is out of bounds of length {len}"),
+        }
+        let page = index / self.page_len;
+        self.frozen[page] = None;
+        self.version += 1;
+    }
+
+    pub fn push(&mut self, value: M::Item) -> Result<()> {
+        self.extend_from_slice(&[value])
+    }
+
+    pub fn extend_from_slice(&mut self, src: &[M::Item]) -> Result<()> {
+        if src.is_empty() {
+            return Ok(());
+        }
+        let len = self.len();
+        self.mem.grow_from_slice(src)?;
+        self.touch(len, len + src.len());
+        Ok(())
+    }
+
+    pub fn truncate(&mut self, len: usize) -> Result<()> {
+        let current = self.len();
+        if len >= current {
+            return Ok(());
+        }
+        self.mem.shrink(current - len)?;
+        self.touch(len, len);
+        Ok(())
+    }
+
+    /// Pins the current version, copying only pages changed since the previous snapshot
+    pub fn snapshot(&mut self) -> CowSnapshot<M::Item> {
+        let (items, page_len) = (self.mem.allocated(), self.page_len);
+        let pages = items
+            .chunks(page_len)
+            .zip(&mut self.frozen)
+            .map(|(chunk, frozen)| frozen.get_or_insert_with(|| Arc::from(chunk)).clone())
+            .collect();
+        CowSnapshot { pages, page_len, len: items.len(), version: self.version }
+    }
+
+    /// Count of pages whose copies are shared with snapshots
+    pub fn shared_pages(&self) -> usize {
+        self.frozen.iter().flatten().filter(|page| Arc::strong_count(page) > 1).count()
+    }
+
+    pub fn into_inner(self) -> M {
+        self.mem
+    }
+}
+
+impl<T> CowSnapshot<T> {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.pages.get(index / self.page_len)?.get(index % self.page_len)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.pages.iter().flat_map(|page| page.iter())
+    }
+}
+
+impl<T> Clone for CowSnapshot<T> {
+    fn clone(&self) -> Self {
+        Self {
+            pages: self.pages.clone(),
+            page_len: self.page_len,
+            len: self.len,
+            version: self.version,
+        }
+    }
+}
+
+impl<M: RawMem + fmt::Debug> fmt::Debug for CowMem<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CowMem")
+            .field("mem", &self.mem)
+            .field("page_len", &self.page_len)
+            .field("version", &self.version)
+            .finish()
+    }
+}
+
+impl<T> fmt::Debug for CowSnapshot<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CowSnapshot")
+            .field("len", &self.len)
+            .field("version", &self.version)
+            .finish()
+    }
+}
@@ -0,0 +1,111 @@
+use {
+    crate::RawMem,
+    std::{
+        fmt::{self, Formatter},
+        io::{self, BufRead, Read, Seek, SeekFrom},
+    },
+};
+
+/// Reads allocated bytes of memory like [`io::Cursor`] reads a slice.
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use {
+///     platform_mem::{MemCursor, RawMem, TempFile},
+///     std::io::{BufRead, Seek, SeekFrom},
+/// };
+///
+/// let mut mem = TempFile::<u8>::new()?;
+/// mem.grow_from_slice(b"first\nsecond\n")?;
+///
+/// let mut cursor = MemCursor::new(&mem);
+/// cursor.seek(SeekFrom::Start(6))?;
+/// let lines: Vec<_> = cursor.lines().collect::<Result<_, _>>()?;
+/// assert_eq!(lines, ["second"]);
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+pub struct MemCursor<'a, M: RawMem<Item = u8> + ?Sized> {
+    mem: &'a M,
+    pos: u64,
+}
+
+impl<'a, M: RawMem<Item = u8> + ?Sized> MemCursor<'a, M> {
+    pub fn new(mem: &'a M) -> Self {
+        Self { mem, pos: 0 }
+    }
+
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+
+    pub fn set_position(&mut self, pos: u64) {
+        self.pos = pos;
+    }
+
+    /// Bytes from the position to the end, empty if the position is beyond it
+    pub fn remaining(&self) -> &'a [u8] {
+        let bytes = self.mem.allocated();
+        let start = usize::try_from(self.pos).map_or(bytes.len(), |pos| pos.min(bytes.len()));
+        &bytes[start..]
+    }
+}
+
+impl<M: RawMem<Item = u8> + ?Sized> Read for MemCursor<'_, M> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.remaining().read(buf)?;
+        self.pos += read as u64;
+        Ok(read)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.remaining().read_exact(buf)?;
+        self.pos += buf.len() as u64;
+        Ok(())
+    }
+}
+
+impl<M: RawMem<Item = u8> + ?Sized> BufRead for MemCursor<'_, M> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        Ok(self.remaining())
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos += amt as u64;
+    }
+}
+
+impl<M: RawMem<Item = u8> + ?Sized> Seek for MemCursor<'_, M> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(pos) => {
+                self.pos = pos;
+                return Ok(pos);
+            }
+            SeekFrom::End(offset) => (self.mem.allocated().len() as u64, offset),
+            SeekFrom::Current(offset) => (self.pos, offset),
+        };
+        match base.checked_add_signed(offset) {
+            Some(pos) => {
+                self.pos = pos;
+                Ok(pos)
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )),
+        }
+    }
+
+    fn stream_position(&mut self) -> io::Result<u64> {
+        Ok(self.pos)
+    }
+}
+
+impl<M: RawMem<Item = u8> + ?Sized> fmt::Debug for MemCursor<'_, M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemCursor")
+            .field("pos", &self.pos)
+            .field("len", &self.mem.allocated().len())
+            .finish()
+    }
+}
@@ -0,0 +1,31 @@
+use std::ops::Range;
+
+// equal blocks are skipped by one slice comparison (`memcmp` for primitives)
+const BLOCK: usize = 64;
+
+/// Ranges of indexes where `a` and `b` differ, adjacent differences are merged.
+/// Elements which exist only in the longer slice are one more range at the end.
+pub fn diff<T: PartialEq>(a: &[T], b: &[T]) -> Vec<Range<usize>> {
+    let common = a.len().min(b.len());
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    let mut push = |i: usize| match ranges.last_mut() {
+        Some(last) if last.end == i => last.end += 1,
+        _ => ranges.push(i..i + 1),
+    };
+
+    for start in (0..common).step_by(BLOCK) {
+        let end = (start + BLOCK).min(common);
+        if a[start..end] != b[start..end] {
+            (start..end).filter(|&i| a[i] != b[i]).for_each(&mut push);
+        }
+    }
+
+    let longest = a.len().max(b.len());
+    if common < longest {
+        match ranges.last_mut() {
+            Some(last) if last.end == common => last.end = longest,
+            _ => ranges.push(common..longest),
+        }
+    }
+    ranges
+}
@@ -0,0 +1,97 @@
+use {
+    crate::{RawMem, Reader, Result, Shared, Writer},
+    std::{
+        fmt::{self, Formatter},
+        mem,
+        sync::RwLockReadGuard,
+    },
+};
+
+/// Front memory which is published to readers and back memory which is built by
+/// the writer. [`swap`] exchanges them while no reader holds the front, so readers
+/// always see a complete buffer, either the previous version or the next one.
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use platform_mem::{DoubleBuffer, Global, RawMem};
+///
+/// let mut buffer = DoubleBuffer::new(Global::new(), Global::new());
+/// let reader = buffer.reader();
+///
+/// buffer.write_back().grow_from_slice(b"next")?;
+/// assert_eq!(&*reader.read(), b""); // not published yet
+///
+/// buffer.swap();
+/// assert_eq!(&*reader.read(), b"next");
+///
+/// buffer.sync_back()?; // continue from the published version
+/// buffer.write_back().grow_from_slice(b" one")?;
+/// buffer.swap();
+/// assert_eq!(&*reader.read(), b"next one");
+/// # Ok::<_, platform_mem::Error>(())
+/// ```
+///
+/// [`swap`]: Self::swap
+pub struct DoubleBuffer<M> {
+    front: Writer<M>,
+    back: M,
+}
+
+impl<M: RawMem> DoubleBuffer<M> {
+    pub fn new(front: M, back: M) -> Self {
+        match Shared::new(front).split() {
+            Ok((front, _)) => Self { front, back },
+            Err(_) => unreachable!("new shared memory has a single handle"),
+        }
+    }
+
+    /// Cloneable handle to read the front memory from other threads
+    pub fn reader(&self) -> Reader<M> {
+        self.front.reader()
+    }
+
+    pub fn front(&self) -> RwLockReadGuard<'_, M> {
+        self.front.read()
+    }
+
+    pub fn back(&self) -> &M {
+        &self.back
+    }
+
+    /// Back memory to build the next version, readers don't see it until [`swap`](Self::swap)
+    pub fn write_back(&mut self) -> &mut M {
+        &mut self.back
+    }
+
+    /// Publishes the back memory, it waits for readers of the front one,
+    /// which becomes the back memory
+    pub fn swap(&mut self) {
+        mem::swap(&mut *self.front.write(), &mut self.back);
+    }
+
+    /// Makes the back memory equal to the front one, e.g. to change only a part
+    /// of the published version
+    pub fn sync_back(&mut self) -> Result<()>
+    where
+        M::Item: Clone,
+    {
+        let front = self.front.read();
+        let (front, len) = (front.allocated(), self.back.allocated().len());
+        if len > front.len() {
+            self.back.shrink(len - front.len())?;
+        }
+
+        let (common, tail) = front.split_at(self.back.allocated().len());
+        self.back.allocated_mut().clone_from_slice(common);
+        self.back.grow_from_slice(tail).map(drop)
+    }
+}
+
+impl<M: fmt::Debug> fmt::Debug for DoubleBuffer<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DoubleBuffer")
+            .field("front", &self.front)
+            .field("back", &self.back)
+            .finish()
+    }
+}
@@ -0,0 +1,201 @@
+use {
+    crate::{RawMem, Result},
+    std::{
+        fmt::{self, Formatter},
+        ops::Deref,
+        sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError},
+    },
+};
+
+struct Version<M> {
+    mem: M,
+    epoch: u64,
+}
+
+struct Slot<M> {
+    /// Empty while the only version is updated in place
+    current: Mutex<Option<Arc<Version<M>>>>,
+    restored: Condvar,
+}
+
+impl<M> Slot<M> {
+    /// Locks the current version, waiting for an in-place update to finish
+    fn lock(&self) -> MutexGuard<'_, Option<Arc<Version<M>>>> {
+        let current = self.current.lock().unwrap_or_else(PoisonError::into_inner);
+        self.restored
+            .wait_while(current, |current| current.is_none())
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn pin(&self) -> Arc<Version<M>> {
+        match &*self.lock() {
+            Some(version) => Arc::clone(version),
+            None => unreachable!("slot is locked only when it has a version"),
+        }
+    }
+
+    fn publish(&self, version: Arc<Version<M>>) {
+        *self.current.lock().unwrap_or_else(PoisonError::into_inner) = Some(version);
+        self.restored.notify_all();
+    }
+}
+
+/// Puts the version back into the slot, even if the update panics
+struct Restore<'a, M> {
+    slot: &'a Slot<M>,
+    version: Option<Version<M>>,
+}
+
+impl<M> Drop for Restore<'_, M> {
+    fn drop(&mut self) {
+        if let Some(version) = self.version.take() {
+            self.slot.publish(Arc::new(version));
+        }
+    }
+}
+
+/// RCU-like memory: readers pin a version and keep reading it until they finish.
+///
+/// Every successful [`update`] installs a new epoch. If nobody pins the current version
+/// it is updated in place (new pins wait for it), otherwise its contents are copied
+/// into a fresh memory made by `fork`, so pinned readers never see their slice
+/// invalidated. Neither the copy nor the update itself blocks pins of the current version.
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use platform_mem::{EpochMem, Global, RawMem};
+///
+/// let mut mem = EpochMem::new(Global::new(), Global::new);
+/// mem.update(|mem| mem.grow_filled(2, 1).map(drop))?;
+///
+/// let pinned = mem.pin();
+/// mem.update(|mem| mem.grow_filled(1_000, 2).map(drop))?;
+///
+/// assert_eq!(*pinned, [1, 1]); // still old version
+/// assert_eq!(mem.pin().len(), 1_002);
+/// # Ok::<_, platform_mem::Error>(())
+/// ```
+///
+/// [`update`]: Self::update
+pub struct EpochMem<M, F> {
+    slot: Arc<Slot<M>>,
+    fork: F,
+}
+
+impl<M: RawMem, F: FnMut() -> M> EpochMem<M, F> {
+    pub fn new(mem: M, fork: F) -> Self {
+        let version = Arc::new(Version { mem, epoch: 0 });
+        let slot = Slot { current: Mutex::new(Some(version)), restored: Condvar::new() };
+        Self { slot: Arc::new(slot), fork }
+    }
+
+    pub fn pin(&self) -> EpochGuard<M> {
+        EpochGuard { version: self.slot.pin() }
+    }
+
+    /// Handle which can be sent to other threads to pin versions from there
+    pub fn reader(&self) -> EpochReader<M> {
+        EpochReader { slot: Arc::clone(&self.slot) }
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.slot.pin().epoch
+    }
+
+    /// Applies `f` to the new version of memory and publishes it.
+    /// If `f` fails on a fork of a pinned version, the fork is discarded and the epoch
+    /// stays the same. A version which nobody pins is changed in place, so its epoch
+    /// is bumped even if `f` fails or panics, because it may be changed partially.
+    pub fn update<R>(&mut self, f: impl FnOnce(&mut M) -> Result<R>) -> Result<R>
+    where
+        M::Item: Clone,
+    {
+        let mut current = self.slot.lock();
+        let Some(version) = current.take() else {
+            unreachable!("slot is locked only when it has a version")
+        };
+        let pinned = match Arc::try_unwrap(version) {
+            // nobody pins it, and new pins wait until it's restored
+            Ok(version) => {
+                drop(current);
+                let mut restore = Restore { slot: &self.slot, version: None };
+                let version = restore.version.insert(version);
+                version.epoch += 1;
+                return f(&mut version.mem);
+            }
+            Err(pinned) => {
+                *current = Some(Arc::clone(&pinned));
+                pinned
+            }
+        };
+        drop(current);
+
+        let mut mem = (self.fork)();
+        mem.grow_from_slice(pinned.mem.allocated())?;
+        let result = f(&mut mem)?;
+        // only this method replaces the version, so `pinned` is still the current one
+        self.slot.publish(Arc::new(Version { mem, epoch: pinned.epoch + 1 }));
+        Ok(result)
+    }
+}
+
+/// Cloneable handle to pin versions of [`EpochMem`]
+pub struct EpochReader<M> {
+    slot: Arc<Slot<M>>,
+}
+
+impl<M> EpochReader<M> {
+    pub fn pin(&self) -> EpochGuard<M> {
+        EpochGuard { version: self.slot.pin() }
+    }
+}
+
+impl<M> Clone for EpochReader<M> {
+    fn clone(&self) -> Self {
+        Self { slot: Arc::clone(&self.slot) }
+    }
+}
+
+/// Pinned version of [`EpochMem`], its slice stays valid until the guard is dropped
+pub struct EpochGuard<M> {
+    version: Arc<Version<M>>,
+}
+
+impl<M> EpochGuard<M> {
+    pub fn epoch(&self) -> u64 {
+        self.version.epoch
+    }
+}
+
+impl<M: RawMem> Deref for EpochGuard<M> {
+    type Target = [M::Item];
+
+    fn deref(&self) -> &Self::Target {
+        self.version.mem.allocated()
+    }
+}
+
+impl<M: fmt::Debug, F> fmt::Debug for EpochMem<M, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let current = self.slot.pin();
+        f.debug_struct("EpochMem")
+            .field("epoch", &current.epoch)
+            .field("mem", &current.mem)
+            .finish()
+    }
+}
+
+impl<M> fmt::Debug for EpochReader<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EpochReader").finish_non_exhaustive()
+    }
+}
+
+impl<M: fmt::Debug> fmt::Debug for EpochGuard<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EpochGuard")
+            .field("epoch", &self.version.epoch)
+            .field("mem", &self.version.mem)
+            .finish()
+    }
+}
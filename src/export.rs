@@ -0,0 +1,79 @@
+use {
+    crate::utils,
+    bytemuck::Pod,
+    std::{io, mem},
+};
+
+const MAGIC: [u8; 8] = *b"PLMEMEXP";
+const VERSION: u16 = 1;
+const HEADER: usize = 32;
+
+const LITTLE: u8 = 0;
+const BIG: u8 = 1;
+const NATIVE: u8 = if cfg!(target_endian = "little") { LITTLE } else { BIG };
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Header fields are little-endian, items are stored in the endianness from the header
+/// (layout of `T` is unknown, so they can't be converted and exports of another
+/// endianness are rejected):
+///
+/// | magic | version | endian | reserved | item size | count | checksum |
+/// |-------|---------|--------|----------|-----------|-------|----------|
+/// | 8     | 2       | 1      | 1        | 4         | 8     | 8        |
+pub(crate) fn encode<T: Pod>(items: &[T]) -> io::Result<Vec<u8>> {
+    let payload: &[u8] = bytemuck::cast_slice(items);
+    let size = u32::try_from(mem::size_of::<T>())
+        .map_err(|_| invalid(format!("item size {} is too large", mem::size_of::<T>())))?;
+
+    let mut bytes = Vec::with_capacity(HEADER + payload.len());
+    bytes.extend_from_slice(&MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.extend_from_slice(&[NATIVE, 0]);
+    bytes.extend_from_slice(&size.to_le_bytes());
+    bytes.extend_from_slice(&(items.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(&utils::checksum(payload).to_le_bytes());
+    bytes.extend_from_slice(payload);
+    Ok(bytes)
+}
+
+pub(crate) fn decode<T: Pod>(bytes: &[u8]) -> io::Result<Vec<T>> {
+    let (header, payload) = bytes
+        .split_first_chunk::<HEADER>()
+        .ok_or_else(|| invalid(format!("export is shorter than its {HEADER} bytes header")))?;
+    let u64_at = |at: usize| u64::from_le_bytes(header[at..at + 8].try_into().unwrap_or_default());
+
+    if header[..8] != MAGIC {
+        return Err(invalid("not an export file".into()));
+    }
+    let version = u16::from_le_bytes([header[8], header[9]]);
+    if version != VERSION {
+        return Err(invalid(format!("unsupported export version {version}")));
+    }
+    if header[10] != NATIVE {
+        return Err(invalid("export was written with another endianness".into()));
+    }
+    let size = u32::from_le_bytes([header[12], header[13], header[14], header[15]]);
+    if size as usize != mem::size_of::<T>() {
+        return Err(invalid(format!(
+            "export item size is {size} bytes, but expected {}",
+            mem::size_of::<T>()
+        )));
+    }
+    let count = u64_at(16);
+    if count.checked_mul(size as u64) != Some(payload.len() as u64) {
+        return Err(invalid(format!("export must contain {count} items of {size} bytes")));
+    }
+    if utils::checksum(payload) != u64_at(24) {
+        return Err(invalid("export checksum mismatch".into()));
+    }
+
+    if size == 0 {
+        let count = usize::try_from(count)
+            .map_err(|_| invalid(format!("export has {count} items, it's too many")))?;
+        return Ok(vec![T::zeroed(); count]);
+    }
+    Ok(bytemuck::pod_collect_to_vec(payload))
+}
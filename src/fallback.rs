@@ -0,0 +1,162 @@
+use {
+    crate::{MemoryUsage, RawMem, Result},
+    std::{
+        fmt::{self, Formatter},
+        mem::MaybeUninit,
+    },
+};
+
+/// Memory that grows in `primary` until it fails to allocate, then moves
+/// the items into `secondary` and stays there, so running out of memory
+/// (e.g. of [`Global`]) turns into slowdown (e.g. of [`TempFile`]) instead of failure.
+///
+/// Items are moved by cloning them into `secondary` and shrinking `primary`,
+/// `secondary` is expected to be empty until then.
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use platform_mem::{Fallback, Global, Limited, RawMem, TempFile};
+///
+/// let mut mem = Fallback::new(Limited::new(Global::<u64>::new(), 100), TempFile::new()?);
+/// mem.grow_filled(100, 1)?;
+/// assert!(!mem.is_fallen_back());
+///
+/// mem.grow_filled(10, 2)?;
+/// assert!(mem.is_fallen_back());
+/// assert_eq!(mem.allocated().len(), 110);
+/// assert_eq!(mem.secondary().allocated()[99..101], [1, 2]);
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+///
+/// [`Global`]: crate::Global
+/// [`TempFile`]: crate::TempFile
+pub struct Fallback<A, B> {
+    primary: A,
+    secondary: B,
+    /// Generation offset of `secondary`, if it's active
+    fallen: Option<u64>,
+}
+
+impl<A: RawMem, B: RawMem<Item = A::Item>> Fallback<A, B> {
+    pub fn new(primary: A, secondary: B) -> Self {
+        Self { primary, secondary, fallen: None }
+    }
+
+    /// Whether items were moved into the secondary memory
+    pub fn is_fallen_back(&self) -> bool {
+        self.fallen.is_some()
+    }
+
+    pub fn primary(&self) -> &A {
+        &self.primary
+    }
+
+    pub fn secondary(&self) -> &B {
+        &self.secondary
+    }
+
+    pub fn into_inner(self) -> (A, B) {
+        (self.primary, self.secondary)
+    }
+
+    fn fall_back(&mut self) -> Result<()>
+    where
+        A::Item: Clone,
+    {
+        self.secondary.grow_from_slice(self.primary.allocated())?;
+        // `primary` may be released lazily, its items are already cloned
+        let _ = self.primary.shrink(self.primary.allocated().len());
+        self.fallen = Some(self.primary.generation() + 1);
+        Ok(())
+    }
+}
+
+impl<A, B> RawMem for Fallback<A, B>
+where
+    A: RawMem,
+    A::Item: Clone,
+    B: RawMem<Item = A::Item>,
+{
+    type Item = A::Item;
+
+    fn allocated(&self) -> &[Self::Item] {
+        match self.fallen {
+            Some(_) => self.secondary.allocated(),
+            None => self.primary.allocated(),
+        }
+    }
+
+    fn allocated_mut(&mut self) -> &mut [Self::Item] {
+        match self.fallen {
+            Some(_) => self.secondary.allocated_mut(),
+            None => self.primary.allocated_mut(),
+        }
+    }
+
+    unsafe fn grow(
+        &mut self,
+        addition: usize,
+        fill: impl FnOnce(usize, (&mut [Self::Item], &mut [MaybeUninit<Self::Item>])),
+    ) -> Result<&mut [Self::Item]> {
+        if self.fallen.is_some() {
+            return self.secondary.grow(addition, fill);
+        }
+
+        // `fill` is kept to retry only if `primary` failed before calling it
+        let mut fill = Some(fill);
+        let result = self.primary.grow(addition, |inited, parts| {
+            if let Some(fill) = fill.take() {
+                fill(inited, parts)
+            }
+        });
+        match (result, fill) {
+            (Err(err), Some(fill)) if err.is_alloc_failure() => {
+                self.fall_back()?;
+                self.secondary.grow(addition, fill)
+            }
+            (result, _) => {
+                result?;
+                let len = self.primary.allocated().len();
+                Ok(&mut self.primary.allocated_mut()[len - addition..])
+            }
+        }
+    }
+
+    fn shrink(&mut self, cap: usize) -> Result<()> {
+        match self.fallen {
+            Some(_) => self.secondary.shrink(cap),
+            None => self.primary.shrink(cap),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        match self.fallen {
+            Some(_) => self.secondary.size_hint(),
+            None => self.primary.size_hint(),
+        }
+    }
+
+    fn generation(&self) -> u64 {
+        match self.fallen {
+            Some(offset) => offset + self.secondary.generation(),
+            None => self.primary.generation(),
+        }
+    }
+
+    fn memory_usage(&self) -> MemoryUsage {
+        match self.fallen {
+            Some(_) => self.secondary.memory_usage(),
+            None => self.primary.memory_usage(),
+        }
+    }
+}
+
+impl<A: fmt::Debug, B: fmt::Debug> fmt::Debug for Fallback<A, B> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Fallback")
+            .field("primary", &self.primary)
+            .field("secondary", &self.secondary)
+            .field("fallen_back", &self.fallen.is_some())
+            .finish()
+    }
+}
@@ -0,0 +1,179 @@
+use {
+    crate::{Error, MemoryUsage, RawMem, Result},
+    std::{
+        collections::BTreeSet,
+        fmt::{self, Formatter},
+        io,
+        mem::MaybeUninit,
+    },
+};
+
+/// Operation which failure is injected by [`FaultyMem`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    Grow { addition: usize },
+    Shrink { cap: usize },
+}
+
+type MakeError = Box<dyn Fn(Fault) -> Error + Send + Sync>;
+
+/// Memory that fails chosen grows and shrinks to test error paths.
+///
+/// Calls are counted from zero separately for grows and shrinks, failed calls are
+/// counted too and leave the inner memory untouched. By default the error is
+/// an [`Error::System`] of kind [`io::ErrorKind::Other`], see [`with_error`].
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use platform_mem::{Error, Fault, FaultyMem, Global, RawMem};
+///
+/// let mut mem = FaultyMem::new(Global::<u8>::new())
+///     .fail_grow_at(1)
+///     .with_error(|fault| match fault {
+///         Fault::Grow { .. } => Error::CapacityOverflow,
+///         Fault::Shrink { .. } => Error::OverGrow { to_grow: 0, available: 0 },
+///     });
+///
+/// mem.grow_filled(10, 0)?;
+/// assert!(matches!(mem.grow_filled(10, 0), Err(Error::CapacityOverflow)));
+/// mem.grow_filled(10, 0)?;
+/// assert_eq!(mem.allocated().len(), 20);
+/// # Ok::<_, platform_mem::Error>(())
+/// ```
+///
+/// [`with_error`]: Self::with_error
+pub struct FaultyMem<M> {
+    mem: M,
+    grows: u64,
+    shrinks: u64,
+    fail_grows: BTreeSet<u64>,
+    fail_shrinks: BTreeSet<u64>,
+    random: Option<(u64, f64)>,
+    make_error: MakeError,
+}
+
+impl<M: RawMem> FaultyMem<M> {
+    pub fn new(mem: M) -> Self {
+        Self {
+            mem,
+            grows: 0,
+            shrinks: 0,
+            fail_grows: BTreeSet::new(),
+            fail_shrinks: BTreeSet::new(),
+            random: None,
+            make_error: Box::new(|_| io::Error::other("injected fault").into()),
+        }
+    }
+
+    /// Fails the `n`th grow (counting from zero)
+    pub fn fail_grow_at(mut self, n: u64) -> Self {
+        self.fail_grows.insert(n);
+        self
+    }
+
+    /// Fails the `n`th shrink (counting from zero)
+    pub fn fail_shrink_at(mut self, n: u64) -> Self {
+        self.fail_shrinks.insert(n);
+        self
+    }
+
+    /// Fails every call with `probability`, the sequence is the same for the same `seed`
+    pub fn fail_randomly(mut self, seed: u64, probability: f64) -> Self {
+        // xorshift gets stuck at zero
+        self.random = Some((seed | 1, probability.clamp(0.0, 1.0)));
+        self
+    }
+
+    pub fn with_error(mut self, make: impl Fn(Fault) -> Error + Send + Sync + 'static) -> Self {
+        self.make_error = Box::new(make);
+        self
+    }
+
+    /// Count of grows called so far, including failed ones
+    pub fn grows(&self) -> u64 {
+        self.grows
+    }
+
+    /// Count of shrinks called so far, including failed ones
+    pub fn shrinks(&self) -> u64 {
+        self.shrinks
+    }
+
+    pub fn into_inner(self) -> M {
+        self.mem
+    }
+
+    fn roll(&mut self) -> bool {
+        let Some((state, probability)) = &mut self.random else {
+            return false;
+        };
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        let sample = (*state >> 11) as f64 / (1u64 << 53) as f64;
+        sample < *probability
+    }
+
+    fn inject(&mut self, fault: Fault) -> Result<()> {
+        let (count, planned) = match fault {
+            Fault::Grow { .. } => (&mut self.grows, &self.fail_grows),
+            Fault::Shrink { .. } => (&mut self.shrinks, &self.fail_shrinks),
+        };
+        let planned = planned.contains(count);
+        *count += 1;
+
+        if self.roll() || planned {
+            Err((self.make_error)(fault))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<M: RawMem> RawMem for FaultyMem<M> {
+    type Item = M::Item;
+
+    fn allocated(&self) -> &[Self::Item] {
+        self.mem.allocated()
+    }
+
+    fn allocated_mut(&mut self) -> &mut [Self::Item] {
+        self.mem.allocated_mut()
+    }
+
+    unsafe fn grow(
+        &mut self,
+        addition: usize,
+        fill: impl FnOnce(usize, (&mut [Self::Item], &mut [MaybeUninit<Self::Item>])),
+    ) -> Result<&mut [Self::Item]> {
+        self.inject(Fault::Grow { addition })?;
+        self.mem.grow(addition, fill)
+    }
+
+    fn shrink(&mut self, cap: usize) -> Result<()> {
+        self.inject(Fault::Shrink { cap })?;
+        self.mem.shrink(cap)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.mem.size_hint()
+    }
+
+    fn generation(&self) -> u64 {
+        self.mem.generation()
+    }
+
+    fn memory_usage(&self) -> MemoryUsage {
+        self.mem.memory_usage()
+    }
+}
+
+impl<M: fmt::Debug> fmt::Debug for FaultyMem<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FaultyMem")
+            .field("mem", &self.mem)
+            .field("grows", &self.grows)
+            .field("shrinks", &self.shrinks)
+            .finish_non_exhaustive()
+    }
+}
@@ -1,106 +1,681 @@
 use {
-    crate::{raw_place::RawPlace, utils, Error::CapacityOverflow, RawMem, Result},
+    crate::{
+        mapped_reader::{Coordination, MappedReader},
+        observer::Observer,
+        raw_place::RawPlace,
+        registry::Tracker,
+        trace,
+        unmap_pool::{self, FileId},
+        utils, watchdog,
+        Error::{self, CapacityOverflow},
+        MemObserver, MemoryUsage, RawMem, Reservation, Result, Signal, StableMem,
+    },
     memmap2::{MmapMut, MmapOptions},
     std::{
         alloc::Layout,
+        any,
         fmt::{self, Formatter},
         fs::File,
         io,
-        mem::{self, MaybeUninit},
-        path::Path,
+        marker::PhantomData,
+        mem::{self, ManuallyDrop, MaybeUninit},
+        ops::{Range, RangeBounds},
+        path::{Path, PathBuf},
         ptr::{self, NonNull},
+        slice,
+        sync::Arc,
     },
 };
 
 pub struct FileMapped<T> {
     buf: RawPlace<T>,
     mmap: Option<MmapMut>,
+    observer: Observer,
+    tracker: Tracker,
+    label: Option<Arc<str>>,
+    path: Option<PathBuf>,
+    max_slack: usize,
+    /// Mappings are kept in the unmap pool by this identity of the file
+    pool: Option<FileId>,
+    /// Shared with readers once any of them is created
+    coordination: Option<Arc<Coordination>>,
+    /// Notified after every grow
+    signal: Option<Signal>,
+    /// Items which fit into the file at opening and are not truncated yet,
+    /// it's an upper bound of stored ones: files are padded to a page
+    file_cap: usize,
     pub(crate) file: File,
 }
 
 impl<T> FileMapped<T> {
+    /// Fails with [`io::ErrorKind::InvalidInput`] if `T` is aligned stricter than
+    /// mappings are, i.e. than a page.
     // todo: say about mapping, read-write guarantees, and `MIN_PAGE_SIZE`
     pub fn new(file: File) -> io::Result<Self> {
         const MIN_PAGE_SIZE: u64 = 4096;
 
-        if file.metadata()?.len() < MIN_PAGE_SIZE {
+        let (align, page) = (mem::align_of::<T>(), utils::page_size());
+        if align > page {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "`{}` requires {align}-byte alignment, but mappings are only aligned \
+                     to {page}-byte pages",
+                    any::type_name::<T>()
+                ),
+            ));
+        }
+
+        // zero-sized items are never written to the file
+        let file_len = file.metadata()?.len();
+        if !RawPlace::<T>::IS_ZST && file_len < MIN_PAGE_SIZE {
             file.set_len(MIN_PAGE_SIZE)?;
         }
+        let file_cap = (file_len as usize).checked_div(mem::size_of::<T>()).unwrap_or(0);
 
-        Ok(Self { file, buf: RawPlace::dangling(), mmap: None })
+        Ok(Self {
+            file,
+            buf: RawPlace::dangling(),
+            mmap: None,
+            observer: Observer::none(),
+            tracker: Tracker::none(),
+            label: None,
+            path: None,
+            max_slack: 0,
+            pool: None,
+            coordination: None,
+            signal: None,
+            file_cap,
+        })
     }
 
     pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<Self> {
-        File::options().create(true).read(true).write(true).open(path).and_then(Self::new)
+        let path = path.as_ref();
+        let mut mem = File::options()
+            .create(true)
+            .truncate(false)
+            .read(true)
+            .write(true)
+            .open(path)
+            .and_then(Self::new)?;
+        mem.path = Some(path.to_owned());
+        Ok(mem)
+    }
+
+    /// Path of the file if it's opened by [`from_path`](Self::from_path),
+    /// it's reported by errors of resizing
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    fn context(&self, bytes: u64) -> impl FnOnce(io::Error) -> Error + '_ {
+        move |source| Error::File { path: self.path.clone(), bytes, source }
     }
 
     fn map_yet(&mut self, cap: u64) -> io::Result<MmapMut> {
+        if let Some(mmap) = self.pool.and_then(|id| unmap_pool::take(id, cap as usize)) {
+            return Ok(mmap);
+        }
+        trace::timed!("mmap", label = self.label(), bytes = cap);
         unsafe { MmapOptions::new().len(cap as usize).map_mut(&self.file) }
     }
 
+    fn unmap(&mut self) {
+        if let Some(mmap) = self.mmap.take() {
+            if let Some(id) = self.pool {
+                return unmap_pool::put(id, mmap);
+            }
+            trace::timed!("munmap", label = self.label(), bytes = mmap.len());
+            drop(mmap);
+        }
+    }
+
     unsafe fn assume_mapped(&mut self) -> &mut [u8] {
         self.mmap.as_mut().unwrap_unchecked()
     }
-}
 
-impl<T> RawMem for FileMapped<T> {
-    type Item = T;
+    /// Writes modified pages of the mapping to the file and waits until it's done
+    pub fn flush(&self) -> io::Result<()> {
+        trace::timed!(
+            "flush",
+            label = self.label(),
+            bytes = self.mmap.as_ref().map_or(0, |mmap| mmap.len())
+        );
+        let result = match &self.mmap {
+            Some(mmap) => mmap.flush(),
+            None => Ok(()),
+        };
+        self.observer.flushed(result)
+    }
 
-    fn allocated(&self) -> &[Self::Item] {
-        unsafe { self.buf.as_slice() }
+    /// Makes everything written before it durable before any later write: modified pages
+    /// are written like by [`flush`](Self::flush), then the file with its length is synced,
+    /// so protocols like write-ahead logging can order their writes by barriers
+    /// ```
+    /// use platform_mem::{FileMapped, RawMem};
+    ///
+    /// let mut mem = FileMapped::<u64>::new(tempfile::tempfile()?)?;
+    /// mem.grow_from_slice(&[1, 2, 3])?; // e.g. the log record
+    /// mem.barrier()?;
+    /// mem.allocated_mut()[0] = 0; // e.g. the page it describes
+    /// # Ok::<_, Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn barrier(&self) -> io::Result<()> {
+        trace::timed!(
+            "barrier",
+            label = self.label(),
+            bytes = self.mmap.as_ref().map_or(0, |mmap| mmap.len())
+        );
+        let result = match &self.mmap {
+            Some(mmap) => mmap.flush(),
+            None => Ok(()),
+        };
+        self.observer.flushed(result.and_then(|()| self.file.sync_all()))
     }
 
-    fn allocated_mut(&mut self) -> &mut [Self::Item] {
-        unsafe { self.buf.as_slice_mut() }
+    /// Deallocates disk blocks of mapped pages which are all zeros, then [`flush`]es
+    /// the rest, and returns size of the deallocated pages in bytes. The file reads
+    /// the same, but sparsely populated files take much less space.
+    ///
+    /// Other handles of the file must not write into it meanwhile.
+    /// Pages are deallocated only on Linux.
+    ///
+    /// [`flush`]: Self::flush
+    pub fn flush_sparse(&mut self) -> io::Result<usize> {
+        let mut punched = 0;
+        if let Some(mmap) = &self.mmap {
+            trace::timed!("flush_sparse", label = self.label(), bytes = mmap.len());
+            let page = utils::page_size();
+            // runs of zeroed pages are punched at once
+            let mut zeros = None;
+            for (i, chunk) in mmap.chunks(page).enumerate() {
+                match (utils::is_zeroed(chunk), zeros) {
+                    (true, None) => zeros = Some(i * page),
+                    (false, Some(start)) => {
+                        punched += utils::punch_hole(&self.file, start, i * page)?;
+                        zeros = None;
+                    }
+                    _ => {}
+                }
+            }
+            if let Some(start) = zeros {
+                punched += utils::punch_hole(&self.file, start, mmap.len())?;
+            }
+        }
+        self.flush().map(|_| punched)
     }
 
-    unsafe fn grow(
+    /// Replaces the observer of grows, shrinks, flushes and their errors
+    pub fn set_observer(&mut self, observer: impl MemObserver + 'static) {
+        self.observer.set(observer);
+    }
+
+    pub fn take_observer(&mut self) -> Option<Box<dyn MemObserver>> {
+        self.observer.take()
+    }
+
+    /// Names the memory in `Debug` output, errors, tracing spans and the registry
+    pub fn with_label(mut self, label: &str) -> Self {
+        let label = Arc::from(label);
+        self.tracker.label(&label);
+        self.label = Some(label);
+        self
+    }
+
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// Makes shrinks and drop of the memory skip dropping of items, e.g. when
+    /// the file is their source of truth and they only wrap plain data
+    pub fn with_manual_drop(mut self) -> Self {
+        self.buf.set_manual_drop(true);
+        self
+    }
+
+    /// Makes shrinks only drop the removed items and keep the file and its mapping
+    /// for the next grows, until more than `max_slack` items are kept
+    /// or [`compact`](Self::compact) is called.
+    ///
+    /// Next grows see kept items as initialized only if they weren't dropped
+    /// (with [`with_manual_drop`](Self::with_manual_drop) or for items without drop glue),
+    /// otherwise [`grow_assumed`](RawMem::grow_assumed) must not be used over them.
+    pub fn with_deferred_shrink(mut self, max_slack: usize) -> Self {
+        self.max_slack = max_slack;
+        self
+    }
+
+    /// Makes shrinks never truncate the file nor map it again, so the file stays at
+    /// its largest length and the next grows up to it are cheap, until
+    /// [`compact`](Self::compact) is called. It's a deferred shrink without limit.
+    /// ```
+    /// use {platform_mem::{FileMapped, RawMem}, std::fs};
+    ///
+    /// let dir = tempfile::tempdir()?;
+    /// let path = dir.path().join("mem");
+    /// let mut mem = FileMapped::<u64>::from_path(&path)?.with_high_water_mark();
+    /// mem.grow_filled(10_000, 1)?;
+    ///
+    /// mem.shrink(9_000)?;
+    /// assert_eq!(fs::metadata(&path)?.len(), 80_000);
+    ///
+    /// mem.grow_filled(5_000, 2)?; // kept place is reused
+    /// mem.compact()?;
+    /// assert_eq!(fs::metadata(&path)?.len(), 48_000);
+    /// # Ok::<_, Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn with_high_water_mark(self) -> Self {
+        self.with_deferred_shrink(usize::MAX)
+    }
+
+    /// Keeps unmapped mappings of the file in a small process-wide pool and reuses
+    /// them for the next mappings of the same length, so memories which oscillate
+    /// in size don't repeat `mmap`/`munmap`. It does nothing on platforms other than Unix.
+    /// ```
+    /// use platform_mem::{FileMapped, RawMem};
+    ///
+    /// let file = tempfile::tempfile()?;
+    /// let mut mem = FileMapped::<u64>::new(file)?.with_unmap_pool();
+    /// for _ in 0..3 {
+    ///     mem.grow_filled(100_000, 1)?; // maps the kept mapping again
+    ///     mem.shrink(90_000)?;
+    /// }
+    /// assert_eq!(mem.allocated().len(), 30_000);
+    /// # Ok::<_, Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn with_unmap_pool(mut self) -> Self {
+        self.pool = unmap_pool::file_id(&self.file);
+        self
+    }
+
+    /// Notifies `signal` after every grow, so readers (e.g. in other processes)
+    /// wait for new items instead of polling the length, see [`Signal`]
+    pub fn with_signal(mut self, signal: Signal) -> Self {
+        self.signal = Some(signal);
+        self
+    }
+
+    pub fn signal(&self) -> Option<&Signal> {
+        self.signal.as_ref()
+    }
+
+    /// Creates a reader with its own mapping of the file, which is coordinated
+    /// with this memory, see [`MappedReader`]
+    pub fn reader(&mut self) -> io::Result<MappedReader<T>> {
+        let len = self.buf.len();
+        let coordination =
+            self.coordination.get_or_insert_with(|| Arc::new(Coordination::new(len)));
+        Ok(MappedReader::new(self.file.try_clone()?, coordination.clone()))
+    }
+
+    /// Publishes the length to readers, so they see no items which are dropped after it
+    fn publish(&self, len: usize) {
+        if let Some(coordination) = &self.coordination {
+            coordination.write().set(len);
+        }
+    }
+
+    /// Deallocates whole unused pages of the file kept by deferred shrinks or reservations
+    /// without changing the file length and the mapping, and returns their size in bytes.
+    /// It does nothing on platforms other than Linux.
+    pub fn trim(&mut self) -> Result<usize> {
+        let (len, cap) = (self.buf.len(), self.buf.cap());
+        if len == cap {
+            return Ok(0);
+        }
+        trace::timed!("trim", mem = "FileMapped", label = self.label(), len, cap);
+
+        let size = mem::size_of::<T>();
+        utils::punch_hole(&self.file, len * size, cap * size)
+            .map_err(self.context((len * size) as u64))
+            .map_err(|err| err.labeled(self.label()))
+    }
+
+    /// Extends the file and maps it for `addition` items after the initialized ones,
+    /// they become visible by [`commit`](Self::commit)
+    pub fn begin_grow(&mut self, addition: usize) -> Result<Reservation> {
+        let result = self.reserve_unobserved(addition).map_err(|err| err.labeled(self.label()));
+        watchdog::verify!(self, "begin_grow");
+        result
+    }
+
+    /// Initializes reserved place by `fill` like [`RawMem::grow`]
+    ///
+    /// # Safety
+    /// Same as [`RawMem::grow`], `reservation` must come from this memory
+    pub unsafe fn commit(
         &mut self,
-        addition: usize,
+        reservation: Reservation,
         fill: impl FnOnce(usize, (&mut [T], &mut [MaybeUninit<T>])),
     ) -> Result<&mut [T]> {
-        let cap = self.buf.cap().checked_add(addition).ok_or(CapacityOverflow)?;
+        self.observed_grow(|this| this.commit_unobserved(reservation, fill))
+    }
+
+    /// Truncates the file and its mapping kept by deferred shrinks
+    pub fn compact(&mut self) -> Result<()> {
+        if self.buf.len() == self.buf.cap() {
+            return Ok(());
+        }
+        trace::timed!(
+            "compact",
+            mem = "FileMapped",
+            label = self.label(),
+            len = self.buf.len(),
+            cap = self.buf.cap()
+        );
+
+        let result = self.release(self.buf.len()).map_err(|err| err.labeled(self.label()));
+        watchdog::verify!(self, "compact");
+        result
+    }
+
+    /// Reads element without letting compiler cache or elide the access,
+    /// which is required when other processes write into the same mapping.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of allocated bounds.
+    pub fn read_volatile(&self, index: usize) -> T
+    where
+        T: Copy,
+    {
+        let place = self.place(index);
+        unsafe { place.read_volatile() }
+    }
+
+    /// Writes element so that the store is never elided or merged by compiler.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of allocated bounds.
+    pub fn write_volatile(&mut self, index: usize, val: T)
+    where
+        T: Copy,
+    {
+        let place = self.place(index);
+        unsafe { place.write_volatile(val) }
+    }
+
+    /// Pointer to the item without a reference to it, which other processes may write
+    fn place(&self, index: usize) -> *mut T {
+        let len = self.buf.len();
+        assert!(index < len, "index {index} is out of allocated bounds of length {len}");
+        // SAFETY: `index` is within the allocated items
+        unsafe { self.buf.ptr().as_ptr().add(index) }
+    }
+
+    /// Unmaps memory and packages the file with the allocated length and the settings
+    /// of the memory, so it can be sent elsewhere and mapped again by [`from_token`].
+    /// Elements are not dropped: their ownership is moved into the token.
+    ///
+    /// [`from_token`]: Self::from_token
+    pub fn into_token(self) -> MappingToken<T> {
+        let mut this = ManuallyDrop::new(self);
+        let len = this.buf.len();
+        this.publish(0);
+        drop(this.mmap.take());
+        if let Some(id) = this.pool {
+            unmap_pool::forget(id);
+        }
+        drop(this.observer.take());
+        drop(mem::replace(&mut this.tracker, Tracker::none()));
+        drop(this.coordination.take());
+
+        let settings = Settings {
+            label: this.label.take(),
+            path: this.path.take(),
+            signal: this.signal.take(),
+            max_slack: this.max_slack,
+            pooled: this.pool.is_some(),
+            manual_drop: this.buf.manual_drop(),
+        };
+        // SAFETY: `this` is never used or dropped after moving out the file,
+        // other fields which own anything are taken above
+        let file = unsafe { ptr::read(&this.file) };
+        MappingToken { file, len, settings, _marker: PhantomData }
+    }
+
+    /// Maps the file of the token again with the settings of the memory it came from
+    pub fn from_token(token: MappingToken<T>) -> Result<Self> {
+        let Settings { label, path, signal, max_slack, pooled, manual_drop } = token.settings;
+        let mut mem = Self::new(token.file)?.with_deferred_shrink(max_slack);
+        if let Some(label) = label {
+            mem.tracker.label(&label);
+            mem.label = Some(label);
+        }
+        if pooled {
+            mem = mem.with_unmap_pool();
+        }
+        mem.buf.set_manual_drop(manual_drop);
+        mem.path = path;
+        mem.signal = signal;
+        // SAFETY: file contains `len` elements which were owned by another `FileMapped<T>`
+        unsafe { mem.grow_assumed(token.len)? };
+        Ok(mem)
+    }
+}
+
+/// Settings of [`FileMapped`] which are carried by its [`MappingToken`]
+#[derive(Debug, Default)]
+struct Settings {
+    label: Option<Arc<str>>,
+    path: Option<PathBuf>,
+    signal: Option<Signal>,
+    max_slack: usize,
+    pooled: bool,
+    manual_drop: bool,
+}
+
+/// Mapping detached from its address space by [`FileMapped::into_token`]
+///
+/// It can be sent to another thread, or decomposed by [`into_parts`] to pass
+/// the file descriptor (handle) to another process.
+///
+/// [`into_parts`]: Self::into_parts
+#[derive(Debug)]
+pub struct MappingToken<T> {
+    file: File,
+    len: usize,
+    settings: Settings,
+    _marker: PhantomData<T>,
+}
+
+impl<T> MappingToken<T> {
+    /// Returns the file and count of initialized elements in it,
+    /// settings of the memory are not kept
+    pub fn into_parts(self) -> (File, usize) {
+        (self.file, self.len)
+    }
+
+    /// # Safety
+    /// `file` must contain at least `len` valid elements of `T`,
+    /// usually these are the parts of [`into_parts`](Self::into_parts)
+    pub unsafe fn from_parts(file: File, len: usize) -> Self {
+        Self { file, len, settings: Settings::default(), _marker: PhantomData }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Label of the memory the token came from
+    pub fn label(&self) -> Option<&str> {
+        self.settings.label.as_deref()
+    }
+}
+
+macro_rules! atomic_views {
+    ($($int:ty => $atomic:ident in $width:literal),* $(,)?) => {$(
+        #[cfg(target_has_atomic = $width)]
+        impl FileMapped<$int> {
+            #[doc = concat!(
+                "Views allocated memory as [`", stringify!($atomic),
+                "`](std::sync::atomic::", stringify!($atomic), ")s."
+            )]
+            ///
+            /// Mapping is shared with other handles of the file (and other processes),
+            /// so atomics are the way to coordinate through it. The view borrows
+            /// the memory mutably, so it can't alias plain accesses of this handle,
+            /// but it can be shared between threads.
+            pub fn allocated_atomic(&mut self) -> &[std::sync::atomic::$atomic] {
+                let slice = self.allocated_mut();
+                if slice.is_empty() {
+                    return &[]; // dangling pointer may be under-aligned for atomic
+                }
+                // SAFETY: atomic integers have the same size and bit validity as `$int`,
+                // mapping is page aligned, so it's aligned for any atomic,
+                // and the unique borrow of the items is held while the view is alive
+                unsafe { &*(slice as *mut [$int] as *const [std::sync::atomic::$atomic]) }
+            }
+        }
+    )*};
+}
+
+atomic_views! {
+    u8 => AtomicU8 in "8",
+    i8 => AtomicI8 in "8",
+    u16 => AtomicU16 in "16",
+    i16 => AtomicI16 in "16",
+    u32 => AtomicU32 in "32",
+    i32 => AtomicI32 in "32",
+    u64 => AtomicU64 in "64",
+    i64 => AtomicI64 in "64",
+    usize => AtomicUsize in "ptr",
+    isize => AtomicIsize in "ptr",
+}
+
+impl<T> FileMapped<T> {
+    fn reserve_unobserved(&mut self, addition: usize) -> Result<Reservation> {
+        trace::timed!(
+            "reserve",
+            mem = "FileMapped",
+            label = self.label(),
+            len = self.buf.len(),
+            addition
+        );
+
+        let cap = self.buf.len().checked_add(addition).ok_or(CapacityOverflow)?;
+        // place kept by a deferred shrink holds its old items, unless they were dropped
+        let kept = if self.buf.manual_drop() || !mem::needs_drop::<T>() {
+            self.buf.cap() - self.buf.len()
+        } else {
+            0
+        };
+        if cap <= self.buf.cap() {
+            let inited = if kept == 0 { 0 } else { addition };
+            return Ok(Reservation { addition, inited, generation: self.buf.generation() });
+        }
+        if RawPlace::<T>::IS_ZST {
+            // nothing to map, but the file "contains" any count of items
+            unsafe { self.buf.handle_fill((NonNull::dangling(), cap), 0, 0, |_, _| {}) };
+            return Ok(Reservation {
+                addition,
+                inited: addition,
+                generation: self.buf.generation(),
+            });
+        }
         // use layout to prevent all capacity bugs
         let layout = Layout::array::<T>(cap).map_err(|_| CapacityOverflow)?;
         let new_size = layout.size() as u64;
 
-        // unmap the file by calling `Drop` of `mmap`
-        let _ = self.mmap.take();
+        self.unmap();
 
-        let old_size = self.file.metadata()?.len();
+        let old_size = self.file.metadata().map_err(self.context(new_size))?.len();
 
         #[rustfmt::skip]
         let inited = if old_size < new_size {
-            self.file.set_len(new_size)?;
-            (old_size as usize / mem::size_of::<T>()) // more flexible without `rustfmt`
-                .unchecked_sub(self.buf.cap())
+            self.file.set_len(new_size).map_err(self.context(new_size))?;
+            unsafe {
+                (old_size as usize / mem::size_of::<T>()) // more flexible without `rustfmt`
+                    .unchecked_sub(self.buf.cap())
+            }
         } else {
-            addition // all place is available as initialized
+            cap - self.buf.cap() // all place is available as initialized
         };
+        // file data is after the kept tail, so it's initialized only if the tail is
+        let inited = if kept == self.buf.cap() - self.buf.len() { kept + inited } else { 0 };
 
-        let ptr = unsafe {
-            let mmap = self.map_yet(new_size)?;
+        unsafe {
+            let mmap = self.map_yet(new_size).map_err(self.context(new_size))?;
             self.mmap.replace(mmap);
             // we set it now: ^^^
-            NonNull::from(self.assume_mapped()) // it assume that `mmap` is some
-        };
+            let ptr = NonNull::from(self.assume_mapped()); // it assume that `mmap` is some
 
-        Ok(self.buf.handle_fill((ptr.cast(), cap), inited, fill))
+            // nothing is filled yet, the place is only owned
+            self.buf.handle_fill((ptr.cast(), cap), 0, 0, |_, _| {});
+        }
+        Ok(Reservation { addition, inited, generation: self.buf.generation() })
     }
 
-    fn shrink(&mut self, cap: usize) -> Result<()> {
-        let cap = self.buf.cap().checked_sub(cap).expect("Tried to shrink to a larger capacity");
+    unsafe fn commit_unobserved(
+        &mut self,
+        reservation: Reservation,
+        fill: impl FnOnce(usize, (&mut [T], &mut [MaybeUninit<T>])),
+    ) -> Result<()> {
+        let Reservation { addition, inited, generation } = reservation;
+        let reserved = generation == self.buf.generation()
+            && self.buf.len().checked_add(addition).is_some_and(|len| len <= self.buf.cap());
+        let inited = if reserved { inited } else { self.reserve_unobserved(addition)?.inited };
+
+        trace::timed!(
+            "grow",
+            mem = "FileMapped",
+            label = self.label(),
+            len = self.buf.len(),
+            addition
+        );
+        self.buf.handle_fill((self.buf.ptr(), self.buf.cap()), addition, inited, fill);
+        self.publish(self.buf.len());
+        if let Some(signal) = &self.signal {
+            signal.notify();
+        }
+        Ok(())
+    }
+
+    fn shrink_unobserved(&mut self, cap: usize) -> Result<()> {
+        trace::timed!(
+            "shrink",
+            mem = "FileMapped",
+            label = self.label(),
+            len = self.buf.len(),
+            removal = cap
+        );
+
+        let cap = self.buf.len().checked_sub(cap).expect("Tried to shrink to a larger capacity");
+        // readers wait until removed items are dropped and the file is truncated
+        let coordination = self.coordination.clone();
+        let mut published = coordination.as_deref().map(Coordination::write);
+        if let Some(published) = &mut published {
+            published.set(cap);
+        }
+        // removed items are dropped, even if the file keeps them
+        self.file_cap = self.file_cap.min(cap);
+        if self.buf.cap() - cap <= self.max_slack {
+            self.buf.truncate(cap);
+            return Ok(());
+        }
+        self.release(cap)
+    }
+
+    /// Truncates the file to `cap` items and maps it again, dropping items after it
+    fn release(&mut self, cap: usize) -> Result<()> {
         self.buf.shrink_to(cap);
+        self.file_cap = self.file_cap.min(cap);
+        if RawPlace::<T>::IS_ZST {
+            return Ok(());
+        }
 
-        let _ = self.mmap.take();
+        self.unmap();
 
         let ptr = unsafe {
             // we can skip this checks because this memory layout is valid
             // then smaller layout will also be valid
             let new_size = mem::size_of::<T>().unchecked_mul(cap) as u64;
-            self.file.set_len(new_size)?;
+            self.file.set_len(new_size).map_err(self.context(new_size))?;
 
-            let mmap = self.map_yet(new_size)?;
+            let mmap = self.map_yet(new_size).map_err(self.context(new_size))?;
             self.mmap.replace(mmap);
 
             self.assume_mapped().into()
@@ -112,19 +687,143 @@ impl<T> RawMem for FileMapped<T> {
     }
 }
 
+impl<T> FileMapped<T> {
+    fn observed_grow(&mut self, grow: impl FnOnce(&mut Self) -> Result<()>) -> Result<&mut [T]> {
+        let old = self.buf.len();
+        let result = grow(self).map_err(|err| err.labeled(self.label()));
+        watchdog::verify!(self, "grow");
+        self.observer.grown(old, self.buf.len(), result)?;
+        self.tracker.resized::<T>("FileMapped", self.buf.len(), true);
+        Ok(unsafe { &mut self.buf.as_slice_mut()[old..] })
+    }
+
+    #[cfg(all(debug_assertions, feature = "watchdog"))]
+    fn verify(&self, op: &'static str) {
+        let (ptr, len, cap) = (self.buf.ptr(), self.buf.len(), self.buf.cap());
+        let size = cap * mem::size_of::<T>();
+        let file_len = self.file.metadata().map(|meta| meta.len());
+        let mapped = self.mmap.as_deref().map(|mmap| (mmap.as_ptr(), mmap.len()));
+        let mut report = watchdog::Report::new("FileMapped", op)
+            .state("label", self.label())
+            .state("ptr", ptr)
+            .state("len", len)
+            .state("cap", cap)
+            .state("mmap", mapped)
+            .state("file length", &file_len);
+
+        report.ensure(len <= cap, || format!("length {len} exceeds capacity {cap}"));
+        report.ensure(ptr.is_aligned(), || {
+            format!("{ptr:?} is not aligned to {}", mem::align_of::<T>())
+        });
+        if let Some((mapped, mapped_len)) = mapped.filter(|_| cap > 0) {
+            report.ensure(mapped_len == size, || {
+                format!("mapping of {mapped_len} bytes, but layout is {size} bytes")
+            });
+            report.ensure(ptr.as_ptr().cast_const().cast() == mapped, || {
+                format!("{ptr:?} points outside of the mapping at {mapped:?}")
+            });
+        }
+        match file_len {
+            Ok(file_len) => report.ensure(file_len >= size as u64, || {
+                format!("file of {file_len} bytes is shorter than {size} mapped bytes")
+            }),
+            Err(err) => report.ensure(false, || format!("file is not available: {err}")),
+        }
+        report.finish();
+    }
+}
+
+// SAFETY: items are in the mapping, which isn't changed by moves and reads
+unsafe impl<T> StableMem for FileMapped<T> {}
+
+impl<T> RawMem for FileMapped<T> {
+    type Item = T;
+
+    fn allocated(&self) -> &[Self::Item] {
+        unsafe { self.buf.as_slice() }
+    }
+
+    fn allocated_mut(&mut self) -> &mut [Self::Item] {
+        unsafe { self.buf.as_slice_mut() }
+    }
+
+    unsafe fn grow(
+        &mut self,
+        addition: usize,
+        fill: impl FnOnce(usize, (&mut [T], &mut [MaybeUninit<T>])),
+    ) -> Result<&mut [T]> {
+        self.observed_grow(|this| {
+            let reservation = this.reserve_unobserved(addition)?;
+            this.commit_unobserved(reservation, fill)
+        })
+    }
+
+    fn shrink(&mut self, cap: usize) -> Result<()> {
+        let old = self.buf.len();
+        let result = self.shrink_unobserved(cap).map_err(|err| err.labeled(self.label()));
+        watchdog::verify!(self, "shrink");
+        self.observer.shrunk(old, self.buf.len(), result)?;
+        self.tracker.resized::<T>("FileMapped", self.buf.len(), false);
+        Ok(())
+    }
+
+    /// Items which fit into the file, so a reopened file can be grown over its data by
+    /// [`grow_assumed`](RawMem::grow_assumed). It's an upper bound: the length of items
+    /// is not stored, and files are padded to a page, so the tail is usually zeroed
+    /// padding which formats should skip by their own headers or framing.
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.buf.len().max(self.file_cap))
+    }
+
+    fn generation(&self) -> u64 {
+        self.buf.generation()
+    }
+
+    /// Also asks the OS to read pages of `range` ahead (`MADV_WILLNEED` on Unix)
+    fn prefetch<R: RangeBounds<usize>>(&self, range: R) {
+        let Range { start, end } = slice::range(range, ..self.buf.len());
+        #[cfg(unix)]
+        if let Some(mmap) = &self.mmap {
+            let size = mem::size_of::<T>();
+            if start < end && size != 0 {
+                // it's only a hint, so failures are ignored
+                let _ = mmap.advise_range(
+                    memmap2::Advice::WillNeed,
+                    start * size,
+                    (end - start) * size,
+                );
+            }
+        }
+        utils::prefetch(&self.allocated()[start..end]);
+    }
+
+    fn memory_usage(&self) -> MemoryUsage {
+        let mapped = self.mmap.as_deref().unwrap_or_default();
+        MemoryUsage::new(
+            self.buf.len() * mem::size_of::<T>(),
+            mapped.len(),
+            utils::resident(mapped).ok(),
+        )
+    }
+}
+
 impl<T> Drop for FileMapped<T> {
     fn drop(&mut self) {
+        self.publish(0);
         unsafe {
-            ptr::drop_in_place(self.buf.as_slice_mut());
+            self.buf.drop_items();
+        }
+        if let Some(id) = self.pool {
+            unmap_pool::forget(id);
         }
 
         let _ = self.file.sync_all();
     }
 }
 
-impl<T> fmt::Debug for FileMapped<T> {
+impl<T: fmt::Debug> fmt::Debug for FileMapped<T> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        utils::debug_mem(f, &self.buf, "FileMapped")?
+        utils::debug_mem(f, &self.buf, "FileMapped", self.label())?
             .field("mmap", &self.mmap)
             .field("file", &self.file)
             .finish()
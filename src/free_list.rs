@@ -0,0 +1,132 @@
+use {
+    crate::{free_ranges::FreeRanges, RawMem, Result},
+    std::{
+        fmt::{self, Formatter},
+        ops::Range,
+    },
+};
+
+/// Which free range [`FreeListMem`] takes for an allocation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FitPolicy {
+    /// The first one (by position) which is large enough
+    #[default]
+    First,
+    /// The smallest one which is large enough, it leaves larger ranges for later
+    Best,
+}
+
+/// Malloc-like ranges of items inside a single memory.
+///
+/// Freed ranges are coalesced with adjacent free ones and reused by the next
+/// allocations, a free tail shrinks the memory. Free ranges are known only
+/// to this allocator, so reopening a file forgets them.
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use platform_mem::{FitPolicy, FreeListMem, Global};
+///
+/// let mut list = FreeListMem::new(Global::new(), FitPolicy::Best);
+/// let large = list.alloc(8, 0)?;
+/// let _ = list.alloc(1, 0)?;
+/// let small = list.alloc(3, 0)?;
+/// let _ = list.alloc(1, 0)?;
+///
+/// list.free(large, 8);
+/// list.free(small, 3);
+/// assert_eq!(list.alloc(2, 7)?, small); // smallest place which fits
+/// assert_eq!(list.get(small, 3), Some(&[7, 7, 0][..]));
+/// assert_eq!(list.free_ranges().collect::<Vec<_>>(), [0..8, 11..12]);
+/// # Ok::<_, platform_mem::Error>(())
+/// ```
+pub struct FreeListMem<M> {
+    mem: M,
+    free: FreeRanges,
+    fit: FitPolicy,
+}
+
+impl<M: RawMem> FreeListMem<M> {
+    /// Allocated items of `mem` are considered used
+    pub fn new(mem: M, fit: FitPolicy) -> Self {
+        Self { mem, free: FreeRanges::default(), fit }
+    }
+
+    /// Allocates `len` items set to `value` and returns start of them
+    pub fn alloc(&mut self, len: usize, value: M::Item) -> Result<usize>
+    where
+        M::Item: Clone,
+    {
+        if len == 0 {
+            return Ok(self.mem.allocated().len());
+        }
+        match self.free.take(len, self.fit == FitPolicy::Best) {
+            Some(start) => {
+                self.mem.allocated_mut()[start..start + len].fill(value);
+                Ok(start)
+            }
+            None => {
+                let start = self.mem.allocated().len();
+                self.mem.grow_filled(len, value)?;
+                Ok(start)
+            }
+        }
+    }
+
+    fn range(&self, start: usize, len: usize) -> Option<Range<usize>> {
+        let end = start.checked_add(len)?;
+        (end <= self.mem.allocated().len()).then_some(start..end)
+    }
+
+    pub fn get(&self, start: usize, len: usize) -> Option<&[M::Item]> {
+        let range = self.range(start, len)?;
+        Some(&self.mem.allocated()[range])
+    }
+
+    pub fn get_mut(&mut self, start: usize, len: usize) -> Option<&mut [M::Item]> {
+        let range = self.range(start, len)?;
+        Some(&mut self.mem.allocated_mut()[range])
+    }
+
+    /// Makes the range reusable, `len` must be its allocated length
+    ///
+    /// # Panics
+    /// Panics if the range is out of bounds or it's already freed.
+    pub fn free(&mut self, start: usize, len: usize) {
+        self.range(start, len).expect("range is out of bounds");
+        if len == 0 {
+            return;
+        }
+        let (start, len) = self.free.give(start, len);
+        if start + len == self.mem.allocated().len() && self.mem.shrink(len).is_ok() {
+            self.free.remove(start);
+        }
+    }
+
+    /// Count of freed items which are not reused yet
+    pub fn free_len(&self) -> usize {
+        self.free.free()
+    }
+
+    /// Coalesced free ranges in order of their starts
+    pub fn free_ranges(&self) -> impl Iterator<Item = Range<usize>> + '_ {
+        self.free.iter()
+    }
+
+    pub fn inner(&self) -> &M {
+        &self.mem
+    }
+
+    pub fn into_inner(self) -> M {
+        self.mem
+    }
+}
+
+impl<M: fmt::Debug> fmt::Debug for FreeListMem<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FreeListMem")
+            .field("mem", &self.mem)
+            .field("fit", &self.fit)
+            .field("free_len", &self.free.free())
+            .finish()
+    }
+}
@@ -0,0 +1,68 @@
+use std::{collections::BTreeMap, ops::Range};
+
+/// Free ranges of a memory by their starts, adjacent ones are always coalesced
+#[derive(Debug, Default)]
+pub(crate) struct FreeRanges {
+    ranges: BTreeMap<usize, usize>,
+    free: usize,
+}
+
+impl FreeRanges {
+    /// Count of free items
+    pub fn free(&self) -> usize {
+        self.free
+    }
+
+    /// Takes `len` items from the first range which is large enough,
+    /// or from the smallest one if `best` is set
+    pub fn take(&mut self, len: usize, best: bool) -> Option<usize> {
+        let mut fits = self.ranges.iter().filter(|(_, &free)| free >= len);
+        let (&start, &free) = if best { fits.min_by_key(|(_, &free)| free) } else { fits.next() }?;
+
+        self.ranges.remove(&start);
+        if free > len {
+            self.ranges.insert(start + len, free - len);
+        }
+        self.free -= len;
+        Some(start)
+    }
+
+    /// Frees `len` items at `start` and returns the coalesced range
+    ///
+    /// # Panics
+    /// Panics if any of items is free already.
+    pub fn give(&mut self, start: usize, len: usize) -> (usize, usize) {
+        let (given, end) = (len, start + len);
+        let (mut start, mut len) = (start, len);
+
+        if let Some((&next, &next_len)) = self.ranges.range(start..).next() {
+            assert!(next >= end, "range is already freed");
+            if next == end {
+                self.ranges.remove(&next);
+                len += next_len;
+            }
+        }
+        if let Some((&prev, &prev_len)) = self.ranges.range(..start).next_back() {
+            assert!(prev + prev_len <= start, "range is already freed");
+            if prev + prev_len == start {
+                self.ranges.remove(&prev);
+                (start, len) = (prev, len + prev_len);
+            }
+        }
+
+        self.ranges.insert(start, len);
+        self.free += given;
+        (start, len)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Range<usize>> + '_ {
+        self.ranges.iter().map(|(&start, &len)| start..start + len)
+    }
+
+    /// Forgets the range at `start`, e.g. when it's cut off the memory
+    pub fn remove(&mut self, start: usize) {
+        if let Some(len) = self.ranges.remove(&start) {
+            self.free -= len;
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use {
+    crate::{Call, RawMem, Result},
+    std::{
+        collections::VecDeque,
+        fmt::{self, Formatter},
+        result,
+        time::SystemTime,
+    },
+};
+
+/// Operation recorded by [`HistoryMem`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub call: Call,
+    /// Length of memory after the call
+    pub len: usize,
+    pub at: SystemTime,
+    /// Message of the error if the call failed
+    pub result: result::Result<(), String>,
+}
+
+/// Memory that keeps the last `cap` grows and shrinks for diagnostics,
+/// they are also printed by `Debug`.
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use platform_mem::{Call, Global, HistoryMem, RawMem};
+///
+/// let mut mem = HistoryMem::new(Global::<u8>::new(), 2);
+/// mem.grow_filled(10, 0)?;
+/// mem.grow_filled(20, 0)?;
+/// mem.shrink(5)?;
+///
+/// let calls: Vec<_> = mem.history().map(|record| (record.call, record.len)).collect();
+/// assert_eq!(calls, [(Call::Grow { addition: 20 }, 30), (Call::Shrink { cap: 5 }, 25)]);
+/// # Ok::<_, platform_mem::Error>(())
+/// ```
+pub struct HistoryMem<M> {
+    mem: M,
+    records: VecDeque<Record>,
+    cap: usize,
+}
+
+impl<M: RawMem> HistoryMem<M> {
+    pub fn new(mem: M, cap: usize) -> Self {
+        Self { mem, records: VecDeque::with_capacity(cap), cap }
+    }
+
+    /// Records from the oldest to the newest
+    pub fn history(&self) -> impl ExactSizeIterator<Item = &Record> {
+        self.records.iter()
+    }
+
+    pub fn clear_history(&mut self) {
+        self.records.clear();
+    }
+
+    pub fn into_inner(self) -> M {
+        self.mem
+    }
+
+    fn record<R>(&mut self, call: Call, result: Result<R>) -> Result<R> {
+        if self.cap == 0 {
+            return result;
+        }
+        if self.records.len() == self.cap {
+            self.records.pop_front();
+        }
+        self.records.push_back(Record {
+            call,
+            len: self.mem.allocated().len(),
+            at: SystemTime::now(),
+            result: result.as_ref().map(drop).map_err(ToString::to_string),
+        });
+        result
+    }
+}
+
+forward_mem! {
+    impl<M> RawMem for HistoryMem<M> {
+        unsafe fn grow(&mut self, addition, fill) {
+            let result = self.mem.grow(addition, fill).map(drop);
+            self.record(Call::Grow { addition }, result)?;
+        }
+
+        fn shrink(&mut self, cap) {
+            let result = self.mem.shrink(cap);
+            self.record(Call::Shrink { cap }, result)
+        }
+    }
+}
+
+impl<M: fmt::Debug> fmt::Debug for HistoryMem<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HistoryMem")
+            .field("mem", &self.mem)
+            .field("history", &self.records)
+            .finish()
+    }
+}
@@ -0,0 +1,203 @@
+use {
+    crate::{utils, RawMem, Result},
+    bytemuck::Pod,
+    std::{
+        fmt::{self, Formatter},
+        fs::File,
+        io::{self, Read, Seek, SeekFrom, Write},
+        mem,
+        path::Path,
+    },
+};
+
+// every record sets memory to some state, so replaying a record twice is harmless
+const RESIZE: u8 = 1;
+const WRITE: u8 = 2;
+const GROW: u8 = 3;
+
+// tag, position and payload length
+const HEAD: usize = 1 + 8 + 8;
+
+/// Memory whose mutations are written to a write-ahead log before being applied.
+///
+/// Records describe target states (not deltas), so [`open`] can replay the whole
+/// log over the inner memory no matter how many of them were applied before a crash.
+/// A torn record at the end of the log is discarded. Once the inner memory is durable
+/// (e.g. after [`FileMapped::flush`]), call [`truncate_log`] to start a new log.
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use platform_mem::{Global, Journaled};
+///
+/// let wal = tempfile::NamedTempFile::new()?;
+/// let mut mem = Journaled::open(Global::new(), wal.path())?;
+/// mem.grow_from_slice(b"hello world")?;
+/// mem.write(0, b"H")?;
+/// mem.shrink(6)?;
+/// drop(mem);
+///
+/// // "crash": inner memory is lost, but the log is not
+/// let mem = Journaled::open(Global::<u8>::new(), wal.path())?;
+/// assert_eq!(mem.allocated(), b"Hello");
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+///
+/// [`open`]: Self::open
+/// [`truncate_log`]: Self::truncate_log
+/// [`FileMapped::flush`]: crate::FileMapped::flush
+pub struct Journaled<M> {
+    mem: M,
+    wal: File,
+}
+
+impl<T: Pod, M: RawMem<Item = T>> Journaled<M> {
+    /// Opens (or creates) the log at `path` and replays it over `mem`
+    ///
+    /// # Errors
+    /// Fails with [`io::ErrorKind::InvalidData`] if an intact record can't be applied,
+    /// e.g. it was logged for another item type. Such log is left untouched.
+    pub fn open<P: AsRef<Path>>(mem: M, path: P) -> Result<Self> {
+        const { assert!(mem::size_of::<T>() != 0) };
+
+        let wal = File::options().create(true).read(true).write(true).truncate(false).open(path)?;
+        let mut this = Self { mem, wal };
+        this.replay()?;
+        Ok(this)
+    }
+
+    fn replay(&mut self) -> Result<()> {
+        let mut log = Vec::new();
+        self.wal.seek(SeekFrom::Start(0))?;
+        self.wal.read_to_end(&mut log)?;
+
+        let mut valid = 0;
+        // only a torn or corrupted record ends the log, records after it were never synced
+        while let Some((tag, pos, payload)) = parse(&log[valid..]) {
+            self.apply(tag, pos, payload)?;
+            valid += HEAD + payload.len() + 8;
+        }
+
+        // drop torn tail, so new records are appended after the last valid one
+        self.wal.set_len(valid as u64)?;
+        self.wal.seek(SeekFrom::End(0))?;
+        Ok(())
+    }
+
+    /// Applies a well-formed record, fails if it doesn't fit into the memory
+    fn apply(&mut self, tag: u8, pos: usize, payload: &[u8]) -> Result<()> {
+        let invalid =
+            |msg: &str| io::Error::new(io::ErrorKind::InvalidData, format!("{msg} in log"));
+
+        if !payload.len().is_multiple_of(mem::size_of::<T>()) {
+            return Err(invalid("misaligned record payload").into());
+        }
+        let items = bytemuck::pod_collect_to_vec::<_, T>(payload);
+        match tag {
+            RESIZE => self.resize(pos),
+            WRITE => {
+                let place = pos
+                    .checked_add(items.len())
+                    .and_then(|end| self.mem.allocated_mut().get_mut(pos..end))
+                    .ok_or_else(|| invalid("out of bounds write"))?;
+                place.copy_from_slice(&items);
+                Ok(())
+            }
+            GROW => {
+                self.resize(pos)?;
+                self.mem.grow_from_slice(&items).map(drop)
+            }
+            _ => Err(invalid("unknown record").into()),
+        }
+    }
+
+    fn resize(&mut self, len: usize) -> Result<()> {
+        let current = self.mem.allocated().len();
+        if len < current {
+            self.mem.shrink(current - len)
+        } else {
+            // SAFETY: `T: Pod`, items which are already stored (e.g. in a reopened file)
+            // are kept, so the base record of a truncated log restores them
+            unsafe { self.mem.grow_zeroed_exact(len - current).map(drop) }
+        }
+    }
+
+    fn log(&mut self, tag: u8, pos: usize, items: &[T]) -> io::Result<()> {
+        let payload: &[u8] = bytemuck::cast_slice(items);
+
+        let mut record = Vec::with_capacity(HEAD + payload.len() + 8);
+        record.push(tag);
+        record.extend_from_slice(&(pos as u64).to_le_bytes());
+        record.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+        record.extend_from_slice(payload);
+        record.extend_from_slice(&utils::checksum(&record).to_le_bytes());
+
+        self.wal.write_all(&record)?;
+        self.wal.sync_data()
+    }
+
+    pub fn allocated(&self) -> &[T] {
+        self.mem.allocated()
+    }
+
+    pub fn grow_from_slice(&mut self, src: &[T]) -> Result<&mut [T]> {
+        self.log(GROW, self.mem.allocated().len(), src)?;
+        self.mem.grow_from_slice(src)
+    }
+
+    pub fn shrink(&mut self, cap: usize) -> Result<()> {
+        let len = self.mem.allocated().len();
+        let len = len.checked_sub(cap).expect("Tried to shrink to a larger capacity");
+        self.log(RESIZE, len, &[])?;
+        self.mem.shrink(cap)
+    }
+
+    /// Overwrites elements starting from `offset`
+    ///
+    /// # Panics
+    /// Panics if `offset + src.len()` is out of allocated bounds.
+    pub fn write(&mut self, offset: usize, src: &[T]) -> Result<()> {
+        assert!(
+            offset.checked_add(src.len()).is_some_and(|end| end <= self.mem.allocated().len()),
+            "write range is out of allocated bounds"
+        );
+        self.log(WRITE, offset, src)?;
+        self.mem.allocated_mut()[offset..offset + src.len()].copy_from_slice(src);
+        Ok(())
+    }
+
+    /// Forgets all logged records, call it only when the inner memory is durable.
+    /// The new log starts with the current length, so replay over the reopened
+    /// memory (e.g. a [`FileMapped`](crate::FileMapped) of length 0) restores it.
+    pub fn truncate_log(&mut self) -> Result<()> {
+        self.wal.set_len(0)?;
+        self.wal.seek(SeekFrom::Start(0))?;
+        Ok(self.log(RESIZE, self.mem.allocated().len(), &[])?)
+    }
+
+    pub fn inner(&self) -> &M {
+        &self.mem
+    }
+
+    pub fn into_inner(self) -> M {
+        self.mem
+    }
+}
+
+fn parse(log: &[u8]) -> Option<(u8, usize, &[u8])> {
+    let head = log.get(..HEAD)?;
+    let pos = u64::from_le_bytes(head[1..9].try_into().ok()?);
+    let len = usize::try_from(u64::from_le_bytes(head[9..17].try_into().ok()?)).ok()?;
+
+    let end = HEAD.checked_add(len)?;
+    let sum = u64::from_le_bytes(log.get(end..end.checked_add(8)?)?.try_into().ok()?);
+    if utils::checksum(&log[..end]) != sum {
+        return None;
+    }
+    Some((head[0], usize::try_from(pos).ok()?, &log[HEAD..end]))
+}
+
+impl<M: fmt::Debug> fmt::Debug for Journaled<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Journaled").field("mem", &self.mem).field("wal", &self.wal).finish()
+    }
+}
@@ -1,14 +1,11 @@
 #![feature(
     allocator_api,
-    unchecked_math,
-    maybe_uninit_slice,
     slice_ptr_get,
     ptr_as_uninit,
-    inline_const,
     slice_range,
-    maybe_uninit_write_slice,
     unboxed_closures,
-    fn_traits
+    fn_traits,
+    min_specialization
 )]
 // special lint
 #![cfg_attr(not(test), forbid(clippy::unwrap_used))]
@@ -16,24 +13,197 @@
 #![deny(unused_must_use)]
 #![warn(missing_debug_implementations)]
 
+/// Implements `RawMem` for a wrapper which keeps the memory in its `mem` field.
+/// `grow` and `shrink` wrap the ones of `mem`, `grow` returns nothing and
+/// the grown items are returned after it, other methods are forwarded.
+macro_rules! forward_mem {
+    (
+        impl<$param:ident> RawMem for $me:ident<$arg:ident> {
+            unsafe fn grow(&mut $grow:ident, $addition:ident, $fill:ident) $grow_body:block
+            fn shrink(&mut $shrink:ident, $cap:ident) $shrink_body:block
+        }
+    ) => {
+        impl<$param: RawMem> RawMem for $me<$param> {
+            type Item = $param::Item;
+
+            fn allocated(&self) -> &[Self::Item] {
+                self.mem.allocated()
+            }
+
+            fn allocated_mut(&mut self) -> &mut [Self::Item] {
+                self.mem.allocated_mut()
+            }
+
+            unsafe fn grow(
+                &mut $grow,
+                $addition: usize,
+                $fill: impl FnOnce(
+                    usize,
+                    (&mut [Self::Item], &mut [std::mem::MaybeUninit<Self::Item>]),
+                ),
+            ) -> $crate::Result<&mut [Self::Item]> {
+                $grow_body
+
+                let len = $grow.mem.allocated().len();
+                Ok(&mut $grow.mem.allocated_mut()[len - $addition..])
+            }
+
+            fn shrink(&mut $shrink, $cap: usize) -> $crate::Result<()> $shrink_body
+
+            fn size_hint(&self) -> Option<usize> {
+                self.mem.size_hint()
+            }
+
+            fn generation(&self) -> u64 {
+                self.mem.generation()
+            }
+
+            fn prefetch<R: std::ops::RangeBounds<usize>>(&self, range: R) {
+                self.mem.prefetch(range)
+            }
+
+            fn memory_usage(&self) -> $crate::MemoryUsage {
+                self.mem.memory_usage()
+            }
+        }
+    };
+}
+
 mod alloc;
+mod append;
+#[cfg(feature = "rkyv")]
+pub mod archive;
+mod backup;
+mod bits;
+mod blob;
+mod budget;
+mod cached;
+mod chain;
+mod channel;
+mod checkpoint;
+mod columns;
+mod cow;
+mod cursor;
+pub mod diff;
+mod double;
+mod epoch;
+mod export;
+mod fallback;
+mod faulty;
 mod file_mapped;
+mod free_list;
+mod free_ranges;
+mod history;
+mod journal;
+mod limited;
+mod log;
+mod mapped_reader;
+mod matrix;
+pub mod merge;
+#[cfg(feature = "prometheus")]
+mod metrics;
+mod migrate;
+mod mirrored;
+mod mock;
+mod observer;
+mod pinned;
+mod pressure;
 mod raw_mem;
 mod raw_place;
+mod record_file;
+#[cfg(feature = "registry")]
+pub mod registry;
+#[cfg(not(feature = "registry"))]
+mod registry;
+mod reservation;
+mod savepoint;
+pub mod scratch;
+mod seqlock;
+mod sharded;
+mod shared;
+mod signal;
+mod slot;
+mod snapshot;
+mod stats;
+mod str_pool;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod trace;
+mod transaction;
+mod unmap_pool;
+mod usage;
 mod utils;
+mod watchdog;
+mod writer;
 
+#[cfg(feature = "prometheus")]
+pub use metrics::MeteredMem;
+#[cfg(feature = "prometheus")]
+pub use prometheus;
 pub(crate) use raw_place::RawPlace;
+#[cfg(feature = "rayon")]
+pub use rayon;
+#[cfg(feature = "rkyv")]
+pub use rkyv;
 pub use {
     alloc::Alloc,
-    file_mapped::FileMapped,
-    raw_mem::{ErasedMem, Error, RawMem, Result},
+    append::{AppendMem, Appender},
+    backup::BackupSet,
+    bits::BitMem,
+    blob::{BlobArena, BlobOffset},
+    budget::{Budget, Budgeted},
+    bytemuck,
+    cached::{Cached, FillPolicy, WritePolicy},
+    chain::Chain,
+    channel::ShmChannel,
+    columns::{ColumnSet, Columns},
+    cow::{CowMem, CowSnapshot},
+    cursor::MemCursor,
+    double::DoubleBuffer,
+    epoch::{EpochGuard, EpochMem, EpochReader},
+    fallback::Fallback,
+    faulty::{Fault, FaultyMem},
+    file_mapped::{FileMapped, MappingToken},
+    free_list::{FitPolicy, FreeListMem},
+    history::{HistoryMem, Record},
+    journal::Journaled,
+    limited::Limited,
+    log::LogMem,
+    mapped_reader::{MappedRead, MappedReader},
+    matrix::Matrix2D,
+    migrate::Migrations,
+    mirrored::{MirrorPolicy, Mirrored},
+    mock::{Call, MockMem},
+    observer::MemObserver,
+    pinned::Pinned,
+    pressure::{Pressure, PressureHub, Subscription},
+    raw_mem::{ErasedFill, ErasedMem, Error, ErrorKind, RawMem, Result, StableMem, PROGRESS_STEP},
+    record_file::{RecordFile, RecordId},
+    reservation::Reservation,
+    savepoint::{Pop, SavePoints},
+    seqlock::SeqlockMem,
+    sharded::ShardedMem,
+    shared::{ReadGuard, Reader, Shared, Writer},
+    signal::Signal,
+    slot::{Slot, SlotKey, SlotMem},
+    snapshot::Snapshot,
+    stats::{Profile, Stats, StatsMem},
+    str_pool::{StrHandle, StrPool},
+    transaction::{Transaction, Transactional},
+    usage::MemoryUsage,
+    writer::MemWriter,
 };
 
 fn _assertion() {
     fn assert_sync_send<T: Sync + Send>() {}
+    fn assert_send<T: Send>() {}
 
     assert_sync_send::<FileMapped<()>>();
+    assert_sync_send::<MappedReader<()>>();
     assert_sync_send::<Alloc<(), std::alloc::Global>>();
+    assert_sync_send::<Shared<FileMapped<()>>>();
+    assert_sync_send::<AppendMem<Global<()>>>();
+    assert_send::<Appender<Global<()>>>();
 }
 
 macro_rules! delegate_memory {
@@ -76,9 +246,84 @@ macro_rules! delegate_memory {
                 fn size_hint(&self) -> Option<usize> {
                     self.0.size_hint()
                 }
+
+                fn generation(&self) -> u64 {
+                    self.0.generation()
+                }
+
+                fn prefetch<R: std::ops::RangeBounds<usize>>(&self, range: R) {
+                    self.0.prefetch(range)
+                }
+
+                fn memory_usage(&self) -> MemoryUsage {
+                    self.0.memory_usage()
+                }
             }
 
-            impl<T> fmt::Debug for $me<$param> {
+            // SAFETY: inner memory is stable
+            unsafe impl<$param> StableMem for $me<$param> {}
+
+            impl<$param> $me<$param> {
+                /// Replaces the observer of memory events
+                pub fn set_observer(&mut self, observer: impl MemObserver + 'static) {
+                    self.0.set_observer(observer)
+                }
+
+                pub fn take_observer(&mut self) -> Option<Box<dyn MemObserver>> {
+                    self.0.take_observer()
+                }
+
+                /// Names the memory in `Debug` output, errors, tracing spans and the registry
+                pub fn with_label(self, label: &str) -> Self {
+                    Self(self.0.with_label(label))
+                }
+
+                pub fn label(&self) -> Option<&str> {
+                    self.0.label()
+                }
+
+                /// Keeps place of shrunk items for the next grows,
+                /// until more than `max_slack` items are kept
+                pub fn with_deferred_shrink(self, max_slack: usize) -> Self {
+                    Self(self.0.with_deferred_shrink(max_slack))
+                }
+
+                /// Makes shrinks and drop of the memory skip dropping of items
+                pub fn with_manual_drop(self) -> Self {
+                    Self(self.0.with_manual_drop())
+                }
+
+                /// Releases the place kept by deferred shrinks
+                pub fn compact(&mut self) -> Result<()> {
+                    self.0.compact()
+                }
+
+                /// Gives unused pages of the place kept by deferred shrinks
+                /// or reservations back to the OS, and returns their size in bytes
+                pub fn trim(&mut self) -> Result<usize> {
+                    self.0.trim()
+                }
+
+                /// Does the backend work of growing by `addition` items
+                /// without making them visible until [`commit`](Self::commit)
+                pub fn begin_grow(&mut self, addition: usize) -> Result<Reservation> {
+                    self.0.begin_grow(addition)
+                }
+
+                /// Initializes reserved place by `fill` like [`RawMem::grow`]
+                ///
+                /// # Safety
+                /// Same as [`RawMem::grow`], `reservation` must come from this memory
+                pub unsafe fn commit(
+                    &mut self,
+                    reservation: Reservation,
+                    fill: impl FnOnce(usize, (&mut [$param], &mut [MaybeUninit<$param>])),
+                ) -> Result<&mut [$param]> {
+                    self.0.commit(reservation, fill)
+                }
+            }
+
+            impl<T: fmt::Debug> fmt::Debug for $me<$param> {
                 fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
                     f.debug_tuple(stringify!($me)).field(&self.0).finish()
                 }
@@ -100,11 +345,19 @@ delegate_memory! {
         pub const fn new() -> Self {
             Self(Alloc::new(GlobalAlloc))
         }
+
+        pub fn with_huge_pages(self, threshold: usize) -> Self {
+            Self(self.0.with_huge_pages(threshold))
+        }
     }
    System<T>(Alloc<T, SystemAlloc>) {
        pub const fn new() -> Self {
            Self(Alloc::new(SystemAlloc))
        }
+
+       pub fn with_huge_pages(self, threshold: usize) -> Self {
+           Self(self.0.with_huge_pages(threshold))
+       }
    }
    TempFile<T>(FileMapped<T>) {
        pub fn new() -> io::Result<Self> {
@@ -115,6 +368,14 @@ delegate_memory! {
            Self::from_temp(tempfile::tempfile_in(path))
        }
 
+       pub fn with_high_water_mark(self) -> Self {
+           Self(self.0.with_high_water_mark())
+       }
+
+       pub fn with_unmap_pool(self) -> Self {
+           Self(self.0.with_unmap_pool())
+       }
+
        fn from_temp(file: io::Result<File>) -> io::Result<Self> {
            file.and_then(FileMapped::new).map(Self)
        }
@@ -0,0 +1,100 @@
+use {
+    crate::{Error::QuotaExceeded, MemoryUsage, RawMem, Result, StableMem},
+    std::{
+        fmt::{self, Formatter},
+        mem::{self, MaybeUninit},
+    },
+};
+
+/// Memory which never grows beyond `limit` items.
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use platform_mem::{Error, Global, Limited, RawMem};
+///
+/// let mut mem = Limited::with_max_bytes(Global::<u64>::new(), 800);
+/// mem.grow_filled(60, 0)?;
+///
+/// let err = mem.grow_filled(50, 0).unwrap_err();
+/// assert_eq!(err, Error::QuotaExceeded { limit: 100, requested: 110 });
+/// assert_eq!(mem.allocated().len(), 60);
+/// # Ok::<_, platform_mem::Error>(())
+/// ```
+pub struct Limited<M> {
+    mem: M,
+    limit: usize,
+}
+
+impl<M: RawMem> Limited<M> {
+    /// Limits the length of memory in items
+    pub fn new(mem: M, limit: usize) -> Self {
+        Self { mem, limit }
+    }
+
+    /// Limits the length of memory to whole items within `bytes`
+    pub fn with_max_bytes(mem: M, bytes: usize) -> Self {
+        Self::new(mem, bytes.checked_div(mem::size_of::<M::Item>()).unwrap_or(usize::MAX))
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Changes the limit, memory already beyond it is kept
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+    }
+
+    pub fn into_inner(self) -> M {
+        self.mem
+    }
+}
+
+impl<M: RawMem> RawMem for Limited<M> {
+    type Item = M::Item;
+
+    fn allocated(&self) -> &[Self::Item] {
+        self.mem.allocated()
+    }
+
+    fn allocated_mut(&mut self) -> &mut [Self::Item] {
+        self.mem.allocated_mut()
+    }
+
+    unsafe fn grow(
+        &mut self,
+        addition: usize,
+        fill: impl FnOnce(usize, (&mut [Self::Item], &mut [MaybeUninit<Self::Item>])),
+    ) -> Result<&mut [Self::Item]> {
+        let requested = self.mem.allocated().len().saturating_add(addition);
+        if requested > self.limit {
+            return Err(QuotaExceeded { limit: self.limit, requested });
+        }
+        self.mem.grow(addition, fill)
+    }
+
+    fn shrink(&mut self, cap: usize) -> Result<()> {
+        self.mem.shrink(cap)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.mem.size_hint()
+    }
+
+    fn generation(&self) -> u64 {
+        self.mem.generation()
+    }
+
+    fn memory_usage(&self) -> MemoryUsage {
+        self.mem.memory_usage()
+    }
+}
+
+// SAFETY: items are only in the inner memory
+unsafe impl<M: StableMem> StableMem for Limited<M> {}
+
+impl<M: fmt::Debug> fmt::Debug for Limited<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Limited").field("mem", &self.mem).field("limit", &self.limit).finish()
+    }
+}
@@ -0,0 +1,113 @@
+use {
+    crate::{utils, RawMem, Result},
+    std::fmt::{self, Formatter},
+};
+
+// payload length before and checksum after the payload
+const LEN: usize = 8;
+const SUM: usize = 8;
+
+/// Append-only log of byte entries over a byte memory.
+///
+/// Every entry is prefixed with its length and followed by the checksum of both,
+/// so [`open`] can cut off an entry torn by a crash together with everything after it.
+///
+/// ```
+/// use platform_mem::{FileMapped, LogMem};
+///
+/// let path = tempfile::NamedTempFile::new()?.into_temp_path();
+/// let mut log = LogMem::open(FileMapped::from_path(&path)?)?;
+/// log.append(b"hello")?;
+/// log.append(b"world")?;
+/// drop(log);
+///
+/// let log = LogMem::open(FileMapped::from_path(&path)?)?;
+/// assert_eq!(log.iter().collect::<Vec<_>>(), [b"hello", b"world"]);
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+///
+/// [`open`]: Self::open
+pub struct LogMem<M> {
+    mem: M,
+    len: usize,
+}
+
+impl<M: RawMem<Item = u8>> LogMem<M> {
+    /// Reads entries of `mem` and shrinks it to the last valid one.
+    /// Empty memory is grown to its [`size_hint`](RawMem::size_hint) first,
+    /// e.g. to the data of a reopened file.
+    pub fn open(mut mem: M) -> Result<Self> {
+        if mem.allocated().is_empty() {
+            if let Some(stored) = mem.size_hint() {
+                // SAFETY: zeroed bytes are valid, stored ones are kept
+                unsafe { mem.grow_zeroed_exact(stored)? };
+            }
+        }
+
+        let (mut len, mut valid) = (0, 0);
+        while let Some(entry) = parse(&mem.allocated()[valid..]) {
+            valid += LEN + entry.len() + SUM;
+            len += 1;
+        }
+        let torn = mem.allocated().len() - valid;
+        mem.shrink(torn)?;
+        Ok(Self { mem, len })
+    }
+
+    /// Count of entries
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Appends the entry and returns its offset in the memory
+    pub fn append(&mut self, entry: &[u8]) -> Result<usize> {
+        let offset = self.mem.allocated().len();
+
+        let mut record = Vec::with_capacity(LEN + entry.len() + SUM);
+        record.extend_from_slice(&(entry.len() as u64).to_le_bytes());
+        record.extend_from_slice(entry);
+        record.extend_from_slice(&utils::checksum(&record).to_le_bytes());
+
+        self.mem.grow_from_slice(&record)?;
+        self.len += 1;
+        Ok(offset)
+    }
+
+    /// Entries from the oldest to the newest
+    pub fn iter(&self) -> impl Iterator<Item = &[u8]> {
+        let mut rest = self.mem.allocated();
+        std::iter::from_fn(move || {
+            let entry = parse(rest)?;
+            rest = &rest[LEN + entry.len() + SUM..];
+            Some(entry)
+        })
+    }
+
+    pub fn inner(&self) -> &M {
+        &self.mem
+    }
+
+    pub fn into_inner(self) -> M {
+        self.mem
+    }
+}
+
+fn parse(log: &[u8]) -> Option<&[u8]> {
+    let mut len = [0; LEN];
+    len.copy_from_slice(log.get(..LEN)?);
+    let end = LEN.checked_add(usize::try_from(u64::from_le_bytes(len)).ok()?)?;
+
+    let mut sum = [0; SUM];
+    sum.copy_from_slice(log.get(end..end.checked_add(SUM)?)?);
+    (utils::checksum(&log[..end]) == u64::from_le_bytes(sum)).then(|| &log[LEN..end])
+}
+
+impl<M: fmt::Debug> fmt::Debug for LogMem<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LogMem").field("len", &self.len).field("mem", &self.mem).finish()
+    }
+}
@@ -0,0 +1,161 @@
+use {
+    memmap2::{Mmap, MmapOptions},
+    std::{
+        fmt::{self, Formatter},
+        fs::File,
+        io,
+        marker::PhantomData,
+        mem,
+        ptr::NonNull,
+        sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard},
+    },
+};
+
+/// Length of the writer which is visible to readers and the count of its changes
+#[derive(Debug)]
+pub(crate) struct Published {
+    len: usize,
+    generation: u64,
+}
+
+impl Published {
+    pub(crate) fn set(&mut self, len: usize) {
+        self.len = len;
+        self.generation += 1;
+    }
+}
+
+/// Lock which is shared by the writer and all readers of a file
+#[derive(Debug)]
+pub(crate) struct Coordination {
+    state: RwLock<Published>,
+}
+
+impl Coordination {
+    pub(crate) fn new(len: usize) -> Self {
+        Self { state: RwLock::new(Published { len, generation: 0 }) }
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Published> {
+        self.state.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Waits for guards of readers, so the file can be truncated under it
+    pub(crate) fn write(&self) -> RwLockWriteGuard<'_, Published> {
+        self.state.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Handle with its own read-only mapping of the file of a [`FileMapped`],
+/// created by [`FileMapped::reader`].
+///
+/// The writer publishes its length after every grow and shrink, and it truncates
+/// the file only while no [`read`](Self::read) guard is alive, so readers map
+/// the file again when it's changed instead of touching pages past its end.
+///
+/// The writer still changes items while they are read, so readers get only
+/// volatile copies of them, like items written by another process.
+/// After the writer is dropped or turned into a token, readers see no items.
+///
+/// ```
+/// use {platform_mem::{FileMapped, RawMem}, std::thread};
+///
+/// let mut mem = FileMapped::<u64>::new(tempfile::tempfile()?)?;
+/// mem.grow_filled(1_000, 1)?;
+/// let mut reader = mem.reader()?;
+///
+/// let sum = thread::spawn(move || reader.read().map(|items| items.iter().sum::<u64>()));
+/// mem.grow_filled(1_000_000, 2)?; // reader sees either length
+///
+/// assert!([1_000, 2_001_000].contains(&sum.join().unwrap()?));
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+///
+/// [`FileMapped`]: crate::FileMapped
+/// [`FileMapped::reader`]: crate::FileMapped::reader
+pub struct MappedReader<T> {
+    file: File,
+    coordination: Arc<Coordination>,
+    mmap: Option<Mmap>,
+    /// Generation of the published state which is mapped
+    generation: Option<u64>,
+    _marker: PhantomData<*const T>,
+}
+
+// SAFETY: reader only gives copies of items which are owned by the writer
+unsafe impl<T: Send> Send for MappedReader<T> {}
+unsafe impl<T: Send> Sync for MappedReader<T> {}
+
+impl<T> MappedReader<T> {
+    pub(crate) fn new(file: File, coordination: Arc<Coordination>) -> Self {
+        Self { file, coordination, mmap: None, generation: None, _marker: PhantomData }
+    }
+
+    /// Items published by the writer, its grows wait for the guard only to publish,
+    /// shrinks wait for it to drop the removed items
+    pub fn read(&mut self) -> io::Result<MappedRead<'_, T>> {
+        let state = self.coordination.read();
+        if self.generation != Some(state.generation) {
+            let bytes = state.len * mem::size_of::<T>();
+            self.mmap = None;
+            if bytes > 0 {
+                // SAFETY: the file isn't truncated while the guard is alive
+                self.mmap = Some(unsafe { MmapOptions::new().len(bytes).map(&self.file)? });
+            }
+            self.generation = Some(state.generation);
+        }
+
+        let ptr = self.mmap.as_ref().map_or(NonNull::dangling(), |mmap| {
+            NonNull::from(&mmap[..]).cast() // mappings are aligned to pages
+        });
+        // the writer initialized `len` items and doesn't drop them under the guard
+        Ok(MappedRead { ptr, len: state.len, _state: state })
+    }
+}
+
+impl<T> fmt::Debug for MappedReader<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MappedReader")
+            .field("file", &self.file)
+            .field("mmap", &self.mmap)
+            .field("generation", &self.generation)
+            .finish()
+    }
+}
+
+/// Guard of items which are read by [`MappedReader::read`], it gives their copies
+/// made by volatile reads, because the writer may change them at the same time
+pub struct MappedRead<'a, T> {
+    ptr: NonNull<T>,
+    len: usize,
+    _state: RwLockReadGuard<'a, Published>,
+}
+
+impl<T: Copy> MappedRead<'_, T> {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<T> {
+        // SAFETY: `index` is within initialized items, no reference to them is created
+        (index < self.len).then(|| unsafe { self.ptr.add(index).read_volatile() })
+    }
+
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = T> + '_ {
+        (0..self.len).map(|index| unsafe { self.ptr.add(index).read_volatile() })
+    }
+
+    pub fn to_vec(&self) -> Vec<T> {
+        self.iter().collect()
+    }
+}
+
+impl<T: Copy + fmt::Debug> fmt::Debug for MappedRead<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("MappedRead").field(&self.to_vec()).finish()
+    }
+}
@@ -0,0 +1,135 @@
+use {
+    crate::{RawMem, Result},
+    std::{
+        fmt::{self, Formatter},
+        ops::{Index, IndexMut},
+    },
+};
+
+/// Row-major matrix view over a memory, which grows and shrinks by whole rows.
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use platform_mem::{Global, Matrix2D};
+///
+/// let mut matrix = Matrix2D::new(Global::new(), 3);
+/// matrix.push_row(&[1, 2, 3])?;
+/// matrix.grow_rows_filled(2, 0)?;
+/// matrix[(2, 1)] = 5;
+///
+/// assert_eq!(matrix.rows(), 3);
+/// assert_eq!(matrix.row(2), [0, 5, 0]);
+/// assert_eq!(matrix.get(0, 2), Some(&3));
+/// assert_eq!(matrix.get(0, 3), None);
+/// # Ok::<_, platform_mem::Error>(())
+/// ```
+pub struct Matrix2D<M> {
+    mem: M,
+    cols: usize,
+}
+
+impl<M: RawMem> Matrix2D<M> {
+    /// # Panics
+    /// Panics if `cols` is zero or allocated items are not whole rows.
+    pub fn new(mem: M, cols: usize) -> Self {
+        assert!(cols != 0, "matrix must have columns");
+        assert_eq!(mem.allocated().len() % cols, 0, "allocated items are not whole rows");
+        Self { mem, cols }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.mem.allocated().len() / self.cols
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    fn index(&self, row: usize, col: usize) -> Option<usize> {
+        if col < self.cols {
+            row.checked_mul(self.cols)?.checked_add(col)
+        } else {
+            None
+        }
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Option<&M::Item> {
+        self.mem.allocated().get(self.index(row, col)?)
+    }
+
+    pub fn get_mut(&mut self, row: usize, col: usize) -> Option<&mut M::Item> {
+        let index = self.index(row, col)?;
+        self.mem.allocated_mut().get_mut(index)
+    }
+
+    /// # Panics
+    /// Panics if `row` is out of bounds.
+    pub fn row(&self, row: usize) -> &[M::Item] {
+        &self.mem.allocated()[row * self.cols..][..self.cols]
+    }
+
+    /// # Panics
+    /// Panics if `row` is out of bounds.
+    pub fn row_mut(&mut self, row: usize) -> &mut [M::Item] {
+        &mut self.mem.allocated_mut()[row * self.cols..][..self.cols]
+    }
+
+    pub fn iter_rows(&self) -> impl ExactSizeIterator<Item = &[M::Item]> {
+        self.mem.allocated().chunks_exact(self.cols)
+    }
+
+    /// # Panics
+    /// Panics if `row` has other length than [`cols`](Self::cols).
+    pub fn push_row(&mut self, row: &[M::Item]) -> Result<&mut [M::Item]>
+    where
+        M::Item: Clone,
+    {
+        assert_eq!(row.len(), self.cols, "row length differs from count of columns");
+        self.mem.grow_from_slice(row)
+    }
+
+    pub fn grow_rows_filled(&mut self, rows: usize, value: M::Item) -> Result<&mut [M::Item]>
+    where
+        M::Item: Clone,
+    {
+        let items = rows.checked_mul(self.cols).ok_or(crate::Error::CapacityOverflow)?;
+        self.mem.grow_filled(items, value)
+    }
+
+    /// Removes last `rows` rows
+    ///
+    /// # Panics
+    /// Panics if there are fewer rows.
+    pub fn shrink_rows(&mut self, rows: usize) -> Result<()> {
+        assert!(rows <= self.rows(), "Tried to shrink to a larger capacity");
+        self.mem.shrink(rows * self.cols)
+    }
+
+    pub fn inner(&self) -> &M {
+        &self.mem
+    }
+
+    pub fn into_inner(self) -> M {
+        self.mem
+    }
+}
+
+impl<M: RawMem> Index<(usize, usize)> for Matrix2D<M> {
+    type Output = M::Item;
+
+    fn index(&self, (row, col): (usize, usize)) -> &Self::Output {
+        self.get(row, col).expect("matrix index is out of bounds")
+    }
+}
+
+impl<M: RawMem> IndexMut<(usize, usize)> for Matrix2D<M> {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut Self::Output {
+        self.get_mut(row, col).expect("matrix index is out of bounds")
+    }
+}
+
+impl<M: fmt::Debug> fmt::Debug for Matrix2D<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Matrix2D").field("cols", &self.cols).field("mem", &self.mem).finish()
+    }
+}
@@ -0,0 +1,99 @@
+use {
+    crate::{diff::diff, RawMem, Result},
+    std::{io, ops::Range},
+};
+
+/// Range changed differently by both sides of [`RawMem::merge_from`]
+#[derive(Debug)]
+pub struct Conflict<'a, T> {
+    pub range: Range<usize>,
+    pub base: &'a [T],
+    pub ours: &'a [T],
+    pub theirs: &'a [T],
+}
+
+/// How to resolve a [`Conflict`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Resolution<T> {
+    Ours,
+    Theirs,
+    /// Elements to write into the conflicting range, must have the same length
+    Custom(Vec<T>),
+}
+
+pub(crate) fn merge_from<M, T>(
+    ours: &mut M,
+    base: &[T],
+    theirs: &[T],
+    mut resolver: impl FnMut(&Conflict<'_, T>) -> Resolution<T>,
+) -> Result<()>
+where
+    M: RawMem<Item = T> + ?Sized,
+    T: Clone + PartialEq,
+{
+    let len = ours.allocated().len();
+    let target = if len == base.len() {
+        theirs.len()
+    } else if theirs.len() == base.len() {
+        len
+    } else {
+        len.max(theirs.len())
+    };
+
+    let changed = diff(base, ours.allocated());
+    let clip = |range: &Range<usize>, len: usize| range.start.min(len)..range.end.min(len);
+
+    // all resolutions are checked before ours is changed, so a wrong one changes nothing
+    let mut resolved = Vec::new();
+    for range in diff(base, theirs) {
+        let range = clip(&range, target);
+        if range.is_empty() {
+            continue; // it's cut by the shrink to `target`
+        }
+        let overlaps = changed.iter().any(|ch| ch.start < range.end && range.start < ch.end);
+
+        let resolution = if overlaps {
+            resolver(&Conflict {
+                base: &base[clip(&range, base.len())],
+                ours: &ours.allocated()[clip(&range, len)],
+                theirs: &theirs[clip(&range, theirs.len())],
+                range: range.clone(),
+            })
+        } else {
+            Resolution::Theirs
+        };
+        if let Resolution::Custom(items) = &resolution {
+            if items.len() != range.len() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("custom resolution of {range:?} has {} elements", items.len()),
+                )
+                .into());
+            }
+        }
+        resolved.push((range, resolution));
+    }
+
+    // ours is extended by theirs first, so every range below is inside ours
+    if len < target {
+        ours.grow_from_slice(&theirs[len..target])?;
+    }
+
+    for (range, resolution) in resolved {
+        let place = &mut ours.allocated_mut()[range.clone()];
+        match resolution {
+            Resolution::Ours => {}
+            // elements truncated by theirs stay, when ours is longer
+            Resolution::Theirs => {
+                let theirs = &theirs[clip(&range, theirs.len())];
+                place[..theirs.len()].clone_from_slice(theirs);
+            }
+            Resolution::Custom(items) => place.clone_from_slice(&items),
+        }
+    }
+
+    if target < len {
+        ours.shrink(len - target)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,133 @@
+use {
+    crate::{FileMapped, RawMem},
+    prometheus::{Histogram, HistogramOpts, IntCounter, IntGauge, Opts, Registry},
+    std::{
+        fmt::{self, Formatter},
+        io, mem,
+    },
+};
+
+struct Metrics {
+    bytes: IntGauge,
+    grows: IntCounter,
+    shrinks: IntCounter,
+    remaps: IntCounter,
+    flush: Histogram,
+}
+
+/// Memory that reports its size and resizes to a prometheus [`Registry`].
+///
+/// All metrics have the `memory` label with the given name:
+/// - `platform_mem_bytes` gauge of allocated bytes
+/// - `platform_mem_grows_total` and `platform_mem_shrinks_total` counters
+/// - `platform_mem_remaps_total` counter of resizes that moved the memory
+/// - `platform_mem_flush_seconds` histogram of [`FileMapped`] flushes
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use platform_mem::{Global, MeteredMem, RawMem};
+/// use prometheus::Registry;
+///
+/// let registry = Registry::new();
+/// let mut mem = MeteredMem::new(Global::<u64>::new(), "links", &registry)?;
+/// mem.grow_filled(4, 0)?;
+///
+/// let families = registry.gather();
+/// let bytes = families.iter().find(|family| family.get_name() == "platform_mem_bytes").unwrap();
+/// assert_eq!(bytes.get_metric()[0].get_gauge().get_value(), 32.0);
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+pub struct MeteredMem<M> {
+    mem: M,
+    name: String,
+    metrics: Metrics,
+}
+
+impl<M: RawMem> MeteredMem<M> {
+    /// Registers metrics of memory `name`, it fails if they are already registered
+    pub fn new(mem: M, name: &str, registry: &Registry) -> prometheus::Result<Self> {
+        let opts = |metric: &str, help: &str| Opts::new(metric, help).const_label("memory", name);
+
+        let metrics = Metrics {
+            bytes: IntGauge::with_opts(opts("platform_mem_bytes", "Allocated bytes"))?,
+            grows: IntCounter::with_opts(opts("platform_mem_grows_total", "Count of grows"))?,
+            shrinks: IntCounter::with_opts(opts("platform_mem_shrinks_total", "Count of shrinks"))?,
+            remaps: IntCounter::with_opts(opts(
+                "platform_mem_remaps_total",
+                "Count of resizes that moved the memory",
+            ))?,
+            flush: Histogram::with_opts(HistogramOpts::from(opts(
+                "platform_mem_flush_seconds",
+                "Latency of flushes to the file",
+            )))?,
+        };
+        registry.register(Box::new(metrics.bytes.clone()))?;
+        registry.register(Box::new(metrics.grows.clone()))?;
+        registry.register(Box::new(metrics.shrinks.clone()))?;
+        registry.register(Box::new(metrics.remaps.clone()))?;
+        registry.register(Box::new(metrics.flush.clone()))?;
+
+        metrics.bytes.set(mem::size_of_val(mem.allocated()) as i64);
+        Ok(Self { mem, name: name.to_owned(), metrics })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn into_inner(self) -> M {
+        self.mem
+    }
+
+    fn observe(&mut self, counter: fn(&Metrics) -> &IntCounter, old: *const M::Item) {
+        let allocated = self.mem.allocated();
+        if !old.is_null() && !allocated.is_empty() && allocated.as_ptr() != old {
+            self.metrics.remaps.inc();
+        }
+        self.metrics.bytes.set(mem::size_of_val(allocated) as i64);
+        counter(&self.metrics).inc();
+    }
+
+    // empty memory may have any dangling pointer
+    fn old_ptr(&self) -> *const M::Item {
+        match self.mem.allocated() {
+            [] => std::ptr::null(),
+            allocated => allocated.as_ptr(),
+        }
+    }
+}
+
+impl<T> MeteredMem<FileMapped<T>> {
+    /// [`FileMapped::flush`] with its latency recorded
+    pub fn flush(&self) -> io::Result<()> {
+        let _timer = self.metrics.flush.start_timer();
+        self.mem.flush()
+    }
+}
+
+forward_mem! {
+    impl<M> RawMem for MeteredMem<M> {
+        unsafe fn grow(&mut self, addition, fill) {
+            let old = self.old_ptr();
+            self.mem.grow(addition, fill)?;
+            self.observe(|metrics| &metrics.grows, old);
+        }
+
+        fn shrink(&mut self, cap) {
+            let old = self.old_ptr();
+            self.mem.shrink(cap)?;
+            self.observe(|metrics| &metrics.shrinks, old);
+            Ok(())
+        }
+    }
+}
+
+impl<M: fmt::Debug> fmt::Debug for MeteredMem<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MeteredMem")
+            .field("mem", &self.mem)
+            .field("name", &self.name)
+            .field("bytes", &self.metrics.bytes.get())
+            .finish()
+    }
+}
@@ -0,0 +1,166 @@
+use {
+    crate::{checkpoint, FileMapped, RawMem, Result},
+    std::{
+        collections::BTreeMap,
+        ffi::OsString,
+        fmt::{self, Formatter},
+        fs, io,
+        path::{Path, PathBuf},
+    },
+};
+
+type Upgrade = Box<dyn Fn(Vec<u8>) -> io::Result<Vec<u8>>>;
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Upgrades of a file format from version to version.
+///
+/// Files start with a [`HEADER`](Self::HEADER) of the format magic and its
+/// version (little-endian `u32`), then the payload goes. [`open`](Self::open)
+/// upgrades the payload of an older file by the chain of steps, copies the old file
+/// to `<path>.v<version>.bak` and atomically replaces it, only then the file is mapped.
+///
+/// Payload is all bytes after the header, including the zeroed tail which
+/// a mapping leaves in the file, so the format has to know its own length.
+///
+/// ```
+/// use platform_mem::{Migrations, RawMem};
+///
+/// let dir = tempfile::tempdir()?;
+/// let path = dir.path().join("names");
+///
+/// let mut mem = Migrations::new(*b"names\0\0\0", 1).open(&path)?;
+/// mem.grow_from_slice(b"alice")?;
+/// drop(mem);
+///
+/// let migrations = Migrations::new(*b"names\0\0\0", 3)
+///     .step(1, |payload| Ok(payload.to_ascii_uppercase()))
+///     .step(2, |mut payload| {
+///         payload[..5].reverse();
+///         Ok(payload)
+///     });
+///
+/// let mem = migrations.open(&path)?;
+/// assert!(mem.allocated()[Migrations::HEADER..].starts_with(b"ECILA\0"));
+/// assert!(dir.path().join("names.v1.bak").exists());
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+pub struct Migrations {
+    magic: [u8; 8],
+    version: u32,
+    steps: BTreeMap<u32, Upgrade>,
+}
+
+impl Migrations {
+    /// Bytes of the header in front of the payload
+    pub const HEADER: usize = 16;
+
+    /// Migrations of the format up to its current `version`
+    pub fn new(magic: [u8; 8], version: u32) -> Self {
+        Self { magic, version, steps: BTreeMap::new() }
+    }
+
+    /// Registers upgrade of the payload from version `from` to `from + 1`
+    ///
+    /// # Panics
+    /// Panics if `from` is not older than the current version.
+    pub fn step(
+        mut self,
+        from: u32,
+        upgrade: impl Fn(Vec<u8>) -> io::Result<Vec<u8>> + 'static,
+    ) -> Self {
+        assert!(from < self.version, "migration must upgrade to at most the current version");
+        self.steps.insert(from, Box::new(upgrade));
+        self
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    fn header(&self, version: u32) -> [u8; Self::HEADER] {
+        let mut header = [0; Self::HEADER];
+        header[..8].copy_from_slice(&self.magic);
+        header[8..12].copy_from_slice(&version.to_le_bytes());
+        header
+    }
+
+    /// Version of the file, if it's in this format
+    fn version_of(&self, bytes: &[u8]) -> io::Result<u32> {
+        match bytes.split_first_chunk::<{ Self::HEADER }>() {
+            Some((header, _)) if header[..8] == self.magic => {
+                Ok(u32::from_le_bytes([header[8], header[9], header[10], header[11]]))
+            }
+            _ => Err(invalid("file is not in the migrated format".into())),
+        }
+    }
+
+    /// Payload of the file upgraded from `version` to the current one
+    fn upgrade(&self, mut payload: Vec<u8>, version: u32) -> io::Result<Vec<u8>> {
+        for from in version..self.version {
+            let upgrade = self
+                .steps
+                .get(&from)
+                .ok_or_else(|| invalid(format!("no migration from version {from}")))?;
+            payload = upgrade(payload)?;
+        }
+        Ok(payload)
+    }
+
+    fn backup_path(path: &Path, version: u32) -> PathBuf {
+        let mut name = OsString::from(path.as_os_str());
+        name.push(format!(".v{version}.bak"));
+        name.into()
+    }
+
+    /// Creates the file of the current version, or upgrades an older one,
+    /// and maps it with the header. Newer files are rejected.
+    pub fn open<P: AsRef<Path>>(&self, path: P) -> Result<FileMapped<u8>> {
+        let path = path.as_ref();
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err.into()),
+        };
+
+        if bytes.is_empty() {
+            checkpoint::write_atomic(path, &self.header(self.version))?;
+        } else {
+            let version = self.version_of(&bytes)?;
+            if version > self.version {
+                return Err(invalid(format!(
+                    "file version {version} is newer than {}",
+                    self.version
+                ))
+                .into());
+            }
+            if version < self.version {
+                // nothing is written until every step succeeds
+                let payload = self.upgrade(bytes[Self::HEADER..].to_vec(), version)?;
+                fs::copy(path, Self::backup_path(path, version))?;
+                checkpoint::write_atomic(
+                    path,
+                    &[&self.header(self.version)[..], &payload].concat(),
+                )?;
+            }
+        }
+
+        let mut mem = FileMapped::from_path(path)?;
+        let stored = mem.size_hint().unwrap_or_default();
+        // SAFETY: bytes of the file are initialized
+        unsafe { mem.grow_assumed(stored)? };
+        Ok(mem)
+    }
+}
+
+impl fmt::Debug for Migrations {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Migrations")
+            .field("magic", &String::from_utf8_lossy(&self.magic))
+            .field("version", &self.version)
+            .field("steps", &self.steps.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
@@ -0,0 +1,199 @@
+use {
+    crate::{Error, MemoryUsage, RawMem, Result},
+    std::{
+        fmt::{self, Formatter},
+        mem::MaybeUninit,
+    },
+};
+
+/// What [`Mirrored`] does when the mirror fails
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MirrorPolicy {
+    /// Undo the operation on the primary memory and return the error
+    #[default]
+    Fail,
+    /// Stop mirroring, the primary memory works alone since then
+    Detach,
+}
+
+/// Memory that replicates every grow, shrink and write into a mirror.
+///
+/// Grows and shrinks are applied to both memories. Writes through
+/// [`write`](Self::write) reach both at once, while writes through
+/// [`allocated_mut`](RawMem::allocated_mut) reach the mirror at the next
+/// grow, shrink or [`sync`](Self::sync).
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use platform_mem::{Global, Mirrored, MirrorPolicy, RawMem, TempFile};
+///
+/// let mut mem = Mirrored::new(Global::new(), TempFile::new()?, MirrorPolicy::Fail)?;
+/// mem.grow_from_slice(&[1, 2, 3])?;
+/// mem.write(0, 10);
+/// mem.allocated_mut()[1] = 20;
+/// mem.sync();
+///
+/// assert_eq!(mem.mirror().map(|mirror| mirror.allocated()), Some(&[10, 20, 3][..]));
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+pub struct Mirrored<A, B> {
+    primary: A,
+    mirror: Option<B>,
+    policy: MirrorPolicy,
+    /// Primary items may differ from the mirror
+    dirty: bool,
+    detached_by: Option<Error>,
+}
+
+impl<A: RawMem, B: RawMem<Item = A::Item>> Mirrored<A, B>
+where
+    A::Item: Clone,
+{
+    /// Replaces items of `mirror` with the ones of `primary`
+    pub fn new(primary: A, mut mirror: B, policy: MirrorPolicy) -> Result<Self> {
+        mirror.shrink(mirror.allocated().len())?;
+        mirror.grow_from_slice(primary.allocated())?;
+        Ok(Self { primary, mirror: Some(mirror), policy, dirty: false, detached_by: None })
+    }
+
+    pub fn primary(&self) -> &A {
+        &self.primary
+    }
+
+    /// Mirror memory, if it's not detached
+    pub fn mirror(&self) -> Option<&B> {
+        self.mirror.as_ref()
+    }
+
+    /// Error which detached the mirror by [`MirrorPolicy::Detach`]
+    pub fn detached_by(&self) -> Option<&Error> {
+        self.detached_by.as_ref()
+    }
+
+    pub fn into_inner(self) -> (A, Option<B>) {
+        (self.primary, self.mirror)
+    }
+
+    /// Writes `value` into both memories
+    ///
+    /// # Panics
+    /// Panics if `index` is out of allocated bounds.
+    pub fn write(&mut self, index: usize, value: A::Item) {
+        if let Some(mirror) = &mut self.mirror {
+            mirror.allocated_mut()[index] = value.clone();
+        }
+        self.primary.allocated_mut()[index] = value;
+    }
+
+    /// Copies `src` into both memories starting at `start`
+    ///
+    /// # Panics
+    /// Panics if the range is out of allocated bounds.
+    pub fn write_slice(&mut self, start: usize, src: &[A::Item]) {
+        let end = start + src.len();
+        if let Some(mirror) = &mut self.mirror {
+            mirror.allocated_mut()[start..end].clone_from_slice(src);
+        }
+        self.primary.allocated_mut()[start..end].clone_from_slice(src);
+    }
+
+    /// Copies items changed through `allocated_mut` into the mirror
+    pub fn sync(&mut self) {
+        if let (Some(mirror), true) = (&mut self.mirror, self.dirty) {
+            mirror.allocated_mut().clone_from_slice(self.primary.allocated());
+        }
+        self.dirty = false;
+    }
+
+    /// Applies the policy to the mirror error, `undo` reverts the primary memory
+    fn diverged(&mut self, err: Error, undo: impl FnOnce(&mut A) -> Result<()>) -> Result<()> {
+        match self.policy {
+            MirrorPolicy::Fail => {
+                undo(&mut self.primary)?;
+                Err(err)
+            }
+            MirrorPolicy::Detach => {
+                self.mirror = None;
+                self.detached_by = Some(err);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<A: RawMem, B: RawMem<Item = A::Item>> RawMem for Mirrored<A, B>
+where
+    A::Item: Clone,
+{
+    type Item = A::Item;
+
+    fn allocated(&self) -> &[Self::Item] {
+        self.primary.allocated()
+    }
+
+    fn allocated_mut(&mut self) -> &mut [Self::Item] {
+        self.dirty = true;
+        self.primary.allocated_mut()
+    }
+
+    unsafe fn grow(
+        &mut self,
+        addition: usize,
+        fill: impl FnOnce(usize, (&mut [Self::Item], &mut [MaybeUninit<Self::Item>])),
+    ) -> Result<&mut [Self::Item]> {
+        self.sync();
+        self.primary.grow(addition, fill)?;
+
+        let len = self.primary.allocated().len();
+        let mirrored = match &mut self.mirror {
+            Some(mirror) => {
+                mirror.grow_from_slice(&self.primary.allocated()[len - addition..]).map(drop)
+            }
+            None => Ok(()),
+        };
+        if let Err(err) = mirrored {
+            self.diverged(err, |primary| primary.shrink(addition))?;
+        }
+        Ok(&mut self.primary.allocated_mut()[len - addition..])
+    }
+
+    fn shrink(&mut self, cap: usize) -> Result<()> {
+        self.sync();
+        // mirror goes first, so it's restorable from the primary items
+        if let Err(err) = self.mirror.as_mut().map_or(Ok(()), |mirror| mirror.shrink(cap)) {
+            self.diverged(err, |_| Ok(()))?;
+        }
+        if let Err(err) = self.primary.shrink(cap) {
+            if let Some(mirror) = &mut self.mirror {
+                let len = self.primary.allocated().len();
+                let _ = mirror.grow_from_slice(&self.primary.allocated()[len - cap..]);
+            }
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.primary.size_hint()
+    }
+
+    fn generation(&self) -> u64 {
+        self.primary.generation()
+    }
+
+    fn memory_usage(&self) -> MemoryUsage {
+        self.primary.memory_usage()
+    }
+}
+
+impl<A: fmt::Debug, B: fmt::Debug> fmt::Debug for Mirrored<A, B> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Mirrored")
+            .field("primary", &self.primary)
+            .field("mirror", &self.mirror)
+            .field("policy", &self.policy)
+            .field("dirty", &self.dirty)
+            .field("detached_by", &self.detached_by)
+            .finish()
+    }
+}
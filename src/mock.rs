@@ -0,0 +1,133 @@
+use {
+    crate::{Error::CapacityOverflow, RawMem, Result},
+    std::{
+        collections::VecDeque,
+        fmt::{self, Formatter},
+        mem::MaybeUninit,
+        slice,
+    },
+};
+
+/// Call recorded by [`MockMem`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Call {
+    Grow { addition: usize },
+    Shrink { cap: usize },
+}
+
+/// Vector-backed memory that records calls and replays canned responses.
+///
+/// Each `grow` and `shrink` takes the next response pushed by [`respond`]:
+/// `Ok` (or no response at all) performs the call, `Err` is returned as is.
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use platform_mem::{Call, Error, MockMem, RawMem};
+///
+/// let mut mem = MockMem::new();
+/// mem.respond(Ok(()));
+/// mem.respond(Err(Error::CapacityOverflow));
+///
+/// mem.grow_filled(3, 0u8)?;
+/// assert!(mem.grow_filled(usize::MAX, 0).is_err());
+/// mem.shrink(1)?;
+///
+/// assert_eq!(mem.allocated(), [0, 0]);
+/// assert_eq!(
+///     mem.calls(),
+///     [Call::Grow { addition: 3 }, Call::Grow { addition: usize::MAX }, Call::Shrink { cap: 1 }]
+/// );
+/// # Ok::<_, platform_mem::Error>(())
+/// ```
+///
+/// [`respond`]: Self::respond
+pub struct MockMem<T> {
+    items: Vec<T>,
+    calls: Vec<Call>,
+    responses: VecDeque<Result<()>>,
+}
+
+impl<T> MockMem<T> {
+    pub fn new() -> Self {
+        Self { items: Vec::new(), calls: Vec::new(), responses: VecDeque::new() }
+    }
+
+    /// Queues the response for the next `grow` or `shrink`
+    pub fn respond(&mut self, response: Result<()>) {
+        self.responses.push_back(response);
+    }
+
+    /// All calls in order, including failed ones
+    pub fn calls(&self) -> &[Call] {
+        &self.calls
+    }
+
+    pub fn clear_calls(&mut self) {
+        self.calls.clear();
+    }
+
+    fn record(&mut self, call: Call) -> Result<()> {
+        self.calls.push(call);
+        self.responses.pop_front().unwrap_or(Ok(()))
+    }
+}
+
+impl<T> RawMem for MockMem<T> {
+    type Item = T;
+
+    fn allocated(&self) -> &[Self::Item] {
+        &self.items
+    }
+
+    fn allocated_mut(&mut self) -> &mut [Self::Item] {
+        &mut self.items
+    }
+
+    unsafe fn grow(
+        &mut self,
+        addition: usize,
+        fill: impl FnOnce(usize, (&mut [Self::Item], &mut [MaybeUninit<Self::Item>])),
+    ) -> Result<&mut [Self::Item]> {
+        self.record(Call::Grow { addition })?;
+        self.items.try_reserve_exact(addition).map_err(|_| CapacityOverflow)?;
+
+        let len = self.items.len();
+        let ptr = self.items.as_mut_ptr();
+        // SAFETY: `len` items are initialized and `addition` more are reserved,
+        // the slices do not overlap; if `fill` panics, the length is unchanged
+        let (inited, uninit) = unsafe {
+            (
+                slice::from_raw_parts_mut(ptr, len),
+                slice::from_raw_parts_mut(ptr.add(len).cast::<MaybeUninit<T>>(), addition),
+            )
+        };
+        // vector memory is never initialized beyond its length
+        fill(0, (inited, uninit));
+        self.items.set_len(len + addition);
+
+        Ok(&mut self.items[len..])
+    }
+
+    fn shrink(&mut self, cap: usize) -> Result<()> {
+        self.record(Call::Shrink { cap })?;
+        let len = self.items.len().checked_sub(cap).expect("Tried to shrink to a larger capacity");
+        self.items.truncate(len);
+        Ok(())
+    }
+}
+
+impl<T> Default for MockMem<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for MockMem<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockMem")
+            .field("len", &self.items.len())
+            .field("calls", &self.calls)
+            .field("responses", &self.responses.len())
+            .finish()
+    }
+}
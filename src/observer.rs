@@ -0,0 +1,116 @@
+use std::{
+    error::Error,
+    fmt::{self, Formatter},
+};
+
+/// Callbacks on events of a memory backend, see `set_observer` of [`Alloc`] and [`FileMapped`].
+///
+/// Lengths are in items. Callbacks run inline after the event,
+/// so they should be cheap (e.g. update counters or send into a channel).
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use {
+///     platform_mem::{Global, MemObserver, RawMem},
+///     std::sync::{
+///         atomic::{AtomicUsize, Ordering},
+///         Arc,
+///     },
+/// };
+///
+/// struct Peak(Arc<AtomicUsize>);
+///
+/// impl MemObserver for Peak {
+///     fn on_grow(&self, _: usize, new: usize) {
+///         self.0.fetch_max(new, Ordering::Relaxed);
+///     }
+/// }
+///
+/// let peak = Arc::new(AtomicUsize::new(0));
+/// let mut mem = Global::<u64>::new();
+/// mem.set_observer(Peak(peak.clone()));
+///
+/// mem.grow_filled(10, 0)?;
+/// mem.shrink(5)?;
+/// assert_eq!(peak.load(Ordering::Relaxed), 10);
+/// # Ok::<_, platform_mem::Error>(())
+/// ```
+///
+/// [`Alloc`]: crate::Alloc
+/// [`FileMapped`]: crate::FileMapped
+pub trait MemObserver: Send + Sync {
+    fn on_grow(&self, old: usize, new: usize) {
+        let _ = (old, new);
+    }
+
+    fn on_shrink(&self, old: usize, new: usize) {
+        let _ = (old, new);
+    }
+
+    /// Modified memory was written to its storage
+    fn on_flush(&self) {}
+
+    /// Any of the operations above failed
+    fn on_error(&self, err: &(dyn Error + 'static)) {
+        let _ = err;
+    }
+}
+
+/// Optional observer stored inside of backends
+pub(crate) struct Observer(Option<Box<dyn MemObserver>>);
+
+impl Observer {
+    pub const fn none() -> Self {
+        Self(None)
+    }
+
+    pub fn set(&mut self, observer: impl MemObserver + 'static) {
+        self.0 = Some(Box::new(observer));
+    }
+
+    pub fn take(&mut self) -> Option<Box<dyn MemObserver>> {
+        self.0.take()
+    }
+
+    fn notify<E: Error + 'static>(
+        &self,
+        result: Result<(), E>,
+        event: impl FnOnce(&dyn MemObserver),
+    ) -> Result<(), E> {
+        if let Some(observer) = &self.0 {
+            match &result {
+                Ok(()) => event(observer.as_ref()),
+                Err(err) => observer.on_error(err),
+            }
+        }
+        result
+    }
+
+    pub fn grown<E: Error + 'static>(
+        &self,
+        old: usize,
+        new: usize,
+        result: Result<(), E>,
+    ) -> Result<(), E> {
+        self.notify(result, |observer| observer.on_grow(old, new))
+    }
+
+    pub fn shrunk<E: Error + 'static>(
+        &self,
+        old: usize,
+        new: usize,
+        result: Result<(), E>,
+    ) -> Result<(), E> {
+        self.notify(result, |observer| observer.on_shrink(old, new))
+    }
+
+    pub fn flushed<E: Error + 'static>(&self, result: Result<(), E>) -> Result<(), E> {
+        self.notify(result, |observer| observer.on_flush())
+    }
+}
+
+impl fmt::Debug for Observer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.0.is_some() { "Some(..)" } else { "None" })
+    }
+}
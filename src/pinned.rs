@@ -0,0 +1,69 @@
+use {
+    crate::RawMem,
+    std::{
+        fmt::{self, Formatter},
+        marker::PhantomData,
+    },
+};
+
+/// Generation of memory remembered by [`RawMem::pin`]
+///
+/// Unlike a slice it does not borrow memory, so it can outlive grows and shrinks,
+/// but access through it panics if the memory was changed since pinning.
+pub struct Pinned<M: ?Sized> {
+    generation: u64,
+    _marker: PhantomData<fn(&M)>,
+}
+
+impl<M: RawMem + ?Sized> Pinned<M> {
+    pub(crate) fn new(generation: u64) -> Self {
+        Self { generation, _marker: PhantomData }
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Whether the slice of `mem` is still the same as at the moment of pinning
+    pub fn is_valid(&self, mem: &M) -> bool {
+        mem.generation() == self.generation
+    }
+
+    /// # Panics
+    /// Panics if `mem` was grown or shrunk since pinning.
+    pub fn get<'a>(&self, mem: &'a M) -> &'a [M::Item] {
+        self.check(mem);
+        mem.allocated()
+    }
+
+    /// # Panics
+    /// Panics if `mem` was grown or shrunk since pinning.
+    pub fn get_mut<'a>(&self, mem: &'a mut M) -> &'a mut [M::Item] {
+        self.check(mem);
+        mem.allocated_mut()
+    }
+
+    #[track_caller]
+    fn check(&self, mem: &M) {
+        let current = mem.generation();
+        assert_eq!(
+            current, self.generation,
+            "memory was grown or shrunk since pinning: generation {} is now {current}",
+            self.generation
+        );
+    }
+}
+
+impl<M: ?Sized> Clone for Pinned<M> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M: ?Sized> Copy for Pinned<M> {}
+
+impl<M: ?Sized> fmt::Debug for Pinned<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Pinned").field(&self.generation).finish()
+    }
+}
@@ -0,0 +1,193 @@
+#[cfg(target_os = "linux")]
+use std::{
+    fs::File,
+    io::{self, Write},
+    os::fd::AsRawFd,
+    thread,
+    time::Duration,
+};
+use {
+    crate::Shared,
+    std::{
+        fmt::{self, Formatter},
+        sync::{Arc, Mutex, PoisonError, Weak},
+    },
+};
+
+/// How hard memories are asked to give memory back
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Pressure {
+    /// Worth dropping caches and shrinking spare tails
+    Moderate,
+    /// Worth flushing and releasing everything that can be reloaded
+    Critical,
+}
+
+type Callback = Arc<dyn Fn(Pressure) + Send + Sync>;
+
+#[derive(Default)]
+struct Callbacks {
+    next: u64,
+    list: Vec<(u64, Callback)>,
+}
+
+/// Delivers low-memory signals to the memories that subscribed to them.
+///
+/// Signals come from [`notify`] (e.g. an allocator failure or an external monitor)
+/// or on Linux from the kernel pressure stall information, see [`watch_psi`].
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use platform_mem::{Global, Pressure, PressureHub, RawMem, Shared};
+///
+/// let hub = PressureHub::new();
+/// let cache = Shared::new(Global::<u64>::new());
+/// cache.write().grow_filled(100, 0)?;
+///
+/// let _subscription = hub.attach(&cache, |mem, pressure| {
+///     let keep = if pressure == Pressure::Critical { 0 } else { mem.allocated().len() / 2 };
+///     let _ = mem.shrink(mem.allocated().len() - keep);
+/// });
+///
+/// hub.notify(Pressure::Moderate);
+/// assert_eq!(cache.len(), 50);
+/// hub.notify(Pressure::Critical);
+/// assert!(cache.is_empty());
+/// # Ok::<_, platform_mem::Error>(())
+/// ```
+///
+/// [`notify`]: Self::notify
+/// [`watch_psi`]: Self::watch_psi
+#[derive(Clone, Default)]
+pub struct PressureHub {
+    callbacks: Arc<Mutex<Callbacks>>,
+}
+
+impl PressureHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Calls `callback` on every signal until the subscription is dropped
+    #[must_use = "dropping the subscription unsubscribes immediately"]
+    pub fn subscribe(&self, callback: impl Fn(Pressure) + Send + Sync + 'static) -> Subscription {
+        let mut callbacks = self.lock();
+        let id = callbacks.next;
+        callbacks.next += 1;
+        callbacks.list.push((id, Arc::new(callback)));
+        Subscription { id, callbacks: Arc::downgrade(&self.callbacks) }
+    }
+
+    /// Lets `respond` shrink, flush or otherwise release the shared memory on every signal.
+    /// The memory itself is not kept alive by the subscription.
+    #[must_use = "dropping the subscription unsubscribes immediately"]
+    pub fn attach<M>(
+        &self,
+        mem: &Shared<M>,
+        respond: impl Fn(&mut M, Pressure) + Send + Sync + 'static,
+    ) -> Subscription
+    where
+        M: Send + Sync + 'static,
+    {
+        let mem = mem.downgrade();
+        self.subscribe(move |pressure| {
+            if let Some(mem) = mem.upgrade() {
+                respond(&mut mem.write().unwrap_or_else(PoisonError::into_inner), pressure);
+            }
+        })
+    }
+
+    /// Calls every callback on the current thread
+    pub fn notify(&self, pressure: Pressure) {
+        // callbacks may subscribe or unsubscribe, so they run without the lock
+        let list: Vec<_> = self.lock().list.iter().map(|(_, callback)| callback.clone()).collect();
+        for callback in list {
+            callback(pressure);
+        }
+    }
+
+    /// Count of live subscriptions
+    pub fn subscribers(&self) -> usize {
+        self.lock().list.len()
+    }
+
+    /// Registers a trigger on `/proc/pressure/memory` and notifies [`Pressure::Moderate`]
+    /// whenever tasks stall on memory for `stall` within `window` ([`Pressure::Critical`]
+    /// if all tasks stall). It's done by a background thread which stops with the last hub.
+    ///
+    /// Kernel requires `window` between 500ms and 10s.
+    #[cfg(target_os = "linux")]
+    pub fn watch_psi(&self, stall: Duration, window: Duration) -> io::Result<()> {
+        let mut triggers = Vec::with_capacity(2);
+        for (kind, pressure) in [("some", Pressure::Moderate), ("full", Pressure::Critical)] {
+            let mut file = File::options().read(true).write(true).open("/proc/pressure/memory")?;
+            // trigger must be written in one call and lives while the file is open
+            file.write_all(
+                format!("{kind} {} {}\0", stall.as_micros(), window.as_micros()).as_bytes(),
+            )?;
+            triggers.push((file, pressure));
+        }
+
+        let hub = Arc::downgrade(&self.callbacks);
+        thread::Builder::new().name("psi-watch".into()).spawn(move || {
+            let mut fds: Vec<_> = triggers
+                .iter()
+                .map(|(file, _)| libc::pollfd {
+                    fd: file.as_raw_fd(),
+                    events: libc::POLLPRI,
+                    revents: 0,
+                })
+                .collect();
+            loop {
+                // wake up once in a while to notice that all hubs are gone
+                let ready = unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as _, 1000) };
+                let Some(callbacks) = hub.upgrade() else { break };
+                if ready < 0 && io::Error::last_os_error().kind() != io::ErrorKind::Interrupted {
+                    break;
+                }
+                let hub = PressureHub { callbacks };
+                for (fd, &(_, pressure)) in fds.iter().zip(&triggers).rev() {
+                    if fd.revents & libc::POLLERR != 0 {
+                        return;
+                    }
+                    if fd.revents & libc::POLLPRI != 0 {
+                        hub.notify(pressure);
+                        break; // full stall implies some stall
+                    }
+                }
+            }
+        })?;
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Callbacks> {
+        self.callbacks.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl fmt::Debug for PressureHub {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PressureHub").field("subscribers", &self.subscribers()).finish()
+    }
+}
+
+/// Registration of [`PressureHub::subscribe`], unsubscribes on drop
+pub struct Subscription {
+    id: u64,
+    callbacks: Weak<Mutex<Callbacks>>,
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if let Some(callbacks) = self.callbacks.upgrade() {
+            let mut callbacks = callbacks.lock().unwrap_or_else(PoisonError::into_inner);
+            callbacks.list.retain(|&(id, _)| id != self.id);
+        }
+    }
+}
+
+impl fmt::Debug for Subscription {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Subscription").field(&self.id).finish()
+    }
+}
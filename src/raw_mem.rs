@@ -1,8 +1,19 @@
-use std::{
-    alloc::Layout,
-    mem::MaybeUninit,
-    ops::{Range, RangeBounds},
-    slice,
+use {
+    crate::{
+        checkpoint, diff, export,
+        merge::{self, Conflict, Resolution},
+        utils, MemoryUsage, Pinned, Snapshot,
+    },
+    bytemuck::Pod,
+    std::{
+        alloc::Layout,
+        cell::Cell,
+        fmt, fs, io,
+        mem::{self, MaybeUninit},
+        ops::{Range, RangeBounds},
+        path::{Path, PathBuf},
+        slice::{self, ChunksExactMut},
+    },
 };
 
 /// Error memory allocation
@@ -19,12 +30,10 @@ pub enum Error {
     ///
     /// ```
     /// # #![feature(allocator_api)]
-    /// # #![feature(assert_matches)]
     /// # use std::alloc::Global;
-    /// # use std::assert_matches::assert_matches;
     /// # use platform_mem::{Error, Alloc, RawMem};
     /// let mut mem = Alloc::new(Global);
-    /// assert_matches!(mem.grow_filled(usize::MAX, 0u64), Err(Error::CapacityOverflow));
+    /// assert!(matches!(mem.grow_filled(usize::MAX, 0u64), Err(Error::CapacityOverflow)));
     /// ```
     #[error("exceeding the capacity maximum")]
     CapacityOverflow,
@@ -45,11 +54,150 @@ pub enum Error {
     /// System error memory allocation occurred
     #[error(transparent)]
     System(#[from] std::io::Error),
+
+    /// Grow would exceed the [`Budget`](crate::Budget) of memory
+    #[error("can't grow by {requested} bytes, only {available} bytes of budget available")]
+    BudgetExceeded { requested: usize, available: usize },
+
+    /// Grow would exceed the length limit of [`Limited`](crate::Limited) memory
+    #[error("can't grow to {requested} elements, limited to {limit}")]
+    QuotaExceeded { limit: usize, requested: usize },
+
+    /// Resizing or mapping of the file failed
+    #[error("can't resize {} to {bytes} bytes: {source}", display_path(.path.as_deref()))]
+    File {
+        /// Known if the memory was opened by path
+        path: Option<PathBuf>,
+        bytes: u64,
+        source: io::Error,
+    },
+
+    /// Error of a memory named by `with_label`
+    #[error("`{label}`: {source}")]
+    Labeled { label: String, source: Box<Error> },
+}
+
+fn display_path(path: Option<&Path>) -> String {
+    path.map_or_else(|| "anonymous file".into(), |path| format!("{path:?}"))
+}
+
+/// Category of [`Error`], it sees through wrappers like [`Error::Labeled`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    CapacityOverflow,
+    OverGrow,
+    /// Allocator has no memory
+    AllocFailure,
+    BudgetExceeded,
+    QuotaExceeded,
+    /// Error of the file or the system call with its kind
+    Io(io::ErrorKind),
+}
+
+impl From<Error> for io::Error {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::System(err) => err,
+            err if err.is_alloc_failure() => io::Error::new(io::ErrorKind::OutOfMemory, err),
+            err => match err.kind() {
+                ErrorKind::Io(kind) => io::Error::new(kind, err),
+                _ => io::Error::other(err),
+            },
+        }
+    }
+}
+
+/// Errors are equal if they are the same variant with equal fields,
+/// system errors are compared by [`io::ErrorKind`] and OS error code.
+impl PartialEq for Error {
+    fn eq(&self, other: &Self) -> bool {
+        use Error::*;
+
+        match (self, other) {
+            (CapacityOverflow, CapacityOverflow) => true,
+            (OverGrow { to_grow: a, available: b }, OverGrow { to_grow: c, available: d })
+            | (
+                BudgetExceeded { requested: a, available: b },
+                BudgetExceeded { requested: c, available: d },
+            )
+            | (
+                QuotaExceeded { limit: a, requested: b },
+                QuotaExceeded { limit: c, requested: d },
+            ) => (a, b) == (c, d),
+            (AllocError { layout: a, .. }, AllocError { layout: b, .. }) => a == b,
+            (System(a), System(b)) => io_eq(a, b),
+            (File { path: a, bytes: b, source: c }, File { path: d, bytes: e, source: f }) => {
+                (a, b) == (d, e) && io_eq(c, f)
+            }
+            (Labeled { label: a, source: b }, Labeled { label: c, source: d }) => (a, b) == (c, d),
+            _ => false,
+        }
+    }
+}
+
+fn io_eq(a: &io::Error, b: &io::Error) -> bool {
+    a.kind() == b.kind() && a.raw_os_error() == b.raw_os_error()
+}
+
+impl Error {
+    /// ```
+    /// # #![feature(allocator_api)]
+    /// use platform_mem::{ErrorKind, Global, RawMem};
+    ///
+    /// let mut mem = Global::<u64>::new().with_label("links");
+    /// let err = mem.grow_filled(usize::MAX, 0).unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::CapacityOverflow);
+    /// assert!(err.is_alloc_failure() && !err.is_io());
+    /// ```
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::CapacityOverflow => ErrorKind::CapacityOverflow,
+            Error::OverGrow { .. } => ErrorKind::OverGrow,
+            Error::AllocError { .. } => ErrorKind::AllocFailure,
+            Error::System(err) | Error::File { source: err, .. } => ErrorKind::Io(err.kind()),
+            Error::BudgetExceeded { .. } => ErrorKind::BudgetExceeded,
+            Error::QuotaExceeded { .. } => ErrorKind::QuotaExceeded,
+            Error::Labeled { source, .. } => source.kind(),
+        }
+    }
+
+    /// Memory can't get more bytes: allocator failure, capacity overflow, exceeded budget or quota
+    pub fn is_alloc_failure(&self) -> bool {
+        matches!(
+            self.kind(),
+            ErrorKind::AllocFailure
+                | ErrorKind::CapacityOverflow
+                | ErrorKind::BudgetExceeded
+                | ErrorKind::QuotaExceeded
+        )
+    }
+
+    pub fn is_io(&self) -> bool {
+        matches!(self.kind(), ErrorKind::Io(_))
+    }
+
+    pub(crate) fn labeled(self, label: Option<&str>) -> Self {
+        match label {
+            Some(label) => Self::Labeled { label: label.to_owned(), source: Box::new(self) },
+            None => self,
+        }
+    }
 }
 
 /// Alias for `Result<T, Error>` to return from `RawMem` methods
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// How many items are processed between calls of progress callbacks
+/// of [`retain`](RawMem::retain) and [`compact_ranges`](RawMem::compact_ranges)
+pub const PROGRESS_STEP: usize = 1 << 16;
+
+/// Memory of initialized items which can be grown and shrunk.
+///
+/// Implementors must stay consistent when any method unwinds, e.g. when the `fill`
+/// of [`grow`](Self::grow) panics, [`allocated`](Self::allocated) must still contain
+/// only initialized items and the next calls must work. Wrappers such as
+/// [`Shared`](crate::Shared) rely on it to ignore lock poisoning.
 pub trait RawMem {
     type Item;
 
@@ -58,7 +206,7 @@ pub trait RawMem {
 
     /// # Safety
     /// Caller must guarantee that `fill` makes the uninitialized part valid for
-    /// [`MaybeUninit::slice_assume_init_mut`]
+    /// [`assume_init_mut`](MaybeUninit::assume_init_mut)
     ///
     /// ### Incorrect usage
     /// ```no_run
@@ -85,10 +233,167 @@ pub trait RawMem {
 
     fn shrink(&mut self, cap: usize) -> Result<()>;
 
+    /// Count of items the backing storage already holds, e.g. a reopened file,
+    /// if the memory knows it. It's an upper bound which may include padding
+    /// of the storage, see [`FileMapped`](crate::FileMapped).
     fn size_hint(&self) -> Option<usize> {
         None
     }
 
+    /// Counter which is changed by every operation that may invalidate
+    /// the [allocated](Self::allocated) slice (grow and shrink).
+    /// Backends which do not track it always return `0`.
+    fn generation(&self) -> u64 {
+        0
+    }
+
+    /// Logical, reserved and (if known) resident bytes of the memory.
+    /// By default the memory reserves exactly its items and residency is unknown.
+    ///
+    /// ```
+    /// # #![feature(allocator_api)]
+    /// use platform_mem::{RawMem, TempFile};
+    ///
+    /// let mut mem = TempFile::<u64>::new()?;
+    /// mem.grow_filled(100, 1)?;
+    ///
+    /// let usage = mem.memory_usage();
+    /// assert_eq!(usage.logical, 800);
+    /// assert!(usage.capacity >= 800);
+    /// assert!(usage.resident.unwrap() >= 800); // just written pages are in RAM
+    /// # Ok::<_, Box<dyn std::error::Error>>(())
+    /// ```
+    fn memory_usage(&self) -> MemoryUsage {
+        let logical = mem::size_of_val(self.allocated());
+        MemoryUsage::new(logical, logical, None)
+    }
+
+    /// Remembers current [`generation`](Self::generation) to detect
+    /// slice invalidation at runtime instead of reading dangling memory
+    ///
+    /// # Examples
+    /// ```
+    /// # #![feature(allocator_api)]
+    /// use platform_mem::{Global, RawMem};
+    ///
+    /// let mut mem = Global::new();
+    /// mem.grow_filled(10, 0u8)?;
+    ///
+    /// let pinned = mem.pin();
+    /// assert_eq!(pinned.get(&mem).len(), 10);
+    ///
+    /// mem.grow_filled(10, 0)?;
+    /// assert!(!pinned.is_valid(&mem)); // `pinned.get(&mem)` would panic
+    /// # Ok::<_, platform_mem::Error>(())
+    /// ```
+    fn pin(&self) -> Pinned<Self>
+    where
+        Self: Sized,
+    {
+        Pinned::new(self.generation())
+    }
+
+    /// Views allocated memory as [`Cell`]s, so several structures of one thread
+    /// can share it and still mutate items.
+    ///
+    /// It borrows the memory mutably: cells are the only view of items while
+    /// they are alive, which also keeps the memory from growing or shrinking under them.
+    ///
+    /// ```
+    /// # #![feature(allocator_api)]
+    /// use platform_mem::{Global, RawMem};
+    ///
+    /// let mut mem = Global::new();
+    /// mem.grow_from_slice(&[1, 2, 3])?;
+    ///
+    /// let cells = mem.as_cell_slice();
+    /// let (first, all) = (&cells[0], cells);
+    /// first.set(10);
+    /// all[2].set(all[0].get() + all[1].get());
+    /// assert_eq!(mem.allocated(), [10, 2, 12]);
+    /// # Ok::<_, platform_mem::Error>(())
+    /// ```
+    fn as_cell_slice(&mut self) -> &[Cell<Self::Item>] {
+        Cell::from_mut(self.allocated_mut()).as_slice_of_cells()
+    }
+
+    /// Bounded `Debug` of allocated items: only the first and last ones if there are many
+    /// ```
+    /// # #![feature(allocator_api)]
+    /// use platform_mem::{Global, RawMem};
+    ///
+    /// let mut mem = Global::new();
+    /// mem.grow_filled(100, 7u64)?;
+    /// assert!(format!("{:?}", mem.debug_items()).contains("7, ..84 more, 7"));
+    /// # Ok::<_, platform_mem::Error>(())
+    /// ```
+    fn debug_items(&self) -> impl fmt::Debug + '_
+    where
+        Self::Item: fmt::Debug,
+    {
+        utils::Preview(self.allocated())
+    }
+
+    /// Hexdump of allocated bytes with their ASCII, only the first and last lines
+    /// if there are many
+    /// ```
+    /// # #![feature(allocator_api)]
+    /// use platform_mem::{Global, RawMem};
+    ///
+    /// let mut mem = Global::new();
+    /// mem.grow_from_slice(b"hello world")?;
+    /// let dump = format!("{:?}", mem.hexdump());
+    /// assert!(dump.starts_with("00000000  68 65 6c 6c 6f 20 77 6f 72 6c 64 "));
+    /// assert!(dump.ends_with("|hello world|\n"));
+    /// # Ok::<_, platform_mem::Error>(())
+    /// ```
+    fn hexdump(&self) -> impl fmt::Debug + '_
+    where
+        Self: RawMem<Item = u8>,
+    {
+        utils::Hexdump(self.allocated())
+    }
+
+    /// Allocated items by chunks of `n`, the last `len % n` items are left out
+    /// (they are in the remainder of the iterator).
+    ///
+    /// Chunks borrow the memory mutably, so it can't be grown or shrunk
+    /// while they are alive. Chunk `i` is items `i * n..(i + 1) * n` until
+    /// the next grow or shrink.
+    ///
+    /// # Panics
+    /// Panics if `n` is zero.
+    fn chunks_exact_mut(&mut self, n: usize) -> ChunksExactMut<'_, Self::Item> {
+        self.allocated_mut().chunks_exact_mut(n)
+    }
+
+    /// Parallel iterator over chunks of `n` allocated items (the last may be shorter)
+    /// with the same rules as [`chunks_exact_mut`](Self::chunks_exact_mut)
+    ///
+    /// # Panics
+    /// Panics if `n` is zero.
+    ///
+    /// ```
+    /// # #![feature(allocator_api)]
+    /// use platform_mem::{rayon::prelude::*, RawMem, TempFile};
+    ///
+    /// let mut mem = TempFile::new()?;
+    /// mem.grow_filled(10_000, 1u64)?;
+    ///
+    /// mem.par_chunks_mut(1024).enumerate().for_each(|(i, chunk)| chunk.fill(i as u64));
+    /// assert_eq!(mem.allocated()[9_999], 9);
+    /// # Ok::<_, Box<dyn std::error::Error>>(())
+    /// ```
+    #[cfg(feature = "rayon")]
+    fn par_chunks_mut(&mut self, n: usize) -> rayon::slice::ChunksMut<'_, Self::Item>
+    where
+        Self::Item: Send,
+    {
+        use rayon::slice::ParallelSliceMut;
+
+        self.allocated_mut().par_chunks_mut(n)
+    }
+
     /// [`grow`] which assumes that the memory is already initialized
     ///
     /// # Safety
@@ -169,9 +474,13 @@ pub trait RawMem {
         })
     }
 
+    /// # Safety
+    /// Items which the memory already has in the grown place (e.g. data of a file)
+    /// are kept and only the rest is zeroed, so they must be valid [`Item`](Self::Item)s
     unsafe fn grow_zeroed_exact(&mut self, cap: usize) -> Result<&mut [Self::Item]> {
         self.grow(cap, |inited, (_, uninit)| {
-            uninit.get_unchecked_mut(inited..).as_mut_ptr().write_bytes(0u8, uninit.len());
+            let rest = uninit.get_unchecked_mut(inited..);
+            rest.as_mut_ptr().write_bytes(0u8, rest.len());
         })
     }
 
@@ -187,6 +496,9 @@ pub trait RawMem {
         }
     }
 
+    /// # Safety
+    /// Items which the memory already has in the grown place (e.g. data of a file)
+    /// are kept, so they must be valid [`Item`](Self::Item)s
     unsafe fn grow_with_exact(
         &mut self,
         addition: usize,
@@ -210,6 +522,9 @@ pub trait RawMem {
         }
     }
 
+    /// # Safety
+    /// Items which the memory already has in the grown place (e.g. data of a file)
+    /// are kept, so they must be valid [`Item`](Self::Item)s
     unsafe fn grow_filled_exact(
         &mut self,
         cap: usize,
@@ -225,6 +540,22 @@ pub trait RawMem {
         }
     }
 
+    /// Grows by clones of allocated items of `range`, which is any kind of range
+    ///
+    /// # Panics
+    /// Panics if `range` is out of bounds.
+    ///
+    /// ```
+    /// # #![feature(allocator_api)]
+    /// use platform_mem::{Global, RawMem};
+    ///
+    /// let mut mem = Global::new();
+    /// mem.grow_from_slice(b"ab")?;
+    /// mem.grow_within(..)?;
+    /// mem.grow_within(1..=2)?;
+    /// assert_eq!(mem.allocated(), b"ababba");
+    /// # Ok::<_, platform_mem::Error>(())
+    /// ```
     fn grow_within<R: RangeBounds<usize>>(&mut self, range: R) -> Result<&mut [Self::Item]>
     where
         Self::Item: Clone,
@@ -232,21 +563,348 @@ pub trait RawMem {
         let Range { start, end } = slice::range(range, ..self.allocated().len());
         unsafe {
             self.grow(end - start, |_, (within, uninit)| {
-                MaybeUninit::write_slice_cloned(uninit, &within[start..end]);
+                uninit.write_clone_of_slice(&within[start..end]);
             })
         }
     }
 
+    /// Grows by clones of items of `range` of `other` memory, which may be another backend
+    ///
+    /// # Panics
+    /// Panics if `range` is out of bounds of `other`.
+    ///
+    /// ```
+    /// # #![feature(allocator_api)]
+    /// use platform_mem::{Global, RawMem, TempFile};
+    ///
+    /// let mut file = TempFile::new()?;
+    /// file.grow_from_slice(&[1, 2, 3, 4])?;
+    ///
+    /// let mut mem = Global::new();
+    /// mem.grow_from_mem(&file, 1..3)?;
+    /// mem.grow_from_mem(&file, 3..)?;
+    /// assert_eq!(mem.allocated(), [2, 3, 4]);
+    /// # Ok::<_, Box<dyn std::error::Error>>(())
+    /// ```
+    fn grow_from_mem<M, R>(&mut self, other: &M, range: R) -> Result<&mut [Self::Item]>
+    where
+        M: RawMem<Item = Self::Item> + ?Sized,
+        R: RangeBounds<usize>,
+        Self::Item: Clone,
+    {
+        let range = slice::range(range, ..other.allocated().len());
+        self.grow_from_slice(&other.allocated()[range])
+    }
+
     fn grow_from_slice(&mut self, src: &[Self::Item]) -> Result<&mut [Self::Item]>
     where
         Self::Item: Clone,
     {
         unsafe {
             self.grow(src.len(), |_, (_, uninit)| {
-                MaybeUninit::write_slice_cloned(uninit, src);
+                uninit.write_clone_of_slice(src);
             })
         }
     }
+
+    /// Hints that items of `range` are about to be traversed, so they are
+    /// prefetched into the cache (and read from disk ahead for mapped files).
+    /// It changes nothing observable.
+    ///
+    /// # Panics
+    /// Panics if `range` is out of bounds.
+    ///
+    /// ```
+    /// # #![feature(allocator_api)]
+    /// use platform_mem::{RawMem, TempFile};
+    ///
+    /// let mut mem = TempFile::new()?;
+    /// mem.grow_filled(1 << 20, 0u64)?;
+    ///
+    /// mem.prefetch(1000..);
+    /// assert_eq!(mem.allocated()[1000..].iter().sum::<u64>(), 0);
+    /// # Ok::<_, Box<dyn std::error::Error>>(())
+    /// ```
+    fn prefetch<R: RangeBounds<usize>>(&self, range: R) {
+        let range = slice::range(range, ..self.allocated().len());
+        utils::prefetch(&self.allocated()[range]);
+    }
+
+    /// Keeps only items for which `keep` returns `true`: kept items are moved
+    /// down in place, then the memory is shrunk by count of removed ones, which is returned.
+    ///
+    /// `progress` gets counts of visited and all items every [`PROGRESS_STEP`]
+    /// items and once at the end.
+    ///
+    /// ```
+    /// # #![feature(allocator_api)]
+    /// use platform_mem::{Global, RawMem};
+    ///
+    /// let mut mem = Global::new();
+    /// mem.grow_from_slice(&[1, 2, 3, 4, 5, 6])?;
+    ///
+    /// let mut reported = Vec::new();
+    /// let removed = mem.retain(|&x| x % 3 != 0, |done, total| reported.push((done, total)))?;
+    /// assert_eq!((removed, mem.allocated()), (2, &[1, 2, 4, 5][..]));
+    /// assert_eq!(reported, [(6, 6)]);
+    /// # Ok::<_, platform_mem::Error>(())
+    /// ```
+    ///
+    /// [`PROGRESS_STEP`]: crate::PROGRESS_STEP
+    fn retain(
+        &mut self,
+        mut keep: impl FnMut(&Self::Item) -> bool,
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<usize> {
+        let items = self.allocated_mut();
+        let total = items.len();
+        let mut kept = 0;
+        for i in 0..total {
+            if keep(&items[i]) {
+                items.swap(kept, i);
+                kept += 1;
+            }
+            if (i + 1) % PROGRESS_STEP == 0 && i + 1 != total {
+                progress(i + 1, total);
+            }
+        }
+        progress(total, total);
+        self.shrink(total - kept).map(|_| total - kept)
+    }
+
+    /// Removes `free` ranges of items like [`retain`](Self::retain), e.g. ranges
+    /// freed in [`FreeListMem`](crate::FreeListMem), and returns count of removed items.
+    /// Items behind the ranges are moved down, so their indices change.
+    ///
+    /// # Panics
+    /// Panics if ranges are out of bounds, unsorted or overlapping.
+    ///
+    /// ```
+    /// # #![feature(allocator_api)]
+    /// use platform_mem::{Global, RawMem};
+    ///
+    /// let mut mem = Global::new();
+    /// mem.grow_from_slice(b"hello, big world")?;
+    ///
+    /// let removed = mem.compact_ranges([0..1, 5..10], |_, _| {})?;
+    /// assert_eq!((removed, mem.allocated()), (6, &b"ello world"[..]));
+    /// # Ok::<_, platform_mem::Error>(())
+    /// ```
+    fn compact_ranges(
+        &mut self,
+        free: impl IntoIterator<Item = Range<usize>>,
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<usize> {
+        let items = self.allocated_mut();
+        let total = items.len();
+        let (mut kept, mut read) = (0, 0);
+        let mut moved = |items: &mut [Self::Item], kept: &mut usize, from: Range<usize>| {
+            for i in from {
+                items.swap(*kept, i);
+                *kept += 1;
+                if (i + 1) % PROGRESS_STEP == 0 && i + 1 != total {
+                    progress(i + 1, total);
+                }
+            }
+        };
+        for Range { start, end } in free {
+            assert!(read <= start && start <= end, "free ranges are unsorted or overlapping");
+            assert!(end <= total, "free range is out of bounds");
+            moved(items, &mut kept, read..start);
+            read = end;
+        }
+        moved(items, &mut kept, read..total);
+        progress(total, total);
+        self.shrink(total - kept).map(|_| total - kept)
+    }
+
+    /// Copies allocated memory, so it can be [restored](Self::restore) later
+    /// on this or any other memory
+    fn snapshot(&self) -> Snapshot<Self::Item>
+    where
+        Self::Item: Pod,
+    {
+        Snapshot::new(self.allocated())
+    }
+
+    /// Makes allocated memory equal to the `snapshot`,
+    /// shrinking or growing it when lengths differ
+    ///
+    /// # Examples
+    /// ```
+    /// # #![feature(allocator_api)]
+    /// use platform_mem::{Global, RawMem};
+    ///
+    /// let mut mem = Global::new();
+    /// mem.grow_from_slice(b"hello")?;
+    /// let snapshot = mem.snapshot();
+    ///
+    /// mem.grow_from_slice(b" world")?;
+    /// mem.allocated_mut()[0] = b'j';
+    ///
+    /// mem.restore(&snapshot)?;
+    /// assert_eq!(mem.allocated(), b"hello");
+    /// # Ok::<_, platform_mem::Error>(())
+    /// ```
+    fn restore(&mut self, snapshot: &Snapshot<Self::Item>) -> Result<()>
+    where
+        Self::Item: Pod,
+    {
+        let len = self.allocated().len();
+        if len > snapshot.len() {
+            self.shrink(len - snapshot.len())?;
+        }
+
+        let (common, tail) = snapshot.split_at(self.allocated().len());
+        self.allocated_mut().copy_from_slice(common);
+        self.grow_from_slice(tail).map(drop)
+    }
+
+    /// Ranges of elements that differ from `other` (see [`diff`](crate::diff::diff))
+    ///
+    /// # Examples
+    /// ```
+    /// # #![feature(allocator_api)]
+    /// use platform_mem::{Global, RawMem};
+    ///
+    /// let mut a = Global::new();
+    /// a.grow_from_slice(&[1, 2, 3, 4, 5])?;
+    /// let mut b = Global::new();
+    /// b.grow_from_slice(&[1, 0, 0, 4, 5, 6, 7])?;
+    ///
+    /// assert_eq!(a.diff(&b), [1..3, 5..7]);
+    /// # Ok::<_, platform_mem::Error>(())
+    /// ```
+    fn diff<M: RawMem<Item = Self::Item> + ?Sized>(&self, other: &M) -> Vec<Range<usize>>
+    where
+        Self::Item: PartialEq,
+    {
+        diff::diff(self.allocated(), other.allocated())
+    }
+
+    /// Three-way merge: applies changes of `other` relative to `base` over this memory.
+    /// Ranges changed by both sides are passed to `resolver`.
+    ///
+    /// If both sides changed the length, the longer one wins and the missing
+    /// elements are taken from `other` before resolving conflicts. So if this
+    /// memory is longer, elements truncated by `other` are kept, and its
+    /// side of their conflicts is shorter than the range.
+    ///
+    /// # Errors
+    /// Fails with [`io::ErrorKind::InvalidInput`] if [`Resolution::Custom`] length
+    /// differs from its conflict range, then the memory is left unchanged.
+    ///
+    /// # Examples
+    /// ```
+    /// # #![feature(allocator_api)]
+    /// use platform_mem::{merge::Resolution, Global, RawMem};
+    ///
+    /// let base = [1, 2, 3, 4];
+    /// let mut ours = Global::new();
+    /// ours.grow_from_slice(&[1, 20, 3, 40])?;
+    /// let mut theirs = Global::new();
+    /// theirs.grow_from_slice(&[10, 2, 3, 4, 5])?;
+    ///
+    /// ours.merge_from(&base, &theirs, |_| Resolution::Ours)?;
+    /// assert_eq!(ours.allocated(), [10, 20, 3, 40, 5]);
+    /// # Ok::<_, platform_mem::Error>(())
+    /// ```
+    fn merge_from<M: RawMem<Item = Self::Item> + ?Sized>(
+        &mut self,
+        base: &[Self::Item],
+        other: &M,
+        resolver: impl FnMut(&Conflict<'_, Self::Item>) -> Resolution<Self::Item>,
+    ) -> Result<()>
+    where
+        Self::Item: Clone + PartialEq,
+    {
+        merge::merge_from(self, base, other.allocated(), resolver)
+    }
+
+    /// Atomically replaces file at `path` with allocated memory: it is written to
+    /// a temporary file, synced and renamed, so the file is never observed torn.
+    /// Zero-sized items have no bytes, so only their count is written.
+    fn checkpoint_to_path<P: AsRef<Path>>(&self, path: P) -> Result<()>
+    where
+        Self::Item: Pod,
+    {
+        let items = self.allocated();
+        if mem::size_of::<Self::Item>() == 0 {
+            let count = (items.len() as u64).to_le_bytes();
+            return Ok(checkpoint::write_atomic(path.as_ref(), &count)?);
+        }
+        Ok(checkpoint::write_atomic(path.as_ref(), bytemuck::cast_slice(items))?)
+    }
+
+    /// [`restore`](Self::restore) from the file written by
+    /// [`checkpoint_to_path`](Self::checkpoint_to_path)
+    fn restore_from_path<P: AsRef<Path>>(&mut self, path: P) -> Result<()>
+    where
+        Self::Item: Pod,
+    {
+        let bytes = fs::read(path)?;
+        if mem::size_of::<Self::Item>() == 0 {
+            let count = <[u8; 8]>::try_from(bytes.as_slice())
+                .ok()
+                .and_then(|count| usize::try_from(u64::from_le_bytes(count)).ok())
+                .ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidData, "checkpoint has no item count")
+                })?;
+            return self.restore(&Snapshot::new(vec![bytemuck::Zeroable::zeroed(); count]));
+        }
+        if !bytes.len().is_multiple_of(mem::size_of::<Self::Item>()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "checkpoint size is not a multiple of the item size",
+            )
+            .into());
+        }
+        self.restore(&Snapshot::new(bytemuck::pod_collect_to_vec::<_, Self::Item>(&bytes)))
+    }
+
+    /// Writes allocated memory into a self-describing file at `path`.
+    ///
+    /// Unlike the file behind a [`FileMapped`] it has no page padding and keeps
+    /// the item size, count, endianness and checksum, so it can be moved between
+    /// machines and [`import`](Self::import)ed into any backend. Items are stored
+    /// as they are in memory, so only machines of the same endianness can import it.
+    ///
+    /// ```
+    /// # #![feature(allocator_api)]
+    /// use platform_mem::{Global, RawMem, TempFile};
+    ///
+    /// let dir = tempfile::tempdir()?;
+    /// let path = dir.path().join("links.export");
+    ///
+    /// let mut mem = TempFile::<u64>::new()?;
+    /// mem.grow_from_slice(&[1, 2, 3])?;
+    /// mem.export(&path)?;
+    ///
+    /// let mut imported = Global::<u64>::new();
+    /// imported.import(&path)?;
+    /// assert_eq!(imported.allocated(), [1, 2, 3]);
+    /// assert!(Global::<u32>::new().import(&path).is_err()); // item size mismatch
+    /// # Ok::<_, Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// [`FileMapped`]: crate::FileMapped
+    fn export<P: AsRef<Path>>(&self, path: P) -> Result<()>
+    where
+        Self::Item: Pod,
+    {
+        Ok(checkpoint::write_atomic(path.as_ref(), &export::encode(self.allocated())?)?)
+    }
+
+    /// Replaces allocated memory with the file written by [`export`](Self::export),
+    /// it fails if the file is corrupted or was written for another item type
+    /// or on a machine of another endianness
+    fn import<P: AsRef<Path>>(&mut self, path: P) -> Result<()>
+    where
+        Self::Item: Pod,
+    {
+        let items = export::decode(&fs::read(path)?)?;
+        self.restore(&Snapshot::new(items))
+    }
 }
 
 struct Unique<T>(MaybeUninit<T>);
@@ -271,16 +929,37 @@ impl<A, B, F: FnOnce(A, B)> FnMut<(A, B)> for Unique<F> {
     }
 }
 
+/// Memory whose allocated items keep their address when the memory is moved,
+/// so pointers to them stay valid until it's grown, shrunk or dropped.
+///
+/// Memories which store items inline don't implement it.
+///
+/// # Safety
+/// Moving the memory and calls of [`allocated`](RawMem::allocated) and
+/// [`allocated_mut`](RawMem::allocated_mut) must neither move nor touch its items.
+pub unsafe trait StableMem: RawMem {}
+
+unsafe impl<M: RawMem + ?Sized> StableMem for &mut M {}
+
+/// `fill` of [`RawMem::grow`] which is passed to [`ErasedMem::erased_grow`]
+pub type ErasedFill<'a, T> = dyn FnMut(usize, (&mut [T], &mut [MaybeUninit<T>])) + 'a;
+
+/// Object safe [`RawMem`], which is implemented for all of them
+///
+/// # Safety
+/// Implementations must uphold the contract of [`RawMem`] for the same methods
 pub unsafe trait ErasedMem {
     type Item;
 
     fn erased_allocated(&self) -> &[Self::Item];
     fn erased_allocated_mut(&mut self) -> &mut [Self::Item];
 
+    /// # Safety
+    /// Same as [`RawMem::grow`]
     unsafe fn erased_grow(
         &mut self,
         cap: usize,
-        fill: &mut dyn FnMut(usize, (&mut [Self::Item], &mut [MaybeUninit<Self::Item>])),
+        fill: &mut ErasedFill<'_, Self::Item>,
     ) -> Result<&mut [Self::Item]>;
 
     fn erased_shrink(&mut self, cap: usize) -> Result<()>;
@@ -288,6 +967,12 @@ pub unsafe trait ErasedMem {
     fn erased_size_hint(&self) -> Option<usize> {
         None
     }
+
+    fn erased_generation(&self) -> u64 {
+        0
+    }
+
+    fn erased_memory_usage(&self) -> MemoryUsage;
 }
 
 macro_rules! impl_erased {
@@ -318,6 +1003,14 @@ macro_rules! impl_erased {
             fn size_hint(&self) -> Option<usize> {
                 (**self).erased_size_hint()
             }
+
+            fn generation(&self) -> u64 {
+                (**self).erased_generation()
+            }
+
+            fn memory_usage(&self) -> MemoryUsage {
+                (**self).erased_memory_usage()
+            }
         }
     };
 }
@@ -342,7 +1035,7 @@ unsafe impl<All: RawMem + ?Sized> ErasedMem for All {
     unsafe fn erased_grow(
         &mut self,
         cap: usize,
-        fill: &mut dyn FnMut(usize, (&mut [Self::Item], &mut [MaybeUninit<Self::Item>])),
+        fill: &mut ErasedFill<'_, Self::Item>,
     ) -> Result<&mut [Self::Item]> {
         self.grow(cap, fill)
     }
@@ -354,6 +1047,14 @@ unsafe impl<All: RawMem + ?Sized> ErasedMem for All {
     fn erased_size_hint(&self) -> Option<usize> {
         self.size_hint()
     }
+
+    fn erased_generation(&self) -> u64 {
+        self.generation()
+    }
+
+    fn erased_memory_usage(&self) -> MemoryUsage {
+        self.memory_usage()
+    }
 }
 
 pub mod uninit {
@@ -396,9 +1097,7 @@ pub mod uninit {
             // SAFETY: this raw slice will contain only initialized objects
             // that's why, it is allowed to drop it.
             unsafe {
-                ptr::drop_in_place(MaybeUninit::slice_assume_init_mut(
-                    self.slice.get_unchecked_mut(..self.init),
-                ));
+                ptr::drop_in_place(self.slice.get_unchecked_mut(..self.init).assume_init_mut());
             }
         }
     }
@@ -11,18 +11,50 @@ pub struct RawPlace<T> {
     ptr: NonNull<T>,
     len: usize, // use to drop at panic
     cap: usize, // usually `cap` is same `len`
+    generation: u64,
+    manual_drop: bool, // items are never dropped, e.g. a file is their source of truth
     _marker: PhantomData<T>,
 }
 
 impl<T> RawPlace<T> {
+    /// Zero-sized items are only counted, they are never allocated
+    pub const IS_ZST: bool = mem::size_of::<T>() == 0;
+
     pub const fn dangling() -> Self {
-        Self { ptr: NonNull::dangling(), len: 0, cap: 0, _marker: PhantomData }
+        Self {
+            ptr: NonNull::dangling(),
+            len: 0,
+            cap: 0,
+            generation: 0,
+            manual_drop: false,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn set_manual_drop(&mut self, manual_drop: bool) {
+        self.manual_drop = manual_drop;
+    }
+
+    pub fn manual_drop(&self) -> bool {
+        self.manual_drop
     }
 
     pub fn cap(&self) -> usize {
         self.cap
     }
 
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn ptr(&self) -> NonNull<T> {
+        self.ptr
+    }
+
     pub unsafe fn as_slice(&self) -> &[T] {
         slice::from_raw_parts(self.ptr.as_ptr(), self.len)
     }
@@ -34,9 +66,9 @@ impl<T> RawPlace<T> {
     pub fn current_memory(&self) -> Option<(NonNull<u8>, Layout)> {
         // rust does not support such types,
         // so we can do better by skipping some checks and avoid an unwrap.
-        const { assert!(mem::size_of::<T>() % mem::align_of::<T>() == 0) };
+        const { assert!(mem::size_of::<T>().is_multiple_of(mem::align_of::<T>())) };
 
-        if self.cap == 0 {
+        if self.cap == 0 || Self::IS_ZST {
             None
         } else {
             unsafe {
@@ -52,39 +84,58 @@ impl<T> RawPlace<T> {
     pub unsafe fn handle_fill(
         &mut self,
         (ptr, cap): (NonNull<T>, usize),
+        addition: usize,
         inited: usize,
         fill: impl FnOnce(usize, (&mut [T], &mut [MaybeUninit<T>])),
     ) -> &mut [T] {
-        // fixme: ZST correctness isn't checked now,
-        // it forbid growing, but allow `RawPlace::<ZST>::dangling` and thus `Alloc::<ZST>::new`'s
-        const { assert!(mem::size_of::<T>() != 0) };
-
+        // after a panicking `fill` or a deferred shrink the tail `len..cap` stays
+        // allocated but unused, so new elements are placed right after the initialized ones
+        debug_assert!(self.len + addition <= cap);
         let uninit = NonNull::slice_from_raw_parts(ptr, cap)
-            .get_unchecked_mut(self.cap..)
+            .get_unchecked_mut(self.len..self.len.unchecked_add(addition))
             .as_uninit_slice_mut();
 
         self.ptr = ptr;
         self.cap = cap; // `ptr` and `cap` changes after panicking `fill`
-        //                 ( alloc memory )
+                        //                 ( alloc memory )
+        self.generation += 1;
 
         // slice from `as_slice_mut` will be the initialized part of owned memory
         // while (&mut [T], &mut [MaybeUninit<T>]) will be the full memory
         fill(inited, (self.as_slice_mut(), uninit)); // panic out!
 
-        self.len = cap; // `len` is same `cap` only if `uninit` was init
+        self.len += addition; // `len` is same `cap` only if every `fill` succeeded
 
-        MaybeUninit::slice_assume_init_mut(uninit)
+        uninit.assume_init_mut()
     }
 
     pub fn shrink_to(&mut self, cap: usize) {
         assert!(cap <= self.cap);
 
-        unsafe {
-            ptr::drop_in_place(&mut self.as_slice_mut()[cap..]);
+        // place may move, so the generation changes even if nothing is dropped
+        self.truncate(cap.min(self.len));
+        self.cap = cap;
+    }
+
+    /// Drops elements after `len` but keeps their place allocated
+    pub fn truncate(&mut self, len: usize) {
+        assert!(len <= self.len);
+
+        if !self.manual_drop {
+            unsafe {
+                ptr::drop_in_place(&mut self.as_slice_mut()[len..]);
+            }
         }
 
-        self.cap = cap;
-        self.len = cap;
+        self.len = len;
+        self.generation += 1;
+    }
+
+    /// Drops all elements (unless they are manually dropped) before the place is freed
+    pub unsafe fn drop_items(&mut self) {
+        if !self.manual_drop {
+            ptr::drop_in_place(self.as_slice_mut());
+        }
     }
 
     pub fn set_ptr(&mut self, ptr: NonNull<[u8]>) {
@@ -112,3 +163,16 @@ unsafe impl<T: Send> Send for RawPlace<T> {}
 fn zst_build() {
     let _: RawPlace<()> = RawPlace::dangling();
 }
+
+#[test]
+fn zst_fill() {
+    let mut place: RawPlace<()> = RawPlace::dangling();
+    unsafe {
+        place.handle_fill((NonNull::dangling(), usize::MAX), usize::MAX, 0, |_, (_, uninit)| {
+            assert_eq!(uninit.len(), usize::MAX);
+        });
+    }
+    assert_eq!((place.len(), place.current_memory()), (usize::MAX, None));
+    place.shrink_to(1);
+    assert_eq!(place.len(), 1);
+}
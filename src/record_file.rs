@@ -0,0 +1,170 @@
+use {
+    crate::{FileMapped, RawMem, Result},
+    bytemuck::Pod,
+    std::{
+        fmt::{self, Formatter},
+        io,
+        marker::PhantomData,
+        mem,
+        path::Path,
+    },
+};
+
+const MAGIC: [u8; 8] = *b"pmrecord";
+// magic, record size and count of committed records
+const SIZE: usize = 8;
+const COUNT: usize = 16;
+const HEADER: usize = 24;
+
+/// Place of the file is reserved at least by this count of bytes
+const MIN_RESERVE: usize = 4096;
+
+/// Index of a record in [`RecordFile`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RecordId(pub u64);
+
+/// File of fixed-size records behind a header with their size and count.
+///
+/// A record is committed by writing the count after the record itself, so a torn
+/// append is cut off at the next opening.
+///
+/// ```
+/// use platform_mem::{RecordFile, RecordId};
+///
+/// let path = tempfile::NamedTempFile::new()?.into_temp_path();
+/// let mut file = RecordFile::<[u32; 2]>::open(&path)?;
+/// let id = file.append([1, 2])?;
+/// file.append([3, 4])?;
+/// file.update(id, [5, 6]);
+/// drop(file);
+///
+/// let file = RecordFile::<[u32; 2]>::open(&path)?;
+/// assert_eq!(file.get(RecordId(0)), Some([5, 6]));
+/// assert_eq!(file.iter().collect::<Vec<_>>(), [[5, 6], [3, 4]]);
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+pub struct RecordFile<T> {
+    mem: FileMapped<u8>,
+    len: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Pod> RecordFile<T> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::new(FileMapped::from_path(path)?)
+    }
+
+    /// Reads records stored in the file of `mem` or writes a new header
+    /// if it's empty, `mem` must not be grown yet
+    pub fn new(mut mem: FileMapped<u8>) -> Result<Self> {
+        const { assert!(mem::size_of::<T>() != 0) };
+
+        let stored = mem.size_hint().unwrap_or(0);
+        if stored < HEADER {
+            mem.grow_from_slice(&MAGIC)?;
+            mem.grow_from_slice(&(mem::size_of::<T>() as u64).to_le_bytes())?;
+            mem.grow_from_slice(&0u64.to_le_bytes())?;
+            return Ok(Self { mem, len: 0, _marker: PhantomData });
+        }
+
+        // SAFETY: bytes are always initialized
+        unsafe { mem.grow_assumed(stored)? };
+        let header = &mem.allocated()[..HEADER];
+        let size = word(header, SIZE);
+        if header[..SIZE] != MAGIC || size != mem::size_of::<T>() as u64 {
+            return Err(invalid(format!(
+                "file is not a record file of {}-byte records",
+                mem::size_of::<T>()
+            )));
+        }
+        let len = word(header, COUNT);
+        let len = usize::try_from(len)
+            .ok()
+            .filter(|&len| Self::offset(len).is_some_and(|end| end <= stored))
+            .ok_or_else(|| invalid(format!("file of {stored} bytes can't fit {len} records")))?;
+
+        // cut off reserved place and a torn append
+        mem.shrink(stored - HEADER - len * mem::size_of::<T>())?;
+        Ok(Self { mem, len, _marker: PhantomData })
+    }
+
+    fn offset(index: usize) -> Option<usize> {
+        index.checked_mul(mem::size_of::<T>())?.checked_add(HEADER)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn append(&mut self, record: T) -> Result<RecordId> {
+        let size = mem::size_of::<T>();
+        let used = self.mem.allocated().len();
+        if self.mem.memory_usage().capacity < used + size {
+            // grows are cheap within the reserved place
+            let _ = self.mem.begin_grow(used.max(MIN_RESERVE))?;
+        }
+        self.mem.grow_from_slice(bytemuck::bytes_of(&record))?;
+
+        let id = RecordId(self.len as u64);
+        self.len += 1;
+        self.mem.allocated_mut()[COUNT..HEADER].copy_from_slice(&(self.len as u64).to_le_bytes());
+        Ok(id)
+    }
+
+    fn place(&self, id: RecordId) -> Option<usize> {
+        usize::try_from(id.0).ok().filter(|&index| index < self.len).map(|index| {
+            // SAFETY: offsets of stored records can't overflow
+            unsafe { Self::offset(index).unwrap_unchecked() }
+        })
+    }
+
+    pub fn get(&self, id: RecordId) -> Option<T> {
+        let at = self.place(id)?;
+        Some(bytemuck::pod_read_unaligned(&self.mem.allocated()[at..at + mem::size_of::<T>()]))
+    }
+
+    /// Overwrites the record
+    ///
+    /// # Panics
+    /// Panics if there is no record with `id`.
+    pub fn update(&mut self, id: RecordId, record: T) {
+        let at = self.place(id).expect("record id is out of bounds");
+        self.mem.allocated_mut()[at..at + mem::size_of::<T>()]
+            .copy_from_slice(bytemuck::bytes_of(&record));
+    }
+
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = T> + '_ {
+        self.mem.allocated()[HEADER..]
+            .chunks_exact(mem::size_of::<T>())
+            .map(bytemuck::pod_read_unaligned)
+    }
+
+    /// Writes modified records to the file and waits until it's done
+    pub fn flush(&self) -> io::Result<()> {
+        self.mem.flush()
+    }
+
+    pub fn into_inner(self) -> FileMapped<u8> {
+        self.mem
+    }
+}
+
+fn word(header: &[u8], at: usize) -> u64 {
+    let mut word = [0; 8];
+    word.copy_from_slice(&header[at..at + 8]);
+    u64::from_le_bytes(word)
+}
+
+fn invalid(msg: String) -> crate::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg).into()
+}
+
+impl<T> fmt::Debug for RecordFile<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecordFile").field("len", &self.len).field("mem", &self.mem).finish()
+    }
+}
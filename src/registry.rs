@@ -0,0 +1,169 @@
+//! Process-wide registry of live memories.
+//!
+//! With the `registry` feature [`Alloc`] and [`FileMapped`] register themselves
+//! at the first grow and unregister on drop, so [`live`] can enumerate
+//! them e.g. from an admin endpoint. Without it tracking costs nothing.
+//!
+//! [`Alloc`]: crate::Alloc
+//! [`FileMapped`]: crate::FileMapped
+
+#[cfg(feature = "registry")]
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+};
+
+/// State of a live memory at the moment of [`live`] call
+#[cfg(feature = "registry")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Entry {
+    /// Unique in the process
+    pub id: u64,
+    pub backend: &'static str,
+    /// Set by `with_label` of the backend
+    pub label: Option<String>,
+    pub len: usize,
+    pub bytes: usize,
+    pub grows: u64,
+    pub shrinks: u64,
+}
+
+#[cfg(feature = "registry")]
+struct Slot {
+    backend: &'static str,
+    label: Mutex<Option<Arc<str>>>,
+    len: AtomicUsize,
+    bytes: AtomicUsize,
+    grows: AtomicU64,
+    shrinks: AtomicU64,
+}
+
+#[cfg(feature = "registry")]
+static REGISTRY: Mutex<BTreeMap<u64, Arc<Slot>>> = Mutex::new(BTreeMap::new());
+
+#[cfg(feature = "registry")]
+fn registry() -> std::sync::MutexGuard<'static, BTreeMap<u64, Arc<Slot>>> {
+    REGISTRY.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// All live memories ordered by registration
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use platform_mem::{registry, Global, RawMem};
+///
+/// let mut mem = Global::<u64>::new().with_label("links");
+/// mem.grow_filled(8, 0)?;
+///
+/// let entry = registry::live().into_iter().find(|entry| entry.bytes == 64).unwrap();
+/// assert_eq!((entry.backend, entry.label.as_deref(), entry.len), ("Alloc", Some("links"), 8));
+///
+/// drop(mem);
+/// assert!(registry::live().iter().all(|other| other.id != entry.id));
+/// # Ok::<_, platform_mem::Error>(())
+/// ```
+#[cfg(feature = "registry")]
+pub fn live() -> Vec<Entry> {
+    registry()
+        .iter()
+        .map(|(&id, slot)| Entry {
+            id,
+            backend: slot.backend,
+            label: slot
+                .label
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .as_deref()
+                .map(Into::into),
+            len: slot.len.load(Ordering::Relaxed),
+            bytes: slot.bytes.load(Ordering::Relaxed),
+            grows: slot.grows.load(Ordering::Relaxed),
+            shrinks: slot.shrinks.load(Ordering::Relaxed),
+        })
+        .collect()
+}
+
+/// Registration of a backend, it's empty without the `registry` feature
+pub(crate) struct Tracker {
+    #[cfg(feature = "registry")]
+    slot: Option<(u64, Arc<Slot>)>,
+    #[cfg(feature = "registry")]
+    label: Option<Arc<str>>,
+}
+
+impl Tracker {
+    pub const fn none() -> Self {
+        Self {
+            #[cfg(feature = "registry")]
+            slot: None,
+            #[cfg(feature = "registry")]
+            label: None,
+        }
+    }
+
+    #[cfg(feature = "registry")]
+    fn slot(&mut self, backend: &'static str) -> &Slot {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+
+        let label = &self.label;
+        &self
+            .slot
+            .get_or_insert_with(|| {
+                let id = NEXT.fetch_add(1, Ordering::Relaxed);
+                let slot = Arc::new(Slot {
+                    backend,
+                    label: Mutex::new(label.clone()),
+                    len: AtomicUsize::new(0),
+                    bytes: AtomicUsize::new(0),
+                    grows: AtomicU64::new(0),
+                    shrinks: AtomicU64::new(0),
+                });
+                registry().insert(id, Arc::clone(&slot));
+                (id, slot)
+            })
+            .1
+    }
+
+    /// Registers the memory if it's not yet and updates its length
+    #[cfg_attr(not(feature = "registry"), allow(unused_variables))]
+    pub fn resized<T>(&mut self, backend: &'static str, len: usize, grown: bool) {
+        #[cfg(feature = "registry")]
+        {
+            let slot = self.slot(backend);
+            slot.len.store(len, Ordering::Relaxed);
+            slot.bytes.store(len * std::mem::size_of::<T>(), Ordering::Relaxed);
+            let counter = if grown { &slot.grows } else { &slot.shrinks };
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Labels the memory, even if it's already registered
+    #[cfg_attr(not(feature = "registry"), allow(unused_variables))]
+    pub fn label(&mut self, label: &std::sync::Arc<str>) {
+        #[cfg(feature = "registry")]
+        {
+            if let Some((_, slot)) = &self.slot {
+                *slot.label.lock().unwrap_or_else(PoisonError::into_inner) =
+                    Some(Arc::clone(label));
+            }
+            self.label = Some(Arc::clone(label));
+        }
+    }
+
+    pub fn unregister(&mut self) {
+        #[cfg(feature = "registry")]
+        if let Some((id, _)) = self.slot.take() {
+            registry().remove(&id);
+        }
+    }
+}
+
+impl Drop for Tracker {
+    fn drop(&mut self) {
+        self.unregister();
+    }
+}
@@ -0,0 +1,21 @@
+/// Place for items prepared by `begin_grow` of a backend, see [`Alloc::begin_grow`].
+///
+/// It's cheap to [`commit`](crate::Alloc::commit) while the memory is not resized,
+/// otherwise committing does the backend work again.
+///
+/// [`Alloc::begin_grow`]: crate::Alloc::begin_grow
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[must_use = "reserved place is only used by `commit`"]
+pub struct Reservation {
+    pub(crate) addition: usize,
+    /// Leading items of the reserved place that are already initialized
+    pub(crate) inited: usize,
+    pub(crate) generation: u64,
+}
+
+impl Reservation {
+    /// Count of items that `commit` adds
+    pub fn addition(&self) -> usize {
+        self.addition
+    }
+}
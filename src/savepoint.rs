@@ -0,0 +1,168 @@
+use {
+    crate::{RawMem, Result},
+    std::fmt::{self, Formatter},
+};
+
+enum Undo<T> {
+    Write { offset: usize, old: Vec<T> },
+    Grow { addition: usize },
+    Shrink { removed: Vec<T> },
+}
+
+/// What [`SavePoints::pop_state`] does with changes made since the matching push
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pop {
+    /// Keep the changes, they still can be undone by an outer save point
+    Discard,
+    /// Undo the changes
+    Restore,
+}
+
+/// Memory with a stack of save points for nested rollbacks.
+///
+/// All mutations go through the wrapper, which records only their inverse
+/// (old values of overwritten ranges, removed tails, grown counts),
+/// so save points are cheap regardless of memory size.
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use platform_mem::{Global, Pop, SavePoints};
+///
+/// let mut mem = SavePoints::new(Global::new());
+/// mem.grow_from_slice(&[1, 2, 3])?;
+///
+/// mem.push_state();
+/// mem.write(0, &[10])?;
+///
+/// mem.push_state();
+/// mem.shrink(2)?;
+/// mem.grow_from_slice(&[4, 5])?;
+/// assert_eq!(mem.allocated(), [10, 4, 5]);
+///
+/// mem.pop_state(Pop::Restore)?;
+/// assert_eq!(mem.allocated(), [10, 2, 3]);
+///
+/// mem.pop_state(Pop::Restore)?;
+/// assert_eq!(mem.allocated(), [1, 2, 3]);
+/// # Ok::<_, platform_mem::Error>(())
+/// ```
+pub struct SavePoints<M: RawMem> {
+    mem: M,
+    log: Vec<Undo<M::Item>>,
+    marks: Vec<usize>,
+}
+
+impl<M: RawMem> SavePoints<M>
+where
+    M::Item: Clone,
+{
+    pub fn new(mem: M) -> Self {
+        Self { mem, log: Vec::new(), marks: Vec::new() }
+    }
+
+    pub fn allocated(&self) -> &[M::Item] {
+        self.mem.allocated()
+    }
+
+    /// Count of pushed save points
+    pub fn depth(&self) -> usize {
+        self.marks.len()
+    }
+
+    pub fn push_state(&mut self) {
+        self.marks.push(self.log.len());
+    }
+
+    /// Pops the last save point, returns `false` if there was none
+    ///
+    /// # Errors
+    /// If the memory fails to undo a change, the save point is kept with
+    /// the changes which are not undone yet, so restoring can be retried.
+    pub fn pop_state(&mut self, pop: Pop) -> Result<bool> {
+        let Some(&mark) = self.marks.last() else {
+            return Ok(false);
+        };
+
+        match pop {
+            Pop::Restore => {
+                while let Some(undo) = self.log.get(mark..).and_then(<[_]>::last) {
+                    Self::undo(&mut self.mem, undo)?;
+                    self.log.pop();
+                }
+            }
+            // nobody can restore these changes anymore
+            Pop::Discard if self.marks.len() == 1 => self.log.clear(),
+            Pop::Discard => {}
+        }
+        self.marks.pop();
+        Ok(true)
+    }
+
+    fn undo(mem: &mut M, undo: &Undo<M::Item>) -> Result<()> {
+        match undo {
+            Undo::Write { offset, old } => {
+                mem.allocated_mut()[*offset..offset + old.len()].clone_from_slice(old);
+                Ok(())
+            }
+            &Undo::Grow { addition } => mem.shrink(addition),
+            Undo::Shrink { removed } => mem.grow_from_slice(removed).map(drop),
+        }
+    }
+
+    fn record(&mut self, undo: impl FnOnce(&M) -> Undo<M::Item>) {
+        if !self.marks.is_empty() {
+            self.log.push(undo(&self.mem));
+        }
+    }
+
+    /// Overwrites elements starting from `offset`
+    ///
+    /// # Panics
+    /// Panics if `offset + src.len()` is out of allocated bounds.
+    pub fn write(&mut self, offset: usize, src: &[M::Item]) -> Result<()> {
+        let range = offset..offset + src.len();
+        self.record(|mem| Undo::Write { offset, old: mem.allocated()[range.clone()].to_vec() });
+        self.mem.allocated_mut()[range].clone_from_slice(src);
+        Ok(())
+    }
+
+    pub fn grow_from_slice(&mut self, src: &[M::Item]) -> Result<&mut [M::Item]> {
+        let len = self.mem.allocated().len();
+        self.mem.grow_from_slice(src)?;
+        self.record(|_| Undo::Grow { addition: src.len() });
+        Ok(&mut self.mem.allocated_mut()[len..])
+    }
+
+    pub fn grow_filled(&mut self, addition: usize, value: M::Item) -> Result<&mut [M::Item]> {
+        let len = self.mem.allocated().len();
+        self.mem.grow_filled(addition, value)?;
+        self.record(|_| Undo::Grow { addition });
+        Ok(&mut self.mem.allocated_mut()[len..])
+    }
+
+    pub fn shrink(&mut self, cap: usize) -> Result<()> {
+        let len = self.mem.allocated().len();
+        let rest = len.checked_sub(cap).expect("Tried to shrink to a larger capacity");
+        let removed = (!self.marks.is_empty()).then(|| self.mem.allocated()[rest..].to_vec());
+
+        self.mem.shrink(cap)?;
+        if let Some(removed) = removed {
+            self.log.push(Undo::Shrink { removed });
+        }
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> M {
+        self.mem
+    }
+}
+
+impl<M: RawMem + fmt::Debug> fmt::Debug for SavePoints<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SavePoints")
+            .field("mem", &self.mem)
+            .field("depth", &self.marks.len())
+            .field("undo", &self.log.len())
+            .finish()
+    }
+}
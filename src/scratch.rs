@@ -0,0 +1,62 @@
+//! Thread-local pool of reusable memories for temporary work.
+//!
+//! ```
+//! # #![feature(allocator_api)]
+//! use platform_mem::{scratch, RawMem};
+//!
+//! let sum = scratch::with::<u64, _>(1_000, |mem| {
+//!     mem.grow_with(1_000, || 1).map(|items| items.iter().sum::<u64>())
+//! })??;
+//! assert_eq!(sum, 1_000);
+//!
+//! // the same place is handed out again without allocation
+//! let capacity = scratch::with::<u64, _>(10, |mem| mem.memory_usage().capacity)?;
+//! assert_eq!(capacity, 8_000);
+//! # Ok::<_, platform_mem::Error>(())
+//! ```
+
+use {
+    crate::{Global, RawMem, Result},
+    std::{
+        any::{Any, TypeId},
+        cell::RefCell,
+        collections::HashMap,
+    },
+};
+
+/// Memories of one item type which are kept per thread, others are freed
+const MAX_POOLED: usize = 4;
+
+thread_local! {
+    static POOL: RefCell<HashMap<TypeId, Vec<Box<dyn Any>>>> = RefCell::new(HashMap::new());
+}
+
+/// Calls `f` with an empty memory which has place for at least `len` items,
+/// so grows up to `len` items don't allocate.
+///
+/// The memory is taken from the pool of the current thread and returned there
+/// after `f` (its items are dropped, place is kept), unless `f` panics.
+/// Nested calls get different memories.
+pub fn with<T: 'static, R>(len: usize, f: impl FnOnce(&mut Global<T>) -> R) -> Result<R> {
+    let key = TypeId::of::<T>();
+    let pooled = POOL
+        .try_with(|pool| pool.borrow_mut().get_mut(&key).and_then(Vec::pop))
+        .ok()
+        .flatten()
+        .and_then(|mem| mem.downcast::<Global<T>>().ok());
+    let mut mem = pooled.map_or_else(|| Global::new().with_deferred_shrink(usize::MAX), |mem| *mem);
+
+    // reservation only keeps the place, which is used by next grows
+    let _ = mem.begin_grow(len)?;
+    let result = f(&mut mem);
+
+    mem.shrink(mem.allocated().len())?;
+    let _ = POOL.try_with(|pool| {
+        let mut pool = pool.borrow_mut();
+        let memories = pool.entry(key).or_default();
+        if memories.len() < MAX_POOLED {
+            memories.push(Box::new(mem));
+        }
+    });
+    Ok(result)
+}
@@ -0,0 +1,181 @@
+use {
+    crate::{Error::CapacityOverflow, Result, StableMem},
+    bytemuck::Pod,
+    std::{
+        fmt::{self, Formatter},
+        hint,
+        marker::PhantomData,
+        mem::{self, MaybeUninit},
+        ptr::NonNull,
+        sync::{
+            atomic::{self, AtomicU64, Ordering},
+            Mutex, PoisonError,
+        },
+    },
+};
+
+// header occupies whole cache line to not share it with the data
+const HEADER: usize = 8;
+
+/// Single-writer multi-reader view with a sequence counter in the header.
+///
+/// Memory starts with a header whose first word is a sequence counter,
+/// followed by `len` elements of `T`. The writer makes the counter odd while
+/// it updates elements in place, readers retry until they copy the elements
+/// under the same even counter. Writers of one `SeqlockMem` are serialized by
+/// a lock, so it can be shared between threads. Several `SeqlockMem` over mappings
+/// of one file work the same way across processes, if only one of them writes.
+///
+/// Elements are only accessed by volatile copies, a writer changes a copy of them
+/// and writes it back, so no reference to them is alive while they are read.
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use {platform_mem::{Global, SeqlockMem}, std::thread};
+///
+/// let lock = SeqlockMem::<[u32; 2], _>::new(Global::new(), 2)?;
+/// thread::scope(|s| {
+///     s.spawn(|| lock.write(|data| data[1] = [1, 2]));
+///     s.spawn(|| assert!([[0, 0], [1, 2]].contains(&lock.load()[1])));
+/// });
+///
+/// assert_eq!(lock.load(), [[0, 0], [1, 2]]);
+/// # Ok::<_, platform_mem::Error>(())
+/// ```
+pub struct SeqlockMem<T, M> {
+    mem: M,
+    /// Start of the allocated words of `mem`, it stays valid because `mem` is stable
+    /// and isn't grown or shrunk after creation. Readers and the writer share it,
+    /// so none of them takes a reference which would invalidate the others.
+    ptr: NonNull<u64>,
+    writer: Mutex<()>,
+    len: usize,
+    _marker: PhantomData<T>,
+}
+
+// SAFETY: `mem` is only kept to be dropped or returned, elements are copied out
+// under the sequence counter and written under the writer lock
+unsafe impl<T: Send, M: Send> Send for SeqlockMem<T, M> {}
+unsafe impl<T: Send, M: Send> Sync for SeqlockMem<T, M> {}
+
+impl<T: Pod, M: StableMem<Item = u64>> SeqlockMem<T, M> {
+    /// Grows `mem` (if needed) to fit the header and `len` elements,
+    /// the grown part is zeroed, already existing content is kept.
+    pub fn new(mut mem: M, len: usize) -> Result<Self> {
+        const { assert!(mem::align_of::<T>() <= mem::align_of::<u64>()) };
+
+        let bytes = len.checked_mul(mem::size_of::<T>()).ok_or(CapacityOverflow)?;
+        let words = HEADER + bytes.div_ceil(mem::size_of::<u64>());
+
+        if let Some(addition) = words.checked_sub(mem.allocated().len()) {
+            unsafe {
+                mem.grow(addition, |inited, (_, uninit)| {
+                    uninit[inited..].fill(MaybeUninit::new(0));
+                })?;
+            }
+        }
+
+        // `M: StableMem` keeps the place when `mem` is moved into `Self`
+        let ptr = NonNull::from(mem.allocated_mut()).cast();
+        Ok(Self { mem, ptr, writer: Mutex::new(()), len, _marker: PhantomData })
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Copies a consistent snapshot of all elements into `dst`.
+    ///
+    /// # Panics
+    /// Panics if `dst.len()` differs from [`len`](Self::len).
+    pub fn read_into(&self, dst: &mut [T]) {
+        assert_eq!(dst.len(), self.len, "destination length must match `SeqlockMem::len`");
+
+        loop {
+            let before = self.seq().load(Ordering::Acquire);
+            if before % 2 == 1 {
+                hint::spin_loop();
+                continue;
+            }
+
+            for (i, place) in dst.iter_mut().enumerate() {
+                // racy read may observe torn value, but `T: Pod` is valid for any bits
+                // and such snapshot is discarded below
+                *place = unsafe { self.data().add(i).read_volatile() };
+            }
+
+            atomic::fence(Ordering::Acquire);
+            if self.seq().load(Ordering::Relaxed) == before {
+                return;
+            }
+        }
+    }
+
+    pub fn load(&self) -> Vec<T> {
+        let mut buf = vec![T::zeroed(); self.len];
+        self.read_into(&mut buf);
+        buf
+    }
+
+    /// Updates elements by `f` on their copy, which is written back, so readers never
+    /// observe a partial update (if `f` panics, nothing is written).
+    /// Concurrent writers wait for each other.
+    pub fn write<R>(&self, f: impl FnOnce(&mut [T]) -> R) -> R {
+        struct Publish<'a>(&'a AtomicU64, u64);
+
+        impl Drop for Publish<'_> {
+            fn drop(&mut self) {
+                self.0.store(self.1, Ordering::Release);
+            }
+        }
+
+        // a panicked writer has already published its update
+        let _writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        let seq = self.seq();
+
+        let before = seq.load(Ordering::Relaxed);
+        seq.store(before.wrapping_add(1), Ordering::Relaxed);
+        atomic::fence(Ordering::Release);
+        let _publish = Publish(seq, before.wrapping_add(2));
+
+        // SAFETY: elements are written only under the writer lock
+        let mut copy: Vec<T> =
+            (0..self.len).map(|i| unsafe { self.data().add(i).read() }).collect();
+        let result = f(&mut copy);
+        for (i, item) in copy.into_iter().enumerate() {
+            // readers copy elements by volatile reads and discard torn copies
+            unsafe { self.data().add(i).write_volatile(item) };
+        }
+        result
+    }
+
+    pub fn into_inner(self) -> M {
+        self.mem
+    }
+}
+
+impl<T, M> SeqlockMem<T, M> {
+    fn seq(&self) -> &AtomicU64 {
+        // SAFETY: `AtomicU64` has the same layout as `u64`, header always exists
+        unsafe { AtomicU64::from_ptr(self.ptr.as_ptr()) }
+    }
+
+    fn data(&self) -> *mut T {
+        // SAFETY: header always exists
+        unsafe { self.ptr.as_ptr().add(HEADER).cast() }
+    }
+}
+
+impl<T, M> fmt::Debug for SeqlockMem<T, M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        // items of `mem` may be written concurrently
+        f.debug_struct("SeqlockMem")
+            .field("seq", &self.seq().load(Ordering::Relaxed))
+            .field("len", &self.len)
+            .finish()
+    }
+}
@@ -0,0 +1,111 @@
+use {
+    crate::RawMem,
+    std::{
+        fmt::{self, Formatter},
+        iter,
+    },
+};
+
+/// Memory partitioned across several inner memories.
+///
+/// Each shard is an independent memory, so threads can grow their own shards
+/// in parallel (see [`shards_mut`]), while reads see all shards concatenated
+/// in order.
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use {
+///     platform_mem::{Global, RawMem, ShardedMem},
+///     std::thread,
+/// };
+///
+/// let mut mem = ShardedMem::with_shards(4, Global::new);
+/// thread::scope(|scope| {
+///     for (i, shard) in mem.shards_mut().iter_mut().enumerate() {
+///         scope.spawn(move || shard.grow_filled(2, i).map(drop));
+///     }
+/// });
+///
+/// assert_eq!(mem.len(), 8);
+/// assert_eq!(mem.get(5), Some(&2));
+/// assert_eq!(mem.iter().copied().collect::<Vec<_>>(), [0, 0, 1, 1, 2, 2, 3, 3]);
+/// ```
+///
+/// [`shards_mut`]: Self::shards_mut
+pub struct ShardedMem<M> {
+    shards: Vec<M>,
+}
+
+impl<M: RawMem> ShardedMem<M> {
+    pub fn new(shards: Vec<M>) -> Self {
+        Self { shards }
+    }
+
+    /// Makes `count` shards with `ctor`
+    pub fn with_shards(count: usize, ctor: impl FnMut() -> M) -> Self {
+        iter::repeat_with(ctor).take(count).collect()
+    }
+
+    pub fn shards(&self) -> &[M] {
+        &self.shards
+    }
+
+    /// Disjoint access to every shard, e.g. one per thread
+    pub fn shards_mut(&mut self) -> &mut [M] {
+        &mut self.shards
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| shard.allocated().len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(|shard| shard.allocated().is_empty())
+    }
+
+    /// Finds the shard and the offset inside it for the merged `index`
+    pub fn locate(&self, mut index: usize) -> Option<(usize, usize)> {
+        for (i, shard) in self.shards.iter().enumerate() {
+            let len = shard.allocated().len();
+            if index < len {
+                return Some((i, index));
+            }
+            index -= len;
+        }
+        None
+    }
+
+    pub fn get(&self, index: usize) -> Option<&M::Item> {
+        let (shard, offset) = self.locate(index)?;
+        self.shards[shard].allocated().get(offset)
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut M::Item> {
+        let (shard, offset) = self.locate(index)?;
+        self.shards[shard].allocated_mut().get_mut(offset)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &M::Item> {
+        self.shards.iter().flat_map(|shard| shard.allocated())
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut M::Item> {
+        self.shards.iter_mut().flat_map(|shard| shard.allocated_mut())
+    }
+
+    pub fn into_shards(self) -> Vec<M> {
+        self.shards
+    }
+}
+
+impl<M: RawMem> FromIterator<M> for ShardedMem<M> {
+    fn from_iter<I: IntoIterator<Item = M>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+impl<M: fmt::Debug> fmt::Debug for ShardedMem<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ShardedMem").field(&self.shards).finish()
+    }
+}
@@ -0,0 +1,218 @@
+use {
+    crate::RawMem,
+    std::{
+        fmt::{self, Formatter},
+        ops::Deref,
+        sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard, TryLockError, Weak},
+    },
+};
+
+/// Cloneable handle to a memory shared between threads.
+///
+/// Every clone refers to the same inner memory: readers take [`read`] guards
+/// concurrently, while [`write`] is exclusive (grow and shrink invalidate slices,
+/// so they are only reachable through the write guard). A panic under a guard
+/// doesn't poison the memory, as [`RawMem`] implementors stay consistent on unwinding.
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use platform_mem::{Global, RawMem, Shared};
+///
+/// let shared = Shared::new(Global::<u64>::new());
+/// let other = shared.clone();
+///
+/// shared.write().grow_filled(10, 1)?;
+/// assert_eq!(other.len(), 10);
+/// assert_eq!(other.read().allocated(), [1; 10]);
+/// # Ok::<_, platform_mem::Error>(())
+/// ```
+///
+/// [`read`]: Self::read
+/// [`write`]: Self::write
+pub struct Shared<M> {
+    inner: Arc<RwLock<M>>,
+}
+
+impl<M> Shared<M> {
+    pub fn new(mem: M) -> Self {
+        Self { inner: Arc::new(RwLock::new(mem)) }
+    }
+
+    // poisoning is ignored: `RawMem` implementors must stay consistent when their
+    // methods unwind, and no other state is guarded by the lock; `M` which is not
+    // a `RawMem` is only guarded, so a panicking user leaves it as the user did
+    pub fn read(&self) -> RwLockReadGuard<'_, M> {
+        self.inner.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn write(&self) -> RwLockWriteGuard<'_, M> {
+        self.inner.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns inner memory if this is the last handle
+    pub fn try_unwrap(self) -> Result<M, Self> {
+        Arc::try_unwrap(self.inner)
+            .map(|lock| lock.into_inner().unwrap_or_else(PoisonError::into_inner))
+            .map_err(|inner| Self { inner })
+    }
+
+    pub(crate) fn downgrade(&self) -> Weak<RwLock<M>> {
+        Arc::downgrade(&self.inner)
+    }
+
+    /// Count of handles which refer to the same memory
+    pub fn handles(&self) -> usize {
+        Arc::strong_count(&self.inner)
+    }
+
+    /// Splits the last handle into the unique [`Writer`] and cloneable [`Reader`]s.
+    ///
+    /// Readers are only able to see the allocated slice, so everything that can
+    /// invalidate it (grow, shrink, mutation) is reachable only through the writer.
+    ///
+    /// ```
+    /// # #![feature(allocator_api)]
+    /// use platform_mem::{Global, RawMem, Shared};
+    ///
+    /// let (writer, reader) = Shared::new(Global::<u8>::new()).split().unwrap();
+    /// writer.write().grow_from_slice(b"hello")?;
+    ///
+    /// assert_eq!(&*reader.read(), b"hello");
+    /// # Ok::<_, platform_mem::Error>(())
+    /// ```
+    pub fn split(self) -> Result<(Writer<M>, Reader<M>), Self> {
+        if self.handles() == 1 {
+            Ok((Writer { shared: self.clone() }, Reader { shared: self }))
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl<M: RawMem> Shared<M> {
+    pub fn len(&self) -> usize {
+        self.read().allocated().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<M::Item>
+    where
+        M::Item: Clone,
+    {
+        self.read().allocated().get(index).cloned()
+    }
+
+    /// Calls `f` with the allocated slice under the read lock
+    pub fn with_allocated<R>(&self, f: impl FnOnce(&[M::Item]) -> R) -> R {
+        f(self.read().allocated())
+    }
+
+    pub fn size_hint(&self) -> Option<usize> {
+        self.read().size_hint()
+    }
+}
+
+/// Unique handle which is allowed to grow, shrink and mutate shared memory
+pub struct Writer<M> {
+    shared: Shared<M>,
+}
+
+impl<M> Writer<M> {
+    pub fn read(&self) -> RwLockReadGuard<'_, M> {
+        self.shared.read()
+    }
+
+    pub fn write(&self) -> RwLockWriteGuard<'_, M> {
+        self.shared.write()
+    }
+
+    pub fn reader(&self) -> Reader<M> {
+        Reader { shared: self.shared.clone() }
+    }
+}
+
+/// Cloneable read-only handle to the allocated part of shared memory
+pub struct Reader<M> {
+    shared: Shared<M>,
+}
+
+impl<M: RawMem> Reader<M> {
+    /// Locks memory for reading, so it cannot be grown or shrunk while the guard is alive
+    pub fn read(&self) -> ReadGuard<'_, M> {
+        ReadGuard(self.shared.read())
+    }
+
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shared.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<M::Item>
+    where
+        M::Item: Clone,
+    {
+        self.shared.get(index)
+    }
+}
+
+/// Read guard of [`Reader`] which only exposes the allocated slice
+pub struct ReadGuard<'a, M>(RwLockReadGuard<'a, M>);
+
+impl<M: RawMem> Deref for ReadGuard<'_, M> {
+    type Target = [M::Item];
+
+    fn deref(&self) -> &Self::Target {
+        self.0.allocated()
+    }
+}
+
+impl<M> Clone for Reader<M> {
+    fn clone(&self) -> Self {
+        Self { shared: self.shared.clone() }
+    }
+}
+
+impl<M: fmt::Debug> fmt::Debug for Writer<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Writer").field(&self.shared).finish()
+    }
+}
+
+impl<M: fmt::Debug> fmt::Debug for Reader<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Reader").field(&self.shared).finish()
+    }
+}
+
+impl<M: RawMem> fmt::Debug for ReadGuard<'_, M>
+where
+    M::Item: fmt::Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<M> Clone for Shared<M> {
+    fn clone(&self) -> Self {
+        Self { inner: Arc::clone(&self.inner) }
+    }
+}
+
+impl<M: fmt::Debug> fmt::Debug for Shared<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_tuple("Shared");
+        match self.inner.try_read() {
+            Ok(mem) => d.field(&*mem),
+            Err(TryLockError::Poisoned(err)) => d.field(&*err.into_inner()),
+            Err(TryLockError::WouldBlock) => d.field(&format_args!("<locked>")),
+        };
+        d.finish()
+    }
+}
@@ -0,0 +1,142 @@
+use {
+    memmap2::{MmapMut, MmapOptions},
+    std::{
+        fmt::{self, Formatter},
+        fs::File,
+        io,
+        path::Path,
+        sync::{
+            atomic::{AtomicU32, Ordering},
+            Arc,
+        },
+    },
+};
+
+// counter of notifications and count of sleeping waiters
+const EVENT: usize = 0;
+const WAITERS: usize = 1;
+const WORDS: usize = 2;
+
+/// Notification of readers of a shared memory, e.g. when the writer grows it.
+///
+/// It's a counter in a mapping: [`notify`](Self::notify) increments it and wakes
+/// waiters, [`wait`](Self::wait) sleeps until it changes. Signals opened by
+/// [`open`](Self::open) from one file notify waiters in other processes,
+/// clones of a signal share it between threads. [`FileMapped`] notifies its signal
+/// after every grow, so readers don't poll its length.
+///
+/// ```
+/// use {platform_mem::{FileMapped, RawMem, Signal}, std::thread};
+///
+/// let dir = tempfile::tempdir()?;
+/// let signal = Signal::open(dir.path().join("links.signal"))?;
+/// let mut mem = FileMapped::<u64>::new(tempfile::tempfile()?)?.with_signal(signal.clone());
+///
+/// let seen = signal.current();
+/// let reader = thread::spawn(move || signal.wait(seen));
+/// mem.grow_filled(10, 0)?;
+/// assert_ne!(reader.join().unwrap(), seen);
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+///
+/// [`FileMapped`]: crate::FileMapped
+#[derive(Clone)]
+pub struct Signal {
+    mmap: Arc<MmapMut>,
+}
+
+impl Signal {
+    /// Signal of threads of this process
+    pub fn new() -> io::Result<Self> {
+        let mmap = MmapOptions::new().len(WORDS * 4).map_anon()?;
+        Ok(Self { mmap: Arc::new(mmap) })
+    }
+
+    /// Signal in the file at `path`, which is created if it doesn't exist
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file =
+            File::options().create(true).truncate(false).read(true).write(true).open(path)?;
+        if file.metadata()?.len() < (WORDS * 4) as u64 {
+            file.set_len((WORDS * 4) as u64)?;
+        }
+        // SAFETY: words of the file are accessed only atomically
+        let mmap = unsafe { MmapOptions::new().len(WORDS * 4).map_mut(&file)? };
+        Ok(Self { mmap: Arc::new(mmap) })
+    }
+
+    fn word(&self, word: usize) -> &AtomicU32 {
+        // SAFETY: mapping is aligned to a page and holds `WORDS` words,
+        // which are accessed only atomically (also from other processes)
+        unsafe { AtomicU32::from_ptr(self.mmap.as_ptr().cast::<u32>().add(word).cast_mut()) }
+    }
+
+    /// Count of notifications so far, it wraps around
+    pub fn current(&self) -> u32 {
+        self.word(EVENT).load(Ordering::SeqCst)
+    }
+
+    pub fn notify(&self) {
+        self.word(EVENT).fetch_add(1, Ordering::SeqCst);
+        if self.word(WAITERS).load(Ordering::SeqCst) != 0 {
+            futex::wake(self.word(EVENT));
+        }
+    }
+
+    /// Sleeps until the count of notifications differs from `seen` and returns it
+    pub fn wait(&self, seen: u32) -> u32 {
+        self.word(WAITERS).fetch_add(1, Ordering::SeqCst);
+        let mut current = self.current();
+        while current == seen {
+            futex::wait(self.word(EVENT), seen);
+            current = self.current();
+        }
+        self.word(WAITERS).fetch_sub(1, Ordering::SeqCst);
+        current
+    }
+}
+
+impl fmt::Debug for Signal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Signal")
+            .field("current", &self.current())
+            .field("waiters", &self.word(WAITERS).load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+pub(crate) mod futex {
+    use std::sync::atomic::AtomicU32;
+
+    #[cfg(target_os = "linux")]
+    pub fn wait(word: &AtomicU32, expected: u32) {
+        // not `FUTEX_PRIVATE_FLAG`: waker may live in another process
+        unsafe {
+            libc::syscall(
+                libc::SYS_futex,
+                word.as_ptr(),
+                libc::FUTEX_WAIT,
+                expected,
+                std::ptr::null::<libc::timespec>(),
+            );
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn wake(word: &AtomicU32) {
+        unsafe {
+            libc::syscall(libc::SYS_futex, word.as_ptr(), libc::FUTEX_WAKE, i32::MAX);
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn wait(word: &AtomicU32, expected: u32) {
+        use std::{sync::atomic::Ordering, thread, time::Duration};
+
+        if word.load(Ordering::SeqCst) == expected {
+            thread::sleep(Duration::from_micros(50));
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn wake(_: &AtomicU32) {}
+}
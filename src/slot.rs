@@ -0,0 +1,184 @@
+use {
+    crate::{Error::CapacityOverflow, RawMem, Result},
+    bytemuck::{Pod, Zeroable},
+    std::{
+        fmt::{self, Formatter},
+        mem::MaybeUninit,
+    },
+};
+
+/// Key of a value in [`SlotMem`], it's plain data to be stored elsewhere
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(C)]
+pub struct SlotKey {
+    pub index: u32,
+    pub generation: u32,
+}
+
+unsafe impl Zeroable for SlotKey {}
+unsafe impl Pod for SlotKey {}
+
+/// Item of memory behind [`SlotMem`]
+#[repr(C)]
+pub struct Slot<T> {
+    /// Odd while the slot is occupied, so keys of removed values never match again
+    generation: u32,
+    /// Next free slot, the first slot keeps the head of the free list
+    next_free: u32,
+    value: MaybeUninit<T>,
+}
+
+impl<T> Slot<T> {
+    fn is_occupied(&self) -> bool {
+        self.generation % 2 == 1
+    }
+}
+
+/// Values addressed by generation-checked keys, which stay valid while the slice
+/// of the memory moves on grows. Freed slots are reused by the next insertions.
+///
+/// Slots (and their free list) live in the memory itself, so it's persistent
+/// with [`FileMapped`](crate::FileMapped).
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use platform_mem::{Global, SlotMem};
+///
+/// let mut slots = SlotMem::new(Global::new())?;
+/// let first = slots.insert("first")?;
+/// assert_eq!(slots.remove(first), Some("first"));
+///
+/// let second = slots.insert("second")?; // takes the same slot
+/// assert_eq!(second.index, first.index);
+/// assert_eq!(slots.get(first), None); // stale key
+/// assert_eq!(slots.get(second), Some(&"second"));
+/// # Ok::<_, platform_mem::Error>(())
+/// ```
+pub struct SlotMem<T, M: RawMem<Item = Slot<T>>> {
+    mem: M,
+    len: usize,
+}
+
+impl<T, M: RawMem<Item = Slot<T>>> SlotMem<T, M> {
+    /// Uses allocated slots of `mem` or allocates the first one if it's empty
+    pub fn new(mut mem: M) -> Result<Self> {
+        if mem.allocated().is_empty() {
+            mem.grow_with(1, || Slot {
+                generation: 0,
+                next_free: 0,
+                value: MaybeUninit::uninit(),
+            })?;
+        }
+        let len = mem.allocated().iter().filter(|slot| slot.is_occupied()).count();
+        Ok(Self { mem, len })
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn insert(&mut self, value: T) -> Result<SlotKey> {
+        let slots = self.mem.allocated_mut();
+        let index = slots[0].next_free;
+        let key = if index != 0 {
+            let slot = &mut slots[index as usize];
+            let next = slot.next_free;
+            slot.generation = slot.generation.wrapping_add(1);
+            slot.value.write(value);
+            let key = SlotKey { index, generation: slot.generation };
+            slots[0].next_free = next;
+            key
+        } else {
+            let index = u32::try_from(slots.len()).map_err(|_| CapacityOverflow)?;
+            let mut value = Some(value);
+            self.mem.grow_with(1, || Slot {
+                generation: 1,
+                next_free: 0,
+                value: MaybeUninit::new(value.take().expect("only one slot is grown")),
+            })?;
+            SlotKey { index, generation: 1 }
+        };
+        self.len += 1;
+        Ok(key)
+    }
+
+    fn slot(&self, key: SlotKey) -> Option<&Slot<T>> {
+        let slot = self.mem.allocated().get(key.index as usize)?;
+        (key.index != 0 && slot.is_occupied() && slot.generation == key.generation).then_some(slot)
+    }
+
+    fn slot_mut(&mut self, key: SlotKey) -> Option<&mut Slot<T>> {
+        let slot = self.mem.allocated_mut().get_mut(key.index as usize)?;
+        (key.index != 0 && slot.is_occupied() && slot.generation == key.generation).then_some(slot)
+    }
+
+    pub fn contains_key(&self, key: SlotKey) -> bool {
+        self.slot(key).is_some()
+    }
+
+    pub fn get(&self, key: SlotKey) -> Option<&T> {
+        // SAFETY: values of occupied slots are initialized
+        self.slot(key).map(|slot| unsafe { slot.value.assume_init_ref() })
+    }
+
+    pub fn get_mut(&mut self, key: SlotKey) -> Option<&mut T> {
+        self.slot_mut(key).map(|slot| unsafe { slot.value.assume_init_mut() })
+    }
+
+    /// Removes the value, so the key (and its copies) becomes stale
+    pub fn remove(&mut self, key: SlotKey) -> Option<T> {
+        let head = self.mem.allocated()[0].next_free;
+        let slot = self.slot_mut(key)?;
+        slot.generation = slot.generation.wrapping_add(1);
+        slot.next_free = head;
+        // SAFETY: slot was occupied and it's free now, so the value is read once
+        let value = unsafe { slot.value.assume_init_read() };
+
+        self.mem.allocated_mut()[0].next_free = key.index;
+        self.len -= 1;
+        Some(value)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (SlotKey, &T)> {
+        self.mem.allocated().iter().enumerate().filter(|(_, slot)| slot.is_occupied()).map(
+            |(index, slot)| {
+                let key = SlotKey { index: index as u32, generation: slot.generation };
+                (key, unsafe { slot.value.assume_init_ref() })
+            },
+        )
+    }
+
+    pub fn inner(&self) -> &M {
+        &self.mem
+    }
+}
+
+impl<T, M: RawMem<Item = Slot<T>>> Drop for SlotMem<T, M> {
+    fn drop(&mut self) {
+        if std::mem::needs_drop::<T>() {
+            for slot in self.mem.allocated_mut().iter_mut().filter(|slot| slot.is_occupied()) {
+                // SAFETY: memory is not used after it
+                unsafe { slot.value.assume_init_drop() };
+            }
+        }
+    }
+}
+
+impl<T> fmt::Debug for Slot<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Slot")
+            .field("generation", &self.generation)
+            .field("next_free", &self.next_free)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T, M: RawMem<Item = Slot<T>> + fmt::Debug> fmt::Debug for SlotMem<T, M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SlotMem").field("len", &self.len).field("mem", &self.mem).finish()
+    }
+}
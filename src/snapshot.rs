@@ -0,0 +1,49 @@
+use std::{
+    fmt::{self, Formatter},
+    ops::Deref,
+    time::SystemTime,
+};
+
+/// Owned copy of memory contents made by [`RawMem::snapshot`]
+///
+/// [`RawMem::snapshot`]: crate::RawMem::snapshot
+#[derive(Clone, PartialEq, Eq)]
+pub struct Snapshot<T> {
+    items: Box<[T]>,
+    taken_at: SystemTime,
+}
+
+impl<T> Snapshot<T> {
+    pub fn new(items: impl Into<Box<[T]>>) -> Self {
+        Self { items: items.into(), taken_at: SystemTime::now() }
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.items
+    }
+
+    pub fn taken_at(&self) -> SystemTime {
+        self.taken_at
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        self.items.into_vec()
+    }
+}
+
+impl<T> Deref for Snapshot<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        &self.items
+    }
+}
+
+impl<T> fmt::Debug for Snapshot<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Snapshot")
+            .field("len", &self.items.len())
+            .field("taken_at", &self.taken_at)
+            .finish()
+    }
+}
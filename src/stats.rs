@@ -0,0 +1,176 @@
+use {
+    crate::{utils, RawMem, Result},
+    std::{
+        fmt::{self, Formatter},
+        mem,
+        time::{Duration, Instant},
+    },
+};
+
+/// Counters collected by [`StatsMem`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Stats {
+    /// Successful `grow` calls
+    pub grows: u64,
+    /// Successful `shrink` calls
+    pub shrinks: u64,
+    /// `grow`/`shrink` calls that returned an error
+    pub failures: u64,
+    /// Currently allocated bytes
+    pub bytes: usize,
+    /// Maximal allocated length in items
+    pub peak_len: usize,
+    /// Resizes that moved allocated memory (reallocation or remap)
+    pub remaps: u64,
+    /// Time spent inside `grow`/`shrink` of the inner memory
+    pub backend_time: Duration,
+}
+
+/// Resizes of a memory between [`profile_start`] and [`profile_stop`]
+///
+/// [`profile_start`]: StatsMem::profile_start
+/// [`profile_stop`]: StatsMem::profile_stop
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Profile {
+    /// Minor page faults of the thread inside `grow`/`shrink`
+    pub minor_faults: u64,
+    /// Major page faults (which read the disk) of the thread inside `grow`/`shrink`
+    pub major_faults: u64,
+    /// Resizes that moved allocated memory (reallocation or remap)
+    pub remaps: u64,
+    /// Time spent inside `grow`/`shrink` of the inner memory
+    pub backend_time: Duration,
+    /// Time from the start of profiling
+    pub elapsed: Duration,
+}
+
+/// Memory that counts its resizes and the time spent in them.
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use platform_mem::{Global, RawMem, StatsMem};
+///
+/// let mut mem = StatsMem::new(Global::<u64>::new());
+/// mem.grow_filled(10, 0)?;
+/// mem.grow_filled(10, 0)?;
+/// mem.shrink(15)?;
+///
+/// let stats = mem.stats();
+/// assert_eq!((stats.grows, stats.shrinks), (2, 1));
+/// assert_eq!(stats.bytes, 5 * 8);
+/// assert_eq!(stats.peak_len, 20);
+/// # Ok::<_, platform_mem::Error>(())
+/// ```
+pub struct StatsMem<M> {
+    mem: M,
+    stats: Stats,
+    profile: Option<(Instant, Profile)>,
+}
+
+impl<M: RawMem> StatsMem<M> {
+    pub fn new(mem: M) -> Self {
+        let len = mem.allocated().len();
+        let stats =
+            Stats { bytes: mem::size_of_val(mem.allocated()), peak_len: len, ..Stats::default() };
+        Self { mem, stats, profile: None }
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    /// Resets counters, but keeps the current size as the peak
+    pub fn reset_stats(&mut self) {
+        self.stats = Stats {
+            bytes: self.stats.bytes,
+            peak_len: self.mem.allocated().len(),
+            ..Stats::default()
+        };
+    }
+
+    /// Starts to record page faults and remaps of resizes, e.g. to find the memory
+    /// which causes latency spikes. Faults are counted by `getrusage`, so profiling
+    /// costs two syscalls per resize.
+    /// ```
+    /// # #![feature(allocator_api)]
+    /// use platform_mem::{Global, RawMem, StatsMem};
+    ///
+    /// let mut mem = StatsMem::new(Global::<u8>::new());
+    /// mem.profile_start();
+    /// mem.grow_filled(1 << 20, 1)?; // touches every page
+    ///
+    /// let profile = mem.profile_stop().unwrap();
+    /// assert!(profile.minor_faults > 0);
+    /// assert!(mem.profile_stop().is_none());
+    /// # Ok::<_, platform_mem::Error>(())
+    /// ```
+    pub fn profile_start(&mut self) {
+        self.profile = Some((Instant::now(), Profile::default()));
+    }
+
+    /// Stops profiling and returns the recorded profile, if it's started
+    pub fn profile_stop(&mut self) -> Option<Profile> {
+        self.profile.take().map(|(start, profile)| Profile { elapsed: start.elapsed(), ..profile })
+    }
+
+    pub fn into_inner(self) -> M {
+        self.mem
+    }
+
+    fn track<R>(&mut self, op: impl FnOnce(&mut M) -> Result<R>) -> Result<R> {
+        let (old_ptr, old_len) = (self.mem.allocated().as_ptr(), self.mem.allocated().len());
+
+        let faults = self.profile.is_some().then(utils::page_faults);
+        let start = Instant::now();
+        let result = op(&mut self.mem);
+        let elapsed = start.elapsed();
+        self.stats.backend_time += elapsed;
+
+        let allocated = self.mem.allocated();
+        // empty memory may have any dangling pointer
+        let remapped = old_len > 0 && !allocated.is_empty() && allocated.as_ptr() != old_ptr;
+        if remapped {
+            self.stats.remaps += 1;
+        }
+        if let (Some((_, profile)), Some((minor, major))) = (&mut self.profile, faults) {
+            let (now_minor, now_major) = utils::page_faults();
+            profile.minor_faults += now_minor.saturating_sub(minor);
+            profile.major_faults += now_major.saturating_sub(major);
+            profile.remaps += u64::from(remapped);
+            profile.backend_time += elapsed;
+        }
+        self.stats.bytes = mem::size_of_val(allocated);
+        self.stats.peak_len = self.stats.peak_len.max(allocated.len());
+        if result.is_err() {
+            self.stats.failures += 1;
+        }
+        result
+    }
+}
+
+forward_mem! {
+    impl<M> RawMem for StatsMem<M> {
+        unsafe fn grow(&mut self, addition, fill) {
+            self.track(|mem| mem.grow(addition, fill).map(drop))?;
+            self.stats.grows += 1;
+        }
+
+        fn shrink(&mut self, cap) {
+            self.track(|mem| mem.shrink(cap))?;
+            self.stats.shrinks += 1;
+            Ok(())
+        }
+    }
+}
+
+impl<M: fmt::Debug> fmt::Debug for StatsMem<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StatsMem")
+            .field("mem", &self.mem)
+            .field("stats", &self.stats)
+            .field("profile", &self.profile)
+            .finish()
+    }
+}
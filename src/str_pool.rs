@@ -0,0 +1,107 @@
+use {
+    crate::{utils, RawMem, Result},
+    bytemuck::{Pod, Zeroable},
+    std::{
+        collections::HashMap,
+        fmt::{self, Formatter},
+        str,
+    },
+};
+
+/// Place of a string in [`StrPool`], it's plain data to be stored next to links
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[repr(C)]
+pub struct StrHandle {
+    pub offset: u64,
+    pub len: u64,
+}
+
+unsafe impl Zeroable for StrHandle {}
+unsafe impl Pod for StrHandle {}
+
+/// Strings appended into a byte memory, which are addressed by stable handles.
+///
+/// Handles stay valid while the memory is not shrunk, e.g. across reopenings of
+/// [`FileMapped`](crate::FileMapped). [`intern`](Self::intern) reuses strings,
+/// but only those added since the pool is created.
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use platform_mem::{Global, StrPool};
+///
+/// let mut pool = StrPool::new(Global::new())?;
+/// let name = pool.intern("name")?;
+/// let other = pool.intern("other")?;
+///
+/// assert_eq!(pool.intern("name")?, name);
+/// assert_eq!(pool.resolve(other), Some("other"));
+/// # Ok::<_, platform_mem::Error>(())
+/// ```
+pub struct StrPool<M> {
+    mem: M,
+    /// Handles of interned strings by their hash
+    interned: HashMap<u64, Vec<StrHandle>>,
+}
+
+impl<M: RawMem<Item = u8>> StrPool<M> {
+    /// Empty memory is grown to its [`size_hint`](RawMem::size_hint) first,
+    /// so handles of a reopened file can be resolved
+    pub fn new(mut mem: M) -> Result<Self> {
+        if mem.allocated().is_empty() {
+            if let Some(stored) = mem.size_hint() {
+                // SAFETY: zeroed bytes are valid, stored ones are kept
+                unsafe { mem.grow_zeroed_exact(stored)? };
+            }
+        }
+        Ok(Self { mem, interned: HashMap::new() })
+    }
+
+    /// Appends the string even if it's already in the pool
+    pub fn push(&mut self, s: &str) -> Result<StrHandle> {
+        let offset = self.mem.allocated().len() as u64;
+        self.mem.grow_from_slice(s.as_bytes())?;
+        Ok(StrHandle { offset, len: s.len() as u64 })
+    }
+
+    /// Returns handle of the same string added by `intern` or appends it
+    pub fn intern(&mut self, s: &str) -> Result<StrHandle> {
+        let hash = utils::checksum(s.as_bytes());
+        if let Some(handles) = self.interned.get(&hash) {
+            if let Some(&handle) = handles.iter().find(|&&handle| self.resolve(handle) == Some(s)) {
+                return Ok(handle);
+            }
+        }
+        let handle = self.push(s)?;
+        self.interned.entry(hash).or_default().push(handle);
+        Ok(handle)
+    }
+
+    /// String of the handle, if it's in bounds and it's valid UTF-8
+    pub fn resolve(&self, handle: StrHandle) -> Option<&str> {
+        let start = usize::try_from(handle.offset).ok()?;
+        let end = start.checked_add(usize::try_from(handle.len).ok()?)?;
+        str::from_utf8(self.mem.allocated().get(start..end)?).ok()
+    }
+
+    /// Bytes of all strings
+    pub fn bytes(&self) -> usize {
+        self.mem.allocated().len()
+    }
+
+    pub fn inner(&self) -> &M {
+        &self.mem
+    }
+
+    pub fn into_inner(self) -> M {
+        self.mem
+    }
+}
+
+impl<M: fmt::Debug> fmt::Debug for StrPool<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StrPool")
+            .field("mem", &self.mem)
+            .field("interned", &self.interned.values().map(Vec::len).sum::<usize>())
+            .finish()
+    }
+}
@@ -0,0 +1,317 @@
+//! Helpers to test [`RawMem`] implementations against the trait contract.
+//!
+//! ```
+//! # #![feature(allocator_api)]
+//! use platform_mem::{testing, Global};
+//!
+//! testing::conformance(Global::new)?;
+//! # Ok::<_, platform_mem::Error>(())
+//! ```
+//!
+//! [`Op`] implements `Arbitrary` of both `quickcheck` and `proptest`,
+//! so random sequences of operations can be checked by [`check_ops`]:
+//!
+//! ```
+//! # #![feature(allocator_api)]
+//! use {
+//!     platform_mem::{testing::{self, Op}, Global},
+//!     proptest::{collection::vec, prelude::*},
+//! };
+//!
+//! proptest!(|(ops in vec(any::<Op>(), 0..32))| {
+//!     testing::check_ops(&mut Global::<u64>::new(), &ops).unwrap();
+//! });
+//! ```
+
+use {
+    crate::{RawMem, Result},
+    proptest::strategy::{BoxedStrategy, Strategy},
+    quickcheck::{Arbitrary, Gen},
+    std::{
+        fmt::{self, Debug, Formatter},
+        panic::{self, AssertUnwindSafe},
+        sync::{
+            atomic::{AtomicIsize, Ordering},
+            Arc,
+        },
+    },
+};
+
+/// Item that counts its live instances, so leaks and double drops are visible
+pub struct Probe {
+    value: u64,
+    live: Arc<AtomicIsize>,
+}
+
+impl Probe {
+    fn new(value: u64, live: &Arc<AtomicIsize>) -> Self {
+        live.fetch_add(1, Ordering::Relaxed);
+        Self { value, live: Arc::clone(live) }
+    }
+
+    pub fn value(&self) -> u64 {
+        self.value
+    }
+}
+
+impl Clone for Probe {
+    fn clone(&self) -> Self {
+        Self::new(self.value, &self.live)
+    }
+}
+
+impl Drop for Probe {
+    fn drop(&mut self) {
+        self.live.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl PartialEq for Probe {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl fmt::Debug for Probe {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Probe").field(&self.value).finish()
+    }
+}
+
+fn values<M: RawMem<Item = Probe>>(mem: &M) -> Vec<u64> {
+    mem.allocated().iter().map(Probe::value).collect()
+}
+
+/// Runs the contract checks on memories created by `ctor`, panics on violation
+/// and returns errors of the memory itself.
+///
+/// Checks that:
+/// - new memory is empty
+/// - `grow` passes initialized and uninitialized parts of the right lengths
+///   and returns exactly the grown part
+/// - `shrink` removes and drops exactly the tail
+/// - a panicking `fill` leaves the memory usable and unchanged
+/// - dropping the memory drops every item once
+pub fn conformance<M, F>(mut ctor: F) -> Result<()>
+where
+    M: RawMem<Item = Probe>,
+    F: FnMut() -> M,
+{
+    let live = Arc::new(AtomicIsize::new(0));
+    let probe = |value| Probe::new(value, &live);
+    {
+        let mut mem = ctor();
+        assert!(mem.allocated().is_empty(), "new memory must be empty");
+
+        let grown = mem.grow_filled(3, probe(1))?;
+        assert_eq!(grown.len(), 3, "`grow` must return the grown part");
+        assert_eq!(values(&mem), [1, 1, 1]);
+
+        unsafe {
+            mem.grow(2, |inited, (within, uninit)| {
+                assert_eq!(within.len(), 3, "`fill` must get all initialized items");
+                assert_eq!(uninit.len(), 2, "`fill` must get exactly `addition` items");
+                assert!(inited <= uninit.len(), "`inited` is out of the grown part");
+                uninit[0].write(probe(2));
+                uninit[1].write(probe(3));
+            })?;
+        }
+        assert_eq!(values(&mem), [1, 1, 1, 2, 3]);
+
+        let grown = mem.grow_from_slice(&[])?;
+        assert!(grown.is_empty(), "growing by zero must return empty part");
+        mem.shrink(0)?;
+        assert_eq!(mem.allocated().len(), 5, "shrinking by zero must change nothing");
+
+        mem.shrink(2)?;
+        assert_eq!(values(&mem), [1, 1, 1]);
+        assert_eq!(live.load(Ordering::Relaxed), 3, "`shrink` must drop removed items");
+
+        let generation = mem.generation();
+        let panicked = panic::catch_unwind(AssertUnwindSafe(|| unsafe {
+            let _ = mem.grow(4, |_, _| panic!("fill panics"));
+        }));
+        assert!(panicked.is_err());
+        assert_eq!(values(&mem), [1, 1, 1], "panicking `fill` must not change items");
+        assert!(mem.generation() >= generation, "generation must never decrease");
+
+        mem.grow_filled(2, probe(4))?;
+        assert_eq!(values(&mem), [1, 1, 1, 4, 4]);
+        mem.allocated_mut()[0] = probe(5);
+        assert_eq!(values(&mem), [5, 1, 1, 4, 4]);
+        assert_eq!(live.load(Ordering::Relaxed), 5);
+
+        mem.shrink(5)?;
+        assert!(mem.allocated().is_empty());
+        assert_eq!(live.load(Ordering::Relaxed), 0);
+
+        mem.grow_filled(10, probe(6))?;
+    }
+    assert_eq!(live.load(Ordering::Relaxed), 0, "dropped memory must drop all items once");
+
+    Ok(())
+}
+
+/// Largest addition of generated [`Op::Grow`]
+pub const MAX_GROW: usize = 64;
+
+/// Operation on memory generated for property tests
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    /// Grows by `addition` copies of `value`
+    Grow { addition: usize, value: u8 },
+    /// Shrinks by `count` modulo length plus one
+    Shrink { count: usize },
+    /// Overwrites the item at `index` modulo length, if any
+    Set { index: usize, value: u8 },
+    /// Grows by `addition` with `fill` that writes `written` items and panics
+    /// with [`InjectedPanic`], the memory must stay unchanged.
+    /// It's decoded by [`run_ops`], but not generated by `Arbitrary` impls.
+    PanickingGrow { addition: usize, written: usize },
+}
+
+/// Panic payload of [`Op::PanickingGrow`]
+#[derive(Debug, Clone, Copy)]
+pub struct InjectedPanic;
+
+/// Wraps the current panic hook to skip [`InjectedPanic`]s,
+/// e.g. fuzzers which abort on any panic by default
+pub fn silence_injected_panics() {
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if !info.payload().is::<InjectedPanic>() {
+            hook(info);
+        }
+    }));
+}
+
+/// Upper bound of operations decoded by [`run_ops`]
+pub const MAX_OPS: usize = 1024;
+
+/// Decodes every three bytes `[tag, a, b]` into an operation:
+/// [`Op::Grow`] by `a` items, [`Op::Shrink`] by `a | b << 8` (usually whole memory),
+/// [`Op::Set`] at `a` and [`Op::PanickingGrow`] by `a` after `b` written items.
+pub fn decode_ops(bytes: &[u8]) -> Vec<Op> {
+    bytes
+        .chunks_exact(3)
+        .take(MAX_OPS)
+        .map(|op| {
+            let (a, b) = (op[1] as usize, op[2]);
+            match op[0] % 4 {
+                0 => Op::Grow { addition: a, value: b },
+                1 => Op::Shrink { count: a | (b as usize) << 8 },
+                2 => Op::Set { index: a, value: b },
+                _ => Op::PanickingGrow { addition: a, written: b as usize },
+            }
+        })
+        .collect()
+}
+
+/// Interprets arbitrary bytes as operations checked by [`check_ops`], it's
+/// designed for fuzz targets: all sizes are bounded and panics mean bugs.
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use platform_mem::{testing, Global};
+///
+/// testing::silence_injected_panics();
+/// testing::run_ops(&mut Global::<u64>::new(), &[0, 10, 1, 3, 4, 2, 1, 255, 255])?;
+/// # Ok::<_, platform_mem::Error>(())
+/// ```
+pub fn run_ops<M>(mem: &mut M, bytes: &[u8]) -> Result<()>
+where
+    M: RawMem + ?Sized,
+    M::Item: Clone + PartialEq + Debug + From<u8>,
+{
+    check_ops(mem, &decode_ops(bytes))
+}
+
+/// Applies `ops` to `mem` and to a vector model, comparing them after each operation.
+/// Panics on mismatch with the index of the operation and returns errors of the memory.
+pub fn check_ops<M>(mem: &mut M, ops: &[Op]) -> Result<()>
+where
+    M: RawMem + ?Sized,
+    M::Item: Clone + PartialEq + Debug + From<u8>,
+{
+    let mut model = mem.allocated().to_vec();
+    let mut generation = mem.generation();
+
+    for (i, &op) in ops.iter().enumerate() {
+        match op {
+            Op::Grow { addition, value } => {
+                let grown = mem.grow_filled(addition, value.into())?;
+                assert_eq!(grown.len(), addition, "op #{i} {op:?}: wrong grown part");
+                model.resize(model.len() + addition, value.into());
+            }
+            Op::Shrink { count } => {
+                let count = count % (model.len() + 1);
+                mem.shrink(count)?;
+                model.truncate(model.len() - count);
+            }
+            Op::Set { index, value } => {
+                if !model.is_empty() {
+                    let index = index % model.len();
+                    mem.allocated_mut()[index] = value.into();
+                    model[index] = value.into();
+                }
+            }
+            Op::PanickingGrow { addition, written } => {
+                // memory may fail before calling `fill`, so the panic is not required
+                let _ = panic::catch_unwind(AssertUnwindSafe(|| unsafe {
+                    let _ = mem.grow(addition, |_, (_, uninit)| {
+                        for place in uninit.iter_mut().take(written) {
+                            place.write(M::Item::from(0));
+                        }
+                        panic::panic_any(InjectedPanic);
+                    });
+                }));
+            }
+        }
+        assert_eq!(mem.allocated(), model, "op #{i} {op:?}: memory differs from the model");
+        assert!(mem.generation() >= generation, "op #{i} {op:?}: generation decreased");
+        generation = mem.generation();
+    }
+    Ok(())
+}
+
+impl Arbitrary for Op {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let value = u8::arbitrary(g);
+        match u8::arbitrary(g) % 3 {
+            0 => Op::Grow { addition: usize::arbitrary(g) % (MAX_GROW + 1), value },
+            1 => Op::Shrink { count: usize::arbitrary(g) },
+            _ => Op::Set { index: usize::arbitrary(g), value },
+        }
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        match *self {
+            Op::Grow { addition, value } => {
+                Box::new(addition.shrink().map(move |addition| Op::Grow { addition, value }))
+            }
+            Op::Shrink { count } => Box::new(count.shrink().map(|count| Op::Shrink { count })),
+            Op::Set { index, value } => {
+                Box::new(index.shrink().map(move |index| Op::Set { index, value }))
+            }
+            Op::PanickingGrow { addition, written } => Box::new(
+                addition.shrink().map(move |addition| Op::PanickingGrow { addition, written }),
+            ),
+        }
+    }
+}
+
+impl proptest::arbitrary::Arbitrary for Op {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Op>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        use proptest::{arbitrary::any, prop_oneof};
+
+        prop_oneof![
+            (0..=MAX_GROW, any::<u8>()).prop_map(|(addition, value)| Op::Grow { addition, value }),
+            any::<usize>().prop_map(|count| Op::Shrink { count }),
+            (any::<usize>(), any::<u8>()).prop_map(|(index, value)| Op::Set { index, value }),
+        ]
+        .boxed()
+    }
+}
@@ -0,0 +1,33 @@
+//! Instrumentation behind the `tracing` feature, it expands to nothing without it
+
+#[cfg(feature = "tracing")]
+pub(crate) struct Timed {
+    span: tracing::span::EnteredSpan,
+    start: std::time::Instant,
+}
+
+#[cfg(feature = "tracing")]
+impl Timed {
+    pub fn new(span: tracing::Span) -> Self {
+        Self { span: span.entered(), start: std::time::Instant::now() }
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl Drop for Timed {
+    fn drop(&mut self) {
+        // the span is still entered here, so the event belongs to it
+        tracing::debug!(parent: &*self.span, elapsed = ?self.start.elapsed(), "done");
+    }
+}
+
+/// Enters a `debug` span until the end of the current block
+/// and reports the elapsed time when leaving it
+macro_rules! timed {
+    ($name:literal $(, $($fields:tt)*)?) => {
+        #[cfg(feature = "tracing")]
+        let _timed = $crate::trace::Timed::new(tracing::debug_span!($name $(, $($fields)*)?));
+    };
+}
+
+pub(crate) use timed;
@@ -0,0 +1,178 @@
+use {
+    crate::{MemoryUsage, RawMem, Result, Snapshot},
+    bytemuck::Pod,
+    std::{
+        fmt::{self, Formatter},
+        mem::MaybeUninit,
+        ops::{Deref, DerefMut},
+    },
+};
+
+/// Memory whose multi-step updates can be rolled back in memory.
+///
+/// Outside of transactions it behaves as the inner memory.
+/// [`begin`] copies the current contents to RAM, and everything done through the
+/// returned [`Transaction`] is undone by [`rollback`] or by dropping it without [`commit`].
+///
+/// Rollback is in-memory only: the undo copy is not persisted, so a crash during
+/// a transaction leaves a file-backed memory with whatever changes reached the file.
+/// Use [`Journaled`] for updates which must survive crashes.
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use platform_mem::{Global, RawMem, Transactional};
+///
+/// let mut mem = Transactional::new(Global::new());
+/// mem.grow_from_slice(&[1, 2, 3])?;
+///
+/// {
+///     let mut tx = mem.begin();
+///     tx.allocated_mut()[0] = 10;
+///     tx.grow_filled(10, 0)?;
+///     // dropped without commit
+/// }
+/// assert_eq!(mem.allocated(), [1, 2, 3]);
+///
+/// let mut tx = mem.begin();
+/// tx.allocated_mut()[0] = 10;
+/// tx.commit();
+/// assert_eq!(mem.allocated(), [10, 2, 3]);
+/// # Ok::<_, platform_mem::Error>(())
+/// ```
+///
+/// [`begin`]: Self::begin
+/// [`rollback`]: Transaction::rollback
+/// [`commit`]: Transaction::commit
+/// [`Journaled`]: crate::Journaled
+pub struct Transactional<M> {
+    mem: M,
+}
+
+impl<M: RawMem> Transactional<M> {
+    pub fn new(mem: M) -> Self {
+        Self { mem }
+    }
+
+    /// Starts transaction, it costs a copy of the whole allocated memory in RAM
+    pub fn begin(&mut self) -> Transaction<'_, M>
+    where
+        M::Item: Pod,
+    {
+        Transaction { undo: Some(self.mem.snapshot()), mem: &mut self.mem }
+    }
+
+    pub fn into_inner(self) -> M {
+        self.mem
+    }
+}
+
+/// Pending changes of [`Transactional`] memory
+pub struct Transaction<'a, M: RawMem>
+where
+    M::Item: Pod,
+{
+    mem: &'a mut M,
+    undo: Option<Snapshot<M::Item>>,
+}
+
+impl<M: RawMem> Transaction<'_, M>
+where
+    M::Item: Pod,
+{
+    pub fn commit(mut self) {
+        self.undo = None;
+    }
+
+    pub fn rollback(mut self) -> Result<()> {
+        self.undo_changes()
+    }
+
+    fn undo_changes(&mut self) -> Result<()> {
+        match self.undo.take() {
+            Some(undo) => self.mem.restore(&undo),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<M: RawMem> Drop for Transaction<'_, M>
+where
+    M::Item: Pod,
+{
+    fn drop(&mut self) {
+        // restoring can only fail growing back shrunk memory,
+        // use `rollback` to observe such error
+        let _ = self.undo_changes();
+    }
+}
+
+impl<M: RawMem> Deref for Transaction<'_, M>
+where
+    M::Item: Pod,
+{
+    type Target = M;
+
+    fn deref(&self) -> &M {
+        self.mem
+    }
+}
+
+impl<M: RawMem> DerefMut for Transaction<'_, M>
+where
+    M::Item: Pod,
+{
+    fn deref_mut(&mut self) -> &mut M {
+        self.mem
+    }
+}
+
+impl<M: RawMem> RawMem for Transactional<M> {
+    type Item = M::Item;
+
+    fn allocated(&self) -> &[Self::Item] {
+        self.mem.allocated()
+    }
+
+    fn allocated_mut(&mut self) -> &mut [Self::Item] {
+        self.mem.allocated_mut()
+    }
+
+    unsafe fn grow(
+        &mut self,
+        addition: usize,
+        fill: impl FnOnce(usize, (&mut [Self::Item], &mut [MaybeUninit<Self::Item>])),
+    ) -> Result<&mut [Self::Item]> {
+        self.mem.grow(addition, fill)
+    }
+
+    fn shrink(&mut self, cap: usize) -> Result<()> {
+        self.mem.shrink(cap)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.mem.size_hint()
+    }
+
+    fn generation(&self) -> u64 {
+        self.mem.generation()
+    }
+
+    fn memory_usage(&self) -> MemoryUsage {
+        self.mem.memory_usage()
+    }
+}
+
+impl<M: fmt::Debug> fmt::Debug for Transactional<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Transactional").field(&self.mem).finish()
+    }
+}
+
+impl<M: RawMem + fmt::Debug> fmt::Debug for Transaction<'_, M>
+where
+    M::Item: Pod,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Transaction").field("mem", &self.mem).finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,70 @@
+//! Process-wide pool of recently unmapped file mappings.
+//!
+//! Memories which are resized back and forth map the same few lengths of their
+//! file again and again, so mappings are kept here after unmapping and taken
+//! back by the next mapping of the same file and length instead of syscalls.
+
+use {
+    memmap2::MmapMut,
+    std::{
+        fs::File,
+        sync::{Mutex, PoisonError},
+    },
+};
+
+/// Mappings kept in the process, the oldest ones are unmapped first
+const MAX_POOLED: usize = 8;
+
+/// Device and inode of a file, they aren't reused while a mapping of it is alive
+pub(crate) type FileId = (u64, u64);
+
+static POOL: Mutex<Vec<(FileId, MmapMut)>> = Mutex::new(Vec::new());
+
+fn pool() -> std::sync::MutexGuard<'static, Vec<(FileId, MmapMut)>> {
+    POOL.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Identity of the file, there is none on platforms other than Unix
+pub(crate) fn file_id(file: &File) -> Option<FileId> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        file.metadata().ok().map(|meta| (meta.dev(), meta.ino()))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = file;
+        None
+    }
+}
+
+/// Keeps the mapping for reuse, it may be past the end of a truncated file,
+/// so it's never read until the file is extended again
+pub(crate) fn put(id: FileId, mmap: MmapMut) {
+    let evicted = {
+        let mut pool = pool();
+        let evicted = (pool.len() == MAX_POOLED).then(|| pool.remove(0));
+        pool.push((id, mmap));
+        evicted
+    };
+    // unmap outside of the lock
+    drop(evicted);
+}
+
+/// Takes the mapping of `len` bytes of the file, if it's kept
+pub(crate) fn take(id: FileId, len: usize) -> Option<MmapMut> {
+    let mut pool = pool();
+    let pos = pool.iter().position(|(key, mmap)| *key == id && mmap.len() == len)?;
+    Some(pool.remove(pos).1)
+}
+
+/// Unmaps kept mappings of the file, so its space can be freed
+pub(crate) fn forget(id: FileId) {
+    let forgotten: Vec<_> = {
+        let mut pool = pool();
+        let (forgotten, kept) = pool.drain(..).partition(|(key, _)| *key == id);
+        *pool = kept;
+        forgotten
+    };
+    drop(forgotten);
+}
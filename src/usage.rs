@@ -0,0 +1,22 @@
+/// Sizes reported by [`RawMem::memory_usage`](crate::RawMem::memory_usage)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MemoryUsage {
+    /// Bytes of allocated items
+    pub logical: usize,
+    /// Bytes reserved by the backend, e.g. the allocation or the mapped file length
+    pub capacity: usize,
+    /// Bytes of `capacity` which are currently in RAM, if the backend can tell it
+    pub resident: Option<usize>,
+}
+
+impl MemoryUsage {
+    pub fn new(logical: usize, capacity: usize, resident: Option<usize>) -> Self {
+        Self { logical, capacity, resident }
+    }
+
+    /// Bytes reserved but not used by items
+    pub fn overhead(&self) -> usize {
+        self.capacity.saturating_sub(self.logical)
+    }
+}
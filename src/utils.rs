@@ -2,15 +2,302 @@ use {
     crate::raw_place::RawPlace,
     std::{
         fmt,
-        fmt::{DebugStruct, Formatter},
+        fmt::{Debug, DebugStruct, Formatter},
     },
 };
 
-pub fn debug_mem<'a, 'b: 'a, T>(
+/// Count of first and last items shown by [`Preview`]
+const PREVIEW: usize = 8;
+/// Count of first and last lines shown by [`Hexdump`]
+const PREVIEW_LINES: usize = 4;
+const LINE: usize = 16;
+
+/// Bounded `Debug` of allocated items
+pub struct Preview<'a, T>(pub &'a [T]);
+
+impl<T: Debug> Debug for Preview<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let items = self.0;
+        if items.len() <= 2 * PREVIEW {
+            return f.debug_list().entries(items).finish();
+        }
+        let skipped = format_args!("..{} more", items.len() - 2 * PREVIEW);
+        f.debug_list()
+            .entries(&items[..PREVIEW])
+            .entry(&Unquoted(skipped))
+            .entries(&items[items.len() - PREVIEW..])
+            .finish()
+    }
+}
+
+/// Bounded hexdump of allocated bytes
+pub struct Hexdump<'a>(pub &'a [u8]);
+
+impl Debug for Hexdump<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let bytes = self.0;
+        let lines = bytes.len().div_ceil(LINE);
+        for (i, line) in bytes.chunks(LINE).enumerate() {
+            if i == PREVIEW_LINES && lines > 2 * PREVIEW_LINES {
+                writeln!(f, "...")?;
+            }
+            if i >= PREVIEW_LINES && i < lines.saturating_sub(PREVIEW_LINES) {
+                continue;
+            }
+            write!(f, "{:08x} ", i * LINE)?;
+            for byte in line {
+                write!(f, " {byte:02x}")?;
+            }
+            let ascii: String = line
+                .iter()
+                .map(
+                    |&byte| {
+                        if byte.is_ascii_graphic() || byte == b' ' {
+                            byte as char
+                        } else {
+                            '.'
+                        }
+                    },
+                )
+                .collect();
+            writeln!(f, "{:pad$}  |{ascii}|", "", pad = 3 * (LINE - line.len()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Items in the alternate `Debug` of backends: [`Preview`] of them or [`Hexdump`] of bytes
+pub trait DebugItems: Debug + Sized {
+    fn fmt_items(items: &[Self], f: &mut Formatter<'_>) -> fmt::Result;
+}
+
+impl<T: Debug> DebugItems for T {
+    default fn fmt_items(items: &[Self], f: &mut Formatter<'_>) -> fmt::Result {
+        Preview(items).fmt(f)
+    }
+}
+
+impl DebugItems for u8 {
+    fn fmt_items(items: &[Self], f: &mut Formatter<'_>) -> fmt::Result {
+        Hexdump(items).fmt(f)
+    }
+}
+
+struct Items<'a, T>(&'a [T]);
+
+impl<T: DebugItems> Debug for Items<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        T::fmt_items(self.0, f)
+    }
+}
+
+struct Unquoted<'a>(fmt::Arguments<'a>);
+
+impl Debug for Unquoted<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_fmt(self.0)
+    }
+}
+
+/// Starts `Debug` of a backend, the alternate form (`{:#?}`) also shows the count
+/// of items and their bounded preview like [`RawMem::debug_items`],
+/// bytes are shown like [`RawMem::hexdump`]
+///
+/// [`RawMem::debug_items`]: crate::RawMem::debug_items
+/// [`RawMem::hexdump`]: crate::RawMem::hexdump
+pub fn debug_mem<'a, 'b: 'a, T: DebugItems>(
     f: &'a mut Formatter<'b>,
     buf: &RawPlace<T>,
     alt: &str,
+    label: Option<&str>,
 ) -> Result<DebugStruct<'a, 'b>, fmt::Error> {
     write!(f, "{:?} ", buf)?;
-    Ok(f.debug_struct(alt))
+    let alternate = f.alternate();
+    let mut debug = f.debug_struct(alt);
+    if let Some(label) = label {
+        debug.field("label", &label);
+    }
+    if alternate {
+        debug.field("len", &buf.len());
+        // SAFETY: `buf` has `len` initialized items
+        debug.field("items", &Items(unsafe { buf.as_slice() }));
+    }
+    Ok(debug)
+}
+
+/// FNV-1a hash, it's enough to detect torn or corrupted records
+pub fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// All bytes are zeros, it's branchless to be vectorized
+pub fn is_zeroed(bytes: &[u8]) -> bool {
+    bytes.iter().fold(0, |acc, &byte| acc | byte) == 0
+}
+
+/// Bytes of pages of `mapped` which are in RAM now, it must be a whole mapping
+#[cfg(unix)]
+pub fn resident(mapped: &[u8]) -> std::io::Result<usize> {
+    if mapped.is_empty() {
+        return Ok(0);
+    }
+    let page = page_size();
+    let mut pages = vec![0u8; mapped.len().div_ceil(page)];
+    // SAFETY: mapping is page aligned and `pages` has a byte for each of its pages
+    if unsafe { libc::mincore(mapped.as_ptr() as *mut _, mapped.len(), pages.as_mut_ptr().cast()) }
+        != 0
+    {
+        return Err(std::io::Error::last_os_error());
+    }
+    let resident = pages.iter().filter(|&&page| page & 1 != 0).count();
+    Ok((resident * page).min(mapped.len()))
+}
+
+/// Nothing is known about residency elsewhere
+#[cfg(not(unix))]
+pub fn resident(_: &[u8]) -> std::io::Result<usize> {
+    Ok(0)
+}
+
+/// Minor and major page faults of the current thread (of the process outside of Linux)
+#[cfg(unix)]
+pub fn page_faults() -> (u64, u64) {
+    #[cfg(target_os = "linux")]
+    let who = libc::RUSAGE_THREAD;
+    #[cfg(not(target_os = "linux"))]
+    let who = libc::RUSAGE_SELF;
+
+    // SAFETY: `getrusage` only writes the zeroed struct
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    if unsafe { libc::getrusage(who, &mut usage) } != 0 {
+        return (0, 0);
+    }
+    (usage.ru_minflt as u64, usage.ru_majflt as u64)
+}
+
+/// Faults are not counted outside of Unix
+#[cfg(not(unix))]
+pub fn page_faults() -> (u64, u64) {
+    (0, 0)
+}
+
+/// Cache lines of one [`prefetch`], farther items are left to the hardware
+/// prefetcher, which follows sequential traversals anyway
+const PREFETCH_LINES: usize = 256;
+const CACHE_LINE: usize = 64;
+
+/// Software prefetch of the first cache lines of `items`, it's a no-op
+/// on architectures without stable prefetch instructions
+pub fn prefetch<T>(items: &[T]) {
+    #[cfg(target_arch = "x86_64")]
+    {
+        use std::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+
+        let bytes = std::mem::size_of_val(items).min(PREFETCH_LINES * CACHE_LINE);
+        let ptr = items.as_ptr().cast::<i8>();
+        for offset in (0..bytes).step_by(CACHE_LINE) {
+            // SAFETY: SSE is baseline of x86_64, address is inside of `items`
+            unsafe { _mm_prefetch(ptr.add(offset), _MM_HINT_T0) };
+        }
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    let _ = items;
+}
+
+#[cfg(unix)]
+pub fn page_size() -> usize {
+    unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize }
+}
+
+/// Pages of common platforms without `sysconf`
+#[cfg(not(unix))]
+pub fn page_size() -> usize {
+    4096
+}
+
+/// Whole pages inside of `start..end` bytes
+#[cfg(unix)]
+fn inner_pages(start: usize, end: usize) -> Option<(usize, usize)> {
+    let page = page_size();
+    let (start, end) = (start.next_multiple_of(page), end / page * page);
+    (start < end).then_some((start, end - start))
+}
+
+/// Size of transparent huge pages on common architectures
+pub const HUGE_PAGE: usize = 2 << 20;
+
+/// Advises the kernel to back pages lying entirely inside of `place` by transparent
+/// huge pages, it fails if they are disabled
+///
+/// # Safety
+/// `place` must be owned private memory, e.g. from the allocator
+#[cfg(target_os = "linux")]
+pub unsafe fn advise_huge(place: *mut [u8]) -> std::io::Result<()> {
+    let start = place.cast::<u8>() as usize;
+    let Some((start, len)) = inner_pages(start, start + place.len()) else {
+        return Ok(());
+    };
+    if libc::madvise(start as *mut _, len, libc::MADV_HUGEPAGE) != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Transparent huge pages are Linux only
+#[cfg(not(target_os = "linux"))]
+pub unsafe fn advise_huge(_: *mut [u8]) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Gives pages lying entirely inside of `place` back to the OS and returns their size,
+/// content of the place becomes unspecified
+///
+/// # Safety
+/// `place` must be owned private memory, e.g. from the allocator
+#[cfg(unix)]
+pub unsafe fn decommit(place: *mut [u8]) -> std::io::Result<usize> {
+    let start = place.cast::<u8>() as usize;
+    let Some((start, len)) = inner_pages(start, start + place.len()) else {
+        return Ok(0);
+    };
+    if libc::madvise(start as *mut _, len, libc::MADV_FREE) != 0 {
+        let err = std::io::Error::last_os_error();
+        // `MADV_FREE` is unknown to old kernels, which still can drop pages eagerly
+        if err.raw_os_error() != Some(libc::EINVAL)
+            || libc::madvise(start as *mut _, len, libc::MADV_DONTNEED) != 0
+        {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    Ok(len)
+}
+
+/// Pages are not given back elsewhere, so nothing is decommitted
+#[cfg(not(unix))]
+pub unsafe fn decommit(_: *mut [u8]) -> std::io::Result<usize> {
+    Ok(0)
+}
+
+/// Deallocates disk blocks and cached pages of whole pages inside of `start..end` bytes
+/// of `file`, keeping its length, and returns their size. They are read as zeros after.
+#[cfg(target_os = "linux")]
+pub fn punch_hole(file: &std::fs::File, start: usize, end: usize) -> std::io::Result<usize> {
+    use std::os::fd::AsRawFd;
+
+    let Some((start, len)) = inner_pages(start, end) else {
+        return Ok(0);
+    };
+    let mode = libc::FALLOC_FL_PUNCH_HOLE | libc::FALLOC_FL_KEEP_SIZE;
+    if unsafe { libc::fallocate(file.as_raw_fd(), mode, start as _, len as _) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(len)
+}
+
+/// Holes are not portable, so elsewhere nothing is deallocated
+#[cfg(not(target_os = "linux"))]
+pub fn punch_hole(_: &std::fs::File, _: usize, _: usize) -> std::io::Result<usize> {
+    Ok(0)
 }
@@ -0,0 +1,54 @@
+//! Invariant checks of backends after every operation, they are compiled
+//! only into debug builds with the `watchdog` feature.
+
+#[cfg(all(debug_assertions, feature = "watchdog"))]
+use std::fmt::{Debug, Write};
+
+/// Collects broken invariants and panics with the state of memory if any
+#[cfg(all(debug_assertions, feature = "watchdog"))]
+pub(crate) struct Report {
+    backend: &'static str,
+    op: &'static str,
+    violations: Vec<String>,
+    state: String,
+}
+
+#[cfg(all(debug_assertions, feature = "watchdog"))]
+impl Report {
+    pub fn new(backend: &'static str, op: &'static str) -> Self {
+        Self { backend, op, violations: Vec::new(), state: String::new() }
+    }
+
+    pub fn state(mut self, name: &str, value: impl Debug) -> Self {
+        let _ = writeln!(self.state, "    {name}: {value:?}");
+        self
+    }
+
+    pub fn ensure(&mut self, holds: bool, violation: impl FnOnce() -> String) {
+        if !holds {
+            self.violations.push(violation());
+        }
+    }
+
+    #[track_caller]
+    pub fn finish(self) {
+        if self.violations.is_empty() {
+            return;
+        }
+        let mut report = format!("`{}` is broken after `{}`:\n", self.backend, self.op);
+        for violation in &self.violations {
+            let _ = writeln!(report, "  - {violation}");
+        }
+        panic!("{report}  state:\n{}", self.state);
+    }
+}
+
+/// Calls `verify` of the backend after the operation `$op`
+macro_rules! verify {
+    ($mem:expr, $op:literal) => {
+        #[cfg(all(debug_assertions, feature = "watchdog"))]
+        $mem.verify($op);
+    };
+}
+
+pub(crate) use verify;
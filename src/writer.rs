@@ -0,0 +1,107 @@
+use {
+    crate::RawMem,
+    std::{
+        fmt::{self, Formatter},
+        io,
+    },
+};
+
+/// Appends written bytes to the end of memory.
+///
+/// Memory grows geometrically ahead of the written bytes, so small writes do not
+/// remap a file each time; the spare tail is shrunk by [`finish`] (or on drop).
+/// If memory can't grow ahead, it grows exactly by the written bytes, and a failed
+/// write keeps all bytes written before it.
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use {
+///     platform_mem::{MemWriter, RawMem, TempFile},
+///     std::io::Write,
+/// };
+///
+/// let mut mem = TempFile::<u8>::new()?;
+/// mem.grow_from_slice(b"log: ")?;
+///
+/// let mut writer = MemWriter::new(&mut mem);
+/// write!(writer, "{} + {} = {}", 2, 2, 4)?;
+/// assert_eq!(writer.written(), 9);
+/// writer.finish()?;
+///
+/// assert_eq!(mem.allocated(), b"log: 2 + 2 = 4");
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+///
+/// [`finish`]: Self::finish
+pub struct MemWriter<'a, M: RawMem<Item = u8> + ?Sized> {
+    mem: &'a mut M,
+    /// Length of memory before the writer
+    start: usize,
+    /// Length of memory with written bytes, the rest is spare
+    len: usize,
+}
+
+impl<'a, M: RawMem<Item = u8> + ?Sized> MemWriter<'a, M> {
+    /// Smallest grow, so tiny writes do not grow memory one by one
+    const MIN_GROW: usize = 4096;
+
+    pub fn new(mem: &'a mut M) -> Self {
+        let len = mem.allocated().len();
+        Self { mem, start: len, len }
+    }
+
+    /// Count of bytes written by this writer
+    pub fn written(&self) -> usize {
+        self.len - self.start
+    }
+
+    /// Shrinks the spare tail of memory, so it ends with the written bytes
+    pub fn finish(mut self) -> io::Result<()> {
+        self.trim()
+    }
+
+    fn trim(&mut self) -> io::Result<()> {
+        let spare = self.mem.allocated().len() - self.len;
+        if spare > 0 {
+            self.mem.shrink(spare)?;
+        }
+        Ok(())
+    }
+}
+
+impl<M: RawMem<Item = u8> + ?Sized> io::Write for MemWriter<'_, M> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let allocated = self.mem.allocated().len();
+        let spare = allocated - self.len;
+        if spare < buf.len() {
+            let needed = buf.len() - spare;
+            let addition = needed.max(allocated - self.start).max(Self::MIN_GROW);
+            // limited memory may still fit the written bytes without the spare
+            if self.mem.grow_filled(addition, 0).is_err() {
+                self.mem.grow_filled(needed, 0)?;
+            }
+        }
+        self.mem.allocated_mut()[self.len..][..buf.len()].copy_from_slice(buf);
+        self.len += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<M: RawMem<Item = u8> + ?Sized> Drop for MemWriter<'_, M> {
+    fn drop(&mut self) {
+        let _ = self.trim();
+    }
+}
+
+impl<M: RawMem<Item = u8> + ?Sized> fmt::Debug for MemWriter<'_, M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemWriter")
+            .field("written", &self.written())
+            .field("spare", &(self.mem.allocated().len() - self.len))
+            .finish()
+    }
+}
@@ -11,24 +11,24 @@ pub fn grow_from_slice(mut mem: impl RawMem<Item = u8>) {
 
 #[test]
 fn yet() -> Result {
-    use std::{fs, io::Write, str};
+    use std::io::Write;
 
-    // may be use tempfile???
-    const FILE: &str = "tmp.file";
     const TAIL_SIZE: usize = 4 * 1024;
 
-    let _ = fs::remove_file(FILE);
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("tmp.file");
     {
         let mut file = File::options() // `create_new` feature
             .write(true)
             .create_new(true)
-            .open(FILE)?;
+            .open(&path)?;
         file.write_all(b"hello world")?;
         file.write_all(&[b'\0'; TAIL_SIZE])?;
     }
 
     unsafe {
-        let mut mem = FileMapped::from_path(FILE)?;
+        let mut mem = FileMapped::from_path(&path)?;
+        assert_eq!(mem.size_hint(), Some(11 + TAIL_SIZE));
 
         assert_eq!(b"hello world", mem.grow_assumed(5 + 1 + 5)?); // is size of `hello world`
 
@@ -36,7 +36,2058 @@ fn yet() -> Result {
             assert_eq!(inited, TAIL_SIZE);
             assert_eq!(10_000, uninit.len());
         })?;
+        mem.shrink(10_000)?;
+        assert_eq!(mem.size_hint(), Some(11));
     }
 
     Ok(())
 }
+
+#[test]
+fn size_hint_of_reopened_file_includes_padding() -> Result {
+    let path = tempfile::NamedTempFile::new()?.into_temp_path();
+    {
+        let mut mem = FileMapped::<u64>::from_path(&path)?;
+        assert_eq!(mem.size_hint(), Some(0));
+        mem.grow_from_slice(&[1, 2, 3])?;
+        assert_eq!(mem.size_hint(), Some(3));
+    }
+
+    // file is padded to a page, so 4096 bytes fit 512 items
+    let mut mem = FileMapped::<u64>::from_path(&path)?;
+    assert_eq!(mem.size_hint(), Some(512));
+    unsafe { mem.grow_assumed(512)? };
+    assert_eq!(mem.allocated()[..3], [1, 2, 3]);
+    assert!(mem.allocated()[3..].iter().all(|&x| x == 0));
+
+    mem.shrink(510)?;
+    assert_eq!(mem.size_hint(), Some(2));
+    Ok(())
+}
+
+#[test]
+fn panicking_fill_keeps_length() -> Result {
+    use {
+        platform_mem::Global,
+        std::panic::{self, AssertUnwindSafe},
+    };
+
+    let mut mem = Global::<u64>::new();
+    mem.grow_filled(2, 1)?;
+    let grown = panic::catch_unwind(AssertUnwindSafe(|| unsafe {
+        mem.grow(3, |_, _| panic!("`fill` fails before initializing items")).map(drop)
+    }));
+    assert!(grown.is_err());
+    assert_eq!(mem.allocated(), [1, 1]);
+
+    // new items go right after the initialized ones, not after the unused tail
+    mem.grow_filled(1, 2)?;
+    assert_eq!(mem.allocated(), [1, 1, 2]);
+    mem.shrink(3)?;
+    assert!(mem.allocated().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn atomic_view() -> Result {
+    use std::sync::atomic::Ordering;
+
+    let mut mem = FileMapped::<u64>::new(tempfile::tempfile()?)?;
+    mem.grow_filled(10, 0)?;
+
+    let atomics = mem.allocated_atomic();
+    atomics[3].fetch_add(5, Ordering::SeqCst);
+    assert_eq!(mem.allocated()[3], 5);
+
+    Ok(())
+}
+
+#[test]
+fn atomic_view_is_shared_between_threads() -> Result {
+    use std::{sync::atomic::Ordering, thread};
+
+    let mut mem = FileMapped::<u64>::new(tempfile::tempfile()?)?;
+    mem.grow_filled(4, 0)?;
+
+    let atomics = mem.allocated_atomic();
+    thread::scope(|scope| {
+        for _ in 0..8 {
+            scope.spawn(|| {
+                for i in 0..1000 {
+                    atomics[i % 4].fetch_add(1, Ordering::Relaxed);
+                }
+            });
+        }
+    });
+    assert_eq!(mem.allocated(), [2000; 4]);
+
+    let mut empty = FileMapped::<u32>::new(tempfile::tempfile()?)?;
+    assert!(empty.allocated_atomic().is_empty());
+    Ok(())
+}
+
+#[test]
+fn channel_between_mappings() -> Result {
+    use {
+        platform_mem::{ErrorKind, ShmChannel},
+        std::io,
+    };
+
+    let file = tempfile::NamedTempFile::new()?;
+    // new channel needs a capacity, the other side reads it from the header
+    let err = ShmChannel::<u32, _>::new(FileMapped::from_path(file.path())?, 0).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Io(io::ErrorKind::InvalidInput));
+    let mut tx = ShmChannel::<u32, _>::new(FileMapped::from_path(file.path())?, 16)?;
+    let mut rx = ShmChannel::<u32, _>::new(FileMapped::from_path(file.path())?, 0)?;
+
+    assert_eq!(rx.capacity(), 16);
+    for i in 0..10 {
+        tx.try_send(i).unwrap();
+    }
+    assert_eq!((tx.len(), rx.len(), rx.is_empty()), (10, 10, false));
+    assert_eq!(
+        (0..10).map(|_| rx.try_recv().unwrap()).collect::<Vec<_>>(),
+        (0..10).collect::<Vec<_>>()
+    );
+    assert_eq!(rx.try_recv(), None);
+
+    Ok(())
+}
+
+#[test]
+fn channel_blocking_recv() -> Result {
+    use {
+        platform_mem::ShmChannel,
+        std::{thread, time::Duration},
+    };
+
+    let file = tempfile::NamedTempFile::new()?;
+    let mut tx = ShmChannel::<u64, _>::new(FileMapped::from_path(file.path())?, 4)?;
+    let mut rx = ShmChannel::<u64, _>::new(FileMapped::from_path(file.path())?, 0)?;
+
+    let receiver = thread::spawn(move || (0..100).map(|_| rx.recv()).sum::<u64>());
+    for i in 0..100 {
+        while tx.try_send(i).is_err() {
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+    assert_eq!(receiver.join().unwrap(), (0..100).sum::<u64>());
+
+    Ok(())
+}
+
+#[test]
+fn token_handoff() -> Result {
+    use std::thread;
+
+    let mut mem = FileMapped::<u64>::new(tempfile::tempfile()?)?;
+    mem.grow_filled(10, 7)?;
+
+    let token = mem.into_token();
+    let mem = thread::spawn(move || FileMapped::from_token(token)).join().unwrap()?;
+    assert_eq!(mem.allocated(), [7; 10]);
+
+    Ok(())
+}
+
+#[test]
+fn token_keeps_settings() -> Result {
+    use std::fs;
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("mem");
+    let mut mem = FileMapped::<u64>::from_path(&path)?.with_label("links").with_high_water_mark();
+    mem.grow_filled(10_000, 7)?;
+    let _reader = mem.reader()?;
+
+    let token = mem.into_token();
+    assert_eq!(token.label(), Some("links"));
+    let mut mem = FileMapped::from_token(token)?;
+    assert_eq!((mem.label(), mem.path()), (Some("links"), Some(path.as_path())));
+
+    mem.shrink(9_000)?; // file keeps its largest length
+    assert_eq!(fs::metadata(&path)?.len(), 80_000);
+
+    let (file, len) = mem.into_token().into_parts();
+    let mem = FileMapped::from_token(unsafe {
+        platform_mem::MappingToken::<u64>::from_parts(file, len)
+    })?;
+    assert_eq!((mem.allocated(), mem.label()), (&[7; 1_000][..], None));
+
+    Ok(())
+}
+
+#[test]
+fn checkpoint_roundtrip() -> Result {
+    use platform_mem::Global;
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("checkpoint");
+
+    let mut mem = Global::<u32>::new();
+    mem.grow_from_slice(&[1, 2, 3])?;
+    mem.checkpoint_to_path(&path)?;
+
+    let mut other = Global::<u32>::new();
+    other.grow_filled(10, 0)?;
+    other.restore_from_path(&path)?;
+    assert_eq!(other.allocated(), [1, 2, 3]);
+
+    // zero-sized items keep their count
+    let mut units = Global::<()>::new();
+    units.grow_filled(5, ())?;
+    units.checkpoint_to_path(&path)?;
+    let mut other = Global::<()>::new();
+    other.restore_from_path(&path)?;
+    assert_eq!(other.allocated().len(), 5);
+
+    std::fs::write(&path, [0; 3])?;
+    assert!(other.restore_from_path(&path).is_err());
+    assert_eq!(other.allocated().len(), 5);
+    Ok(())
+}
+
+#[test]
+fn export_detects_corruption() -> Result {
+    use platform_mem::Global;
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("export");
+
+    let mut mem = Global::<u64>::new();
+    mem.grow_from_slice(&[1, 2, 3])?;
+    mem.export(&path)?;
+
+    let mut bytes = std::fs::read(&path)?;
+    *bytes.last_mut().unwrap() ^= 1;
+    std::fs::write(&path, bytes)?;
+
+    let mut other = Global::<u64>::new();
+    assert!(other.import(&path).is_err());
+    assert!(other.allocated().is_empty());
+
+    // zero-sized items have only the count
+    let mut units = Global::<()>::new();
+    units.grow_filled(7, ())?;
+    units.export(&path)?;
+    let mut other = Global::<()>::new();
+    other.import(&path)?;
+    assert_eq!(other.allocated().len(), 7);
+
+    // another endianness is rejected
+    let mut bytes = std::fs::read(&path)?;
+    bytes[10] ^= 1;
+    std::fs::write(&path, bytes)?;
+    let err = other.import(&path).unwrap_err();
+    assert!(err.to_string().contains("another endianness"), "{err}");
+    Ok(())
+}
+
+#[test]
+fn journal_torn_tail() -> Result {
+    use {
+        platform_mem::{Global, Journaled},
+        std::io::Write,
+    };
+
+    let wal = tempfile::NamedTempFile::new()?;
+    {
+        let mut mem = Journaled::open(Global::<u16>::new(), wal.path())?;
+        mem.grow_from_slice(&[1, 2, 3])?;
+    }
+    // crash in the middle of writing the next record
+    File::options().append(true).open(wal.path())?.write_all(&[3, 0, 0, 0])?;
+
+    let mut mem = Journaled::open(Global::<u16>::new(), wal.path())?;
+    assert_eq!(mem.allocated(), [1, 2, 3]);
+    mem.grow_from_slice(&[4])?;
+
+    let mem = Journaled::open(Global::<u16>::new(), wal.path())?;
+    assert_eq!(mem.allocated(), [1, 2, 3, 4]);
+
+    Ok(())
+}
+
+#[test]
+fn journal_rejects_unappliable_records() -> Result {
+    use {
+        platform_mem::{ErrorKind, Global, Journaled, RawMem},
+        std::{fs, io},
+    };
+
+    let wal = tempfile::NamedTempFile::new()?;
+    {
+        let mut mem = Journaled::open(Global::<u16>::new(), wal.path())?;
+        mem.grow_from_slice(&[1, 2, 3])?;
+        mem.truncate_log()?; // but inner memory is lost
+        mem.write(2, &[4])?;
+    }
+    let len = fs::metadata(wal.path())?.len();
+
+    // lost memory is zeroed up to the length of the log base
+    let mem = Journaled::open(Global::<u16>::new(), wal.path())?;
+    assert_eq!(mem.allocated(), [0, 0, 4]);
+    drop(mem);
+
+    // three bytes of the base can't fit a write of two bytes at 2
+    let err = Journaled::open(Global::<u8>::new(), wal.path()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Io(io::ErrorKind::InvalidData));
+    assert_eq!(fs::metadata(wal.path())?.len(), len);
+
+    // two bytes of payload can't be `u32`
+    let err = Journaled::open(Global::<u32>::new(), wal.path()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Io(io::ErrorKind::InvalidData));
+    assert_eq!(fs::metadata(wal.path())?.len(), len);
+
+    // log applies over durable memory
+    let mut durable = Global::<u16>::new();
+    durable.grow_from_slice(&[1, 2, 3])?;
+    let mem = Journaled::open(durable, wal.path())?;
+    assert_eq!(mem.allocated(), [1, 2, 4]);
+
+    Ok(())
+}
+
+#[quickcheck_macros::quickcheck]
+fn diff_patches_into_other(a: Vec<u8>, b: Vec<u8>) -> bool {
+    let mut patched = a.clone();
+    patched.resize(b.len(), 0);
+    for range in platform_mem::diff::diff(&a, &b) {
+        // tail which exists only in `a` is cut by `resize`
+        let range = range.start.min(b.len())..range.end.min(b.len());
+        patched[range.clone()].copy_from_slice(&b[range]);
+    }
+    patched == b
+}
+
+#[test]
+fn faulty_random_failures_keep_memory() -> Result {
+    use platform_mem::{FaultyMem, Global};
+
+    let mut mem = FaultyMem::new(Global::<u8>::new()).fail_randomly(42, 0.5);
+    let mut expected = 0;
+    for _ in 0..100 {
+        if mem.grow_filled(3, 1).is_ok() {
+            expected += 3;
+        }
+    }
+    assert!(0 < expected && expected < 300);
+    assert_eq!(mem.grows(), 100);
+    assert_eq!(mem.allocated(), vec![1; expected]);
+
+    Ok(())
+}
+
+#[test]
+fn budget_released_by_failed_grow() -> Result {
+    use {
+        platform_mem::{Budget, FaultyMem, Global},
+        std::panic::{self, AssertUnwindSafe},
+    };
+
+    let budget = Budget::new(100);
+    let mut mem = budget.attach(FaultyMem::new(Global::<u8>::new()).fail_grow_at(0));
+
+    assert!(mem.grow_filled(60, 0).is_err());
+    assert_eq!(budget.used(), 0);
+    mem.grow_filled(60, 0)?;
+    mem.shrink(20)?;
+    assert_eq!(budget.used(), 40);
+
+    let panicked = panic::catch_unwind(AssertUnwindSafe(|| {
+        mem.grow_with(10, || panic!("fill panicked")).map(drop)
+    }));
+    assert!(panicked.is_err());
+    assert_eq!((budget.used(), mem.allocated().len()), (40, 40));
+
+    let mem = mem.into_inner();
+    assert_eq!((budget.used(), mem.allocated().len()), (0, 40));
+    Ok(())
+}
+
+#[test]
+fn pressure_subscription_is_weak() -> Result {
+    use platform_mem::{Global, Pressure, PressureHub, Shared};
+
+    let hub = PressureHub::new();
+    let mem = Shared::new(Global::<u8>::new());
+    let subscription = hub.attach(&mem, |mem, _| mem.shrink(mem.allocated().len()).unwrap());
+
+    drop(mem);
+    hub.notify(Pressure::Critical); // memory is gone, nothing to shrink
+    assert_eq!(hub.subscribers(), 1);
+    drop(subscription);
+    assert_eq!(hub.subscribers(), 0);
+    Ok(())
+}
+
+#[test]
+fn cursor_reads_written_bytes() -> Result {
+    use {
+        platform_mem::{Global, MemCursor, MemWriter},
+        std::io::{Read, Seek, SeekFrom, Write},
+    };
+
+    let mut mem = Global::<u8>::new();
+    let mut writer = MemWriter::new(&mut mem);
+    for i in 0..1000u32 {
+        writer.write_all(&i.to_le_bytes())?;
+    }
+    writer.finish()?;
+    assert_eq!(mem.allocated().len(), 4000);
+
+    let mut cursor = MemCursor::new(&mem);
+    let mut word = [0; 4];
+    cursor.seek(SeekFrom::End(-4))?;
+    cursor.read_exact(&mut word)?;
+    assert_eq!(u32::from_le_bytes(word), 999);
+    assert!(cursor.read_exact(&mut word).is_err());
+    assert!(cursor.seek(SeekFrom::Current(-5000)).is_err());
+    Ok(())
+}
+
+#[test]
+fn alternate_debug_previews_items() -> Result {
+    use platform_mem::Global;
+
+    let mut bytes = Global::<u8>::new();
+    bytes.grow_from_slice(b"hello world")?;
+    let dump = format!("{:?}", bytes.hexdump());
+    assert!(dump.contains("00000000  68 65 6c 6c 6f 20 77 6f 72 6c 64"), "{dump}");
+    assert!(dump.contains("|hello world|"), "{dump}");
+    let alternate = format!("{bytes:#?}");
+    assert!(alternate.contains("len: 11") && alternate.contains("|hello world|"), "{alternate}");
+    assert!(!format!("{bytes:?}").contains("hello"));
+
+    bytes.grow_filled(1000, b'x')?;
+    let dump = format!("{:?}", bytes.hexdump());
+    assert_eq!(dump.lines().count(), 2 * 4 + 1, "{dump}");
+    assert!(dump.contains("\n...\n"), "{dump}");
+
+    let mut words = Global::<u64>::new();
+    words.grow_filled(100, 7)?;
+    assert!(format!("{:?}", words.debug_items()).contains("..84 more"));
+    words.shrink(90)?;
+    assert_eq!(format!("{:?}", words.debug_items()), format!("{:?}", [7; 10]));
+    words.grow_filled(90, 8)?;
+    let alternate = format!("{words:#?}");
+    assert!(alternate.contains("len: 100") && alternate.contains("..84 more"), "{alternate}");
+    Ok(())
+}
+
+#[test]
+fn file_error_has_path_and_size() -> Result {
+    use platform_mem::{Error, ErrorKind};
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("huge");
+    let mut mem = FileMapped::<u8>::from_path(&path)?;
+
+    // either the file or the address space is too small
+    let err = mem.grow_filled(1 << 62, 0).unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::Io(_)));
+    let Error::File { path: Some(failed), bytes, .. } = &err else { panic!("{err:?}") };
+    assert_eq!((failed, *bytes), (&path, 1 << 62));
+    assert!(err.to_string().contains("huge"));
+    Ok(())
+}
+
+#[test]
+fn mirror_failure_policies() -> Result {
+    use platform_mem::{FaultyMem, Global, MirrorPolicy, Mirrored};
+
+    let faulty = || FaultyMem::new(Global::<u8>::new()).fail_grow_at(2);
+
+    let mut mem = Mirrored::new(Global::new(), faulty(), MirrorPolicy::Fail)?;
+    mem.grow_from_slice(b"abc")?;
+    assert!(mem.grow_from_slice(b"def").is_err());
+    assert_eq!(mem.allocated(), b"abc");
+
+    let mut mem = Mirrored::new(Global::new(), faulty(), MirrorPolicy::Detach)?;
+    mem.grow_from_slice(b"abc")?;
+    mem.grow_from_slice(b"def")?;
+    assert_eq!(mem.allocated(), b"abcdef");
+    assert!(mem.mirror().is_none() && mem.detached_by().is_some());
+    Ok(())
+}
+
+#[test]
+fn cache_writes_through() -> Result {
+    use platform_mem::{Cached, FillPolicy, Global, WritePolicy};
+
+    let mut slow = Global::<u8>::new();
+    slow.grow_from_slice(b"abc")?;
+    let mut mem =
+        Cached::new(Global::new(), slow, FillPolicy::OnDemand, WritePolicy::WriteThrough)?;
+    assert!(!mem.is_loaded());
+
+    mem.grow_from_slice(b"def")?;
+    assert_eq!(mem.slow().allocated(), b"abcdef");
+    mem.allocated_mut()[0] = b'x';
+    mem.shrink(2)?;
+    assert_eq!((mem.allocated(), mem.slow().allocated()), (&b"xbcd"[..], &b"xbcd"[..]));
+    Ok(())
+}
+
+#[test]
+fn deferred_shrink_keeps_mapping() -> Result {
+    use platform_mem::TempFile;
+
+    let mut mem = TempFile::<u64>::new()?.with_deferred_shrink(512);
+    mem.grow_filled(1024, 1)?;
+    let generation = mem.generation();
+
+    mem.shrink(512)?;
+    mem.grow_filled(256, 2)?;
+    assert_eq!(mem.memory_usage().capacity, 8192);
+    assert_eq!(mem.allocated()[511..513], [1, 2]);
+    assert!(mem.generation() > generation);
+
+    mem.compact()?;
+    assert_eq!(mem.memory_usage().capacity, 768 * 8);
+    mem.shrink(768)?; // too much slack to keep
+    assert_eq!(mem.memory_usage().capacity, 0);
+    Ok(())
+}
+
+#[test]
+fn zero_sized_items() -> Result {
+    use {
+        platform_mem::Global,
+        std::sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+    #[derive(Clone)]
+    struct Unit;
+    impl Drop for Unit {
+        fn drop(&mut self) {
+            DROPS.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    let mut mem = Global::<Unit>::new();
+    mem.grow_filled(1000, Unit)?;
+    assert_eq!(mem.memory_usage().capacity, 0);
+    mem.shrink(990)?;
+    let before = DROPS.load(Ordering::Relaxed);
+    drop(mem);
+    assert_eq!(DROPS.load(Ordering::Relaxed) - before, 10);
+
+    let file = tempfile::NamedTempFile::new()?;
+    let mut mem = FileMapped::<()>::from_path(file.path())?;
+    mem.grow_filled(1000, ())?;
+    mem.shrink(500)?;
+    assert_eq!(mem.allocated().len(), 500);
+    assert_eq!(file.as_file().metadata()?.len(), 0);
+    Ok(())
+}
+
+#[test]
+fn over_aligned_items_are_rejected() {
+    use {platform_mem::TempFile, std::io};
+
+    #[derive(Debug)]
+    #[repr(align(1048576))]
+    struct Huge;
+
+    let err = TempFile::<Huge>::new().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    assert!(err.to_string().contains("1048576-byte alignment"), "{err}");
+}
+
+#[test]
+fn record_file_cuts_torn_append() -> Result {
+    use platform_mem::{ErrorKind, RecordFile};
+
+    let path = tempfile::NamedTempFile::new()?.into_temp_path();
+    let mut file = RecordFile::<u32>::open(&path)?;
+    file.append(1)?;
+    file.append(2)?;
+    // crash in the middle of the third append
+    file.into_inner().grow_from_slice(&[3, 0])?;
+
+    let file = RecordFile::<u32>::open(&path)?;
+    assert_eq!(file.iter().collect::<Vec<_>>(), [1, 2]);
+    drop(file);
+
+    let err = RecordFile::<u64>::open(&path).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Io(std::io::ErrorKind::InvalidData));
+    Ok(())
+}
+
+#[test]
+fn log_recovers_last_valid_entry() -> Result {
+    use platform_mem::{Global, LogMem};
+
+    let mut log = LogMem::open(Global::new())?;
+    log.append(b"first")?;
+    let torn = log.append(b"second")?;
+    log.append(b"")?;
+
+    let mut mem = log.into_inner();
+    mem.allocated_mut()[torn + 10] ^= 1;
+    let log = LogMem::open(mem)?;
+    assert_eq!(log.iter().collect::<Vec<_>>(), [b"first"]);
+    assert_eq!((log.len(), log.inner().allocated().len()), (1, 21));
+    Ok(())
+}
+
+#[quickcheck_macros::quickcheck]
+fn bits_rank_select_match_model(bits: Vec<u16>) -> bool {
+    use platform_mem::{BitMem, Global};
+
+    let mut mem = BitMem::new(Global::new());
+    let mut model: Vec<_> = bits.iter().map(|&bit| bit as usize).collect();
+    model.sort_unstable();
+    model.dedup();
+    for &bit in &bits {
+        mem.set(bit as usize).unwrap();
+    }
+
+    mem.ones().eq(model.iter().copied())
+        && model.iter().enumerate().all(|(rank, &bit)| {
+            mem.rank(bit) == rank && mem.select(rank) == Some(bit) && mem.get(bit)
+        })
+        && mem.select(model.len()).is_none()
+}
+
+#[test]
+fn str_pool_handles_survive_reopening() -> Result {
+    use platform_mem::{StrHandle, StrPool};
+
+    let path = tempfile::NamedTempFile::new()?.into_temp_path();
+    let mut pool = StrPool::new(FileMapped::from_path(&path)?)?;
+    let handle = pool.intern("links")?;
+    pool.intern("")?;
+    drop(pool);
+
+    let mut pool = StrPool::new(FileMapped::from_path(&path)?)?;
+    assert_eq!(pool.resolve(handle), Some("links"));
+    assert_eq!(pool.resolve(StrHandle { offset: u64::MAX, len: 1 }), None);
+    let other = pool.intern("links")?; // interned only since opening
+    assert_ne!(other, handle);
+    assert_eq!(pool.resolve(other), Some("links"));
+    Ok(())
+}
+
+#[test]
+fn blob_arena_coalesces_freed_tail() -> Result {
+    use platform_mem::{BlobArena, Global};
+
+    let mut arena = BlobArena::new(Global::new());
+    let a = arena.alloc(&[1; 10])?;
+    let b = arena.alloc(&[2; 20])?;
+    let c = arena.alloc(&[3; 30])?;
+
+    arena.free(a, 10);
+    arena.free(b, 20);
+    assert_eq!(arena.alloc(&[4; 25])?, a); // fits only in coalesced place
+    arena.free(c, 30);
+    assert_eq!((arena.free_bytes(), arena.inner().allocated().len()), (0, 25));
+    Ok(())
+}
+
+#[quickcheck_macros::quickcheck]
+fn free_list_keeps_live_ranges(ops: Vec<(bool, u8)>, best: bool) -> Result {
+    use platform_mem::{FitPolicy, FreeListMem, Global};
+
+    let fit = if best { FitPolicy::Best } else { FitPolicy::First };
+    let mut list = FreeListMem::new(Global::new(), fit);
+    let mut live = Vec::new();
+    for (id, (alloc, len)) in ops.into_iter().enumerate() {
+        if alloc || live.is_empty() {
+            let len = len as usize % 16 + 1;
+            live.push((list.alloc(len, id)?, len, id));
+        } else {
+            let (start, len, _) = live.swap_remove(len as usize % live.len());
+            list.free(start, len);
+        }
+        for &(start, len, id) in &live {
+            assert!(list.get(start, len).is_some_and(|items| items.iter().all(|&x| x == id)));
+        }
+        let used: usize = live.iter().map(|&(_, len, _)| len).sum();
+        assert_eq!(used + list.free_len(), list.inner().allocated().len());
+    }
+    Ok(())
+}
+
+#[test]
+fn retain_reports_progress() -> Result {
+    use platform_mem::{RawMem, TempFile, PROGRESS_STEP};
+
+    let mut mem = TempFile::new()?;
+    mem.grow_from_slice(&(0..150_000u32).collect::<Vec<_>>())?;
+
+    let mut reported = Vec::new();
+    let removed = mem.retain(|&x| x % 2 == 0, |done, _| reported.push(done))?;
+    assert_eq!(reported, [PROGRESS_STEP, 2 * PROGRESS_STEP, 150_000]);
+    assert_eq!(
+        (removed, mem.allocated().len(), mem.allocated()[74_999]),
+        (75_000, 75_000, 149_998)
+    );
+
+    let removed = mem.compact_ranges([0..10, 10..74_990], |_, _| {})?;
+    assert_eq!(
+        (removed, mem.allocated()),
+        (
+            74_990,
+            &[
+                149_980, 149_982, 149_984, 149_986, 149_988, 149_990, 149_992, 149_994, 149_996,
+                149_998
+            ][..]
+        )
+    );
+    Ok(())
+}
+
+#[test]
+fn columns_stay_in_sync_on_failure() -> Result {
+    use platform_mem::{Columns, Global, Limited, RawMem};
+
+    let mut columns = Columns::new((Global::new(), Limited::new(Global::new(), 3), Global::new()));
+    columns.grow_rows_filled(2, (1u8, 2u16, 3u32))?;
+    columns.push_row((4, 5, 6))?;
+
+    assert!(columns.push_row((7, 8, 9)).is_err());
+    assert!(columns.grow_rows_filled(2, (0, 0, 0)).is_err());
+    let (a, b, c) = columns.inner();
+    assert_eq!(
+        (a.allocated(), b.allocated(), c.allocated()),
+        (&[1, 1, 4][..], &[2, 2, 5][..], &[3, 3, 6][..])
+    );
+
+    columns.shrink_rows(2)?;
+    assert_eq!(columns.slices(), (&[1][..], &[2][..], &[3][..]));
+    Ok(())
+}
+
+#[test]
+fn double_buffer_is_never_torn() -> Result {
+    use {
+        platform_mem::{DoubleBuffer, Global, RawMem},
+        std::thread,
+    };
+
+    let mut buffer = DoubleBuffer::new(Global::<u64>::new(), Global::new());
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let reader = buffer.reader();
+            thread::spawn(move || {
+                for _ in 0..1_000 {
+                    let items = reader.read();
+                    assert!(items.iter().all(|&x| x as usize == items.len()));
+                }
+            })
+        })
+        .collect();
+
+    for version in 1..=100 {
+        buffer.write_back().grow_filled(1, 0)?;
+        buffer.write_back().allocated_mut().fill(version);
+        buffer.swap();
+        buffer.sync_back()?;
+    }
+    for reader in readers {
+        reader.join().unwrap();
+    }
+    assert_eq!(buffer.front().allocated(), buffer.back().allocated());
+    Ok(())
+}
+
+#[test]
+fn nested_scratch_memories_differ() -> Result {
+    use platform_mem::{scratch, RawMem};
+
+    scratch::with::<u8, _>(16, |outer| {
+        outer.grow_from_slice(b"outer")?;
+        scratch::with::<u8, _>(16, |inner| {
+            assert!(inner.allocated().is_empty());
+            inner.grow_from_slice(b"inner").map(drop)
+        })??;
+        assert_eq!(outer.allocated(), b"outer");
+        Ok::<_, platform_mem::Error>(())
+    })??;
+    scratch::with::<u8, _>(0, |mem| assert!(mem.allocated().is_empty()))?;
+    Ok(())
+}
+
+#[test]
+fn manual_drop_keeps_file_items() -> Result {
+    use {
+        platform_mem::{RawMem, TempFile},
+        std::sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+    struct Counted(#[allow(dead_code)] u64);
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            DROPS.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    let mut mem = TempFile::new()?.with_manual_drop();
+    mem.grow_with(10, || Counted(1))?;
+    mem.shrink(4)?;
+    drop(mem);
+    assert_eq!(DROPS.load(Ordering::Relaxed), 0);
+    Ok(())
+}
+
+#[test]
+fn failed_migration_keeps_file() -> Result {
+    use {platform_mem::Migrations, std::io};
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("data");
+    Migrations::new(*b"testdata", 1).open(&path)?.grow_from_slice(b"payload")?;
+    let original = std::fs::read(&path)?;
+
+    let broken = Migrations::new(*b"testdata", 3)
+        .step(1, Ok)
+        .step(2, |_| Err(io::Error::other("broken step")));
+    assert!(broken.open(&path).is_err());
+    assert!(Migrations::new(*b"testdata", 3).step(2, Ok).open(&path).is_err()); // no step from 1
+    assert_eq!(std::fs::read(&path)?, original);
+    assert!(!dir.path().join("data.v1.bak").exists());
+
+    assert!(Migrations::new(*b"testdata", 2).step(1, Ok).open(&path).is_ok());
+    assert!(Migrations::new(*b"testdata", 1).open(&path).is_err()); // newer file
+    assert!(Migrations::new(*b"otherfmt", 2).open(&path).is_err());
+    Ok(())
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn flush_sparse_punches_zero_pages() -> Result {
+    use std::os::unix::fs::MetadataExt;
+
+    let file = tempfile::NamedTempFile::new()?;
+    let mut mem = FileMapped::<u8>::new(file.reopen()?)?;
+    mem.grow_filled(1 << 20, 1)?;
+    mem.flush()?;
+    let blocks = file.as_file().metadata()?.blocks();
+
+    mem.allocated_mut()[4096..].fill(0);
+    assert!(mem.flush_sparse()? >= (1 << 20) - 4096);
+    assert!(file.as_file().metadata()?.blocks() < blocks / 2);
+    assert!(mem.allocated()[..4096].iter().all(|&x| x == 1));
+    assert!(mem.allocated()[4096..].iter().all(|&x| x == 0));
+    Ok(())
+}
+
+#[test]
+fn huge_pages_round_only_large_grows() -> Result {
+    use platform_mem::Global;
+
+    let mut mem = Global::<u64>::new().with_huge_pages(1 << 20);
+    mem.grow_filled(1000, 7)?;
+    assert_eq!(mem.memory_usage().capacity, 8000);
+
+    mem.grow_filled(200_000, 7)?;
+    assert_eq!(mem.memory_usage().capacity, 2 << 20);
+    assert!(mem.allocated().iter().all(|&item| item == 7));
+
+    mem.shrink(200_000)?;
+    mem.compact()?;
+    assert_eq!(mem.memory_usage().capacity, 8000);
+    Ok(())
+}
+
+#[test]
+fn unmap_pool_keeps_contents_of_each_file() -> Result {
+    let mut mems = [
+        FileMapped::<u32>::new(tempfile::tempfile()?)?.with_unmap_pool(),
+        FileMapped::<u32>::new(tempfile::tempfile()?)?.with_unmap_pool(),
+    ];
+
+    for round in 0..4 {
+        for (i, mem) in mems.iter_mut().enumerate() {
+            mem.grow_filled(50_000, (round * 2 + i) as u32)?;
+            mem.shrink(49_000)?;
+        }
+    }
+    for (i, mem) in mems.iter().enumerate() {
+        let expected: Vec<_> = (0..4).flat_map(|round| [(round * 2 + i) as u32; 1000]).collect();
+        assert_eq!(mem.allocated(), expected);
+    }
+    Ok(())
+}
+
+#[test]
+fn mapped_readers_follow_shrinks() -> Result {
+    use std::thread;
+
+    let mut mem = FileMapped::<u64>::new(tempfile::tempfile()?)?;
+    let readers = (0..4).map(|_| mem.reader()).collect::<result::Result<Vec<_>, _>>()?;
+    let handles: Vec<_> = readers
+        .into_iter()
+        .map(|mut reader| {
+            thread::spawn(move || {
+                for _ in 0..200 {
+                    let items = reader.read()?;
+                    assert!(items.iter().enumerate().all(|(i, item)| item == i as u64));
+                }
+                std::io::Result::Ok(())
+            })
+        })
+        .collect();
+
+    for _ in 0..50 {
+        let mut next = 0..;
+        mem.grow_with(100_000, || next.next().unwrap_or_default())?;
+        mem.shrink(100_000)?;
+    }
+    for handle in handles {
+        handle.join().expect("reader panicked")?;
+    }
+    Ok(())
+}
+
+fn global_of(items: &[i32]) -> platform_mem::Result<platform_mem::Global<i32>> {
+    let mut mem = platform_mem::Global::new();
+    mem.grow_from_slice(items)?;
+    Ok(mem)
+}
+
+#[test]
+fn merge_keeps_elements_truncated_by_theirs() -> Result {
+    let base = [1, 2, 3, 4, 5];
+    let mut ours = global_of(&[1, 2, 3, 4, 5, 6])?;
+    ours.merge_from(&base, &global_of(&[1, 2, 3])?, |_| unreachable!("nothing conflicts"))?;
+    assert_eq!(ours.allocated(), [1, 2, 3, 4, 5, 6]);
+
+    // ours changed a truncated element, theirs side of the conflict is empty
+    let mut ours = global_of(&[1, 2, 3, 0, 5, 6])?;
+    let mut conflicts = Vec::new();
+    ours.merge_from(&base, &global_of(&[1, 2, 3])?, |conflict| {
+        conflicts.push((conflict.range.clone(), conflict.ours.to_vec(), conflict.theirs.to_vec()));
+        platform_mem::merge::Resolution::Theirs
+    })?;
+    assert_eq!(conflicts, [(3..5, vec![0, 5], vec![])]);
+    assert_eq!(ours.allocated(), [1, 2, 3, 0, 5, 6]);
+
+    // only theirs changed the length
+    let mut ours = global_of(&[1, 0, 3, 4, 5])?;
+    ours.merge_from(&base, &global_of(&[1, 2, 3])?, |_| unreachable!("nothing conflicts"))?;
+    assert_eq!(ours.allocated(), [1, 0, 3]);
+    Ok(())
+}
+
+#[test]
+fn merge_resolves_overlapping_changes() -> Result {
+    use platform_mem::{merge::Resolution, ErrorKind};
+
+    let base = [1, 2, 3, 4];
+    let theirs = global_of(&[1, 8, 3, 5])?;
+
+    let mut ours = global_of(&[1, 9, 3, 4])?;
+    ours.merge_from(&base, &theirs, |conflict| {
+        assert_eq!((conflict.base, conflict.ours, conflict.theirs), (&[2][..], &[9][..], &[8][..]));
+        Resolution::Custom(vec![17])
+    })?;
+    assert_eq!(ours.allocated(), [1, 17, 3, 5]);
+
+    let mut ours = global_of(&[1, 9, 3, 4])?;
+    ours.merge_from(&base, &theirs, |_| Resolution::Ours)?;
+    assert_eq!(ours.allocated(), [1, 9, 3, 5]);
+
+    // wrong resolution is found before anything is merged
+    let mut ours = global_of(&[1, 9, 3, 4, 0])?;
+    let err = ours.merge_from(&base, &theirs, |_| Resolution::Custom(vec![])).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Io(std::io::ErrorKind::InvalidInput));
+    assert_eq!(ours.allocated(), [1, 9, 3, 4, 0]);
+    Ok(())
+}
+
+#[test]
+fn seqlock_readers_never_see_torn_writes() -> Result {
+    use {
+        platform_mem::{Global, SeqlockMem},
+        std::{
+            panic::{self, AssertUnwindSafe},
+            thread,
+        },
+    };
+
+    let lock = SeqlockMem::<u64, _>::new(Global::new(), 64)?;
+    thread::scope(|s| {
+        for _ in 0..2 {
+            s.spawn(|| {
+                for i in 0..1_000 {
+                    lock.write(|data| data.fill(i));
+                }
+            });
+        }
+        for _ in 0..4 {
+            s.spawn(|| {
+                for _ in 0..1_000 {
+                    let data = lock.load();
+                    assert!(data.iter().all(|&x| x == data[0]), "torn read: {data:?}");
+                }
+            });
+        }
+    });
+    assert_eq!(lock.load(), [999; 64]);
+
+    // moved memory keeps its place, a panicked writer publishes nothing
+    let lock = Box::new(lock);
+    let panicked = panic::catch_unwind(AssertUnwindSafe(|| {
+        lock.write(|data| {
+            data[0] = 1;
+            panic!("writer fails");
+        })
+    }));
+    assert!(panicked.is_err());
+    assert_eq!(lock.load(), [999; 64]);
+    Ok(())
+}
+
+#[test]
+fn transaction_commits_or_rolls_back() -> Result {
+    use platform_mem::{Global, Transactional};
+
+    let mut mem = Transactional::new(Global::new());
+    mem.grow_from_slice(&[1, 2, 3])?;
+
+    let mut tx = mem.begin();
+    tx.allocated_mut()[0] = 10;
+    tx.shrink(1)?;
+    tx.commit();
+    assert_eq!(mem.allocated(), [10, 2]);
+
+    let mut tx = mem.begin();
+    tx.shrink(2)?;
+    tx.grow_filled(4, 7)?;
+    tx.rollback()?;
+    assert_eq!(mem.allocated(), [10, 2]);
+
+    {
+        let mut tx = mem.begin();
+        tx.allocated_mut().fill(0);
+        tx.grow_filled(1_000, 1)?;
+    }
+    assert_eq!(mem.allocated(), [10, 2]);
+
+    // empty memory is restored as empty
+    mem.shrink(2)?;
+    let mut tx = mem.begin();
+    tx.grow_filled(3, 1)?;
+    drop(tx);
+    assert!(mem.allocated().is_empty());
+    Ok(())
+}
+
+#[test]
+fn cow_snapshots_stay_isolated() -> Result {
+    use platform_mem::CowMem;
+
+    let mut mem = CowMem::with_page_len(FileMapped::<u64>::new(tempfile::tempfile()?)?, 2);
+    mem.extend_from_slice(&[1, 2, 3, 4, 5])?;
+    let first = mem.snapshot();
+    let version = mem.version();
+
+    // reads and snapshots are not mutations
+    assert_eq!((mem.get(4), mem.get(5)), (Some(&5), None));
+    let _ = mem.snapshot();
+    assert_eq!(mem.version(), version);
+
+    mem.set(0, 10);
+    mem.push(6)?;
+    // page of untouched `[3, 4]` is still shared with the first snapshot
+    assert_eq!((mem.version(), mem.shared_pages()), (version + 2, 1));
+    let second = mem.snapshot();
+
+    mem.truncate(3)?;
+    mem.set(1, 20);
+    assert_eq!(first.iter().copied().collect::<Vec<_>>(), [1, 2, 3, 4, 5]);
+    assert_eq!(second.iter().copied().collect::<Vec<_>>(), [10, 2, 3, 4, 5, 6]);
+    assert_eq!(mem.allocated(), [10, 20, 3]);
+
+    mem.truncate(0)?;
+    assert!(mem.snapshot().is_empty() && first.len() == 5);
+    Ok(())
+}
+
+#[test]
+fn append_readers_see_prefixes_of_appends() -> Result {
+    use {
+        platform_mem::{AppendMem, Error, Global, Limited},
+        std::thread,
+    };
+
+    let (mut writer, reader) = AppendMem::new(7, Global::<usize>::new);
+    thread::scope(|s| {
+        for _ in 0..4 {
+            let reader = reader.clone();
+            s.spawn(move || {
+                let mut seen = 0;
+                while seen < 10_000 {
+                    let items: Vec<_> = reader.iter().copied().collect();
+                    assert!(items.len() >= seen);
+                    assert!(items.iter().enumerate().all(|(i, &x)| x == i));
+                    seen = items.len();
+                }
+            });
+        }
+        s.spawn(move || {
+            for i in (0..10_000).step_by(2) {
+                writer.push(i)?;
+                writer.extend_from_slice(&[i + 1])?;
+            }
+            platform_mem::Result::Ok(())
+        })
+        .join()
+        .unwrap()
+    })?;
+    assert_eq!((reader.len(), reader.get(9_999), reader.get(10_000)), (10_000, Some(&9_999), None));
+    assert!(reader
+        .segments()
+        .map(<[_]>::len)
+        .eq([7, 14, 28, 56, 112, 224, 448, 896, 1792, 3584, 2839]));
+
+    // segment which can't grow publishes nothing
+    let (mut writer, reader) = AppendMem::new(4, || Limited::new(Global::<u8>::new(), 4));
+    writer.extend_from_slice(b"abc")?;
+    let err = writer.extend_from_slice(b"de").unwrap_err();
+    assert_eq!(err, Error::QuotaExceeded { limit: 4, requested: 8 });
+    writer.push(b'd')?;
+    assert_eq!(reader.segments().collect::<Vec<_>>(), [b"abcd"]);
+    Ok(())
+}
+
+#[test]
+fn append_segments_are_reopened_from_files() -> Result {
+    use platform_mem::AppendMem;
+
+    let dir = tempfile::tempdir()?;
+    let segments = |dir: std::path::PathBuf| {
+        let mut next = 0;
+        move || {
+            next += 1;
+            FileMapped::<u64>::from_path(dir.join(format!("links.{next}"))).unwrap()
+        }
+    };
+
+    let (mut writer, _) = AppendMem::new(2, segments(dir.path().into()));
+    writer.extend_from_slice(&[1, 2, 3, 4, 5])?;
+    drop(writer);
+
+    let (mut writer, reader) = AppendMem::open(2, 5, segments(dir.path().into()))?;
+    writer.push(6)?;
+    assert_eq!(reader.iter().copied().collect::<Vec<_>>(), [1, 2, 3, 4, 5, 6]);
+    assert_eq!(reader.segments().count(), 2);
+    Ok(())
+}
+
+#[test]
+fn epoch_update_does_not_block_pins() -> Result {
+    use {
+        platform_mem::{EpochMem, Error, Global},
+        std::{panic, thread},
+    };
+
+    let mut mem = EpochMem::new(Global::new(), Global::new);
+    mem.update(|mem| mem.grow_filled(2, 1).map(drop))?;
+    let reader = mem.reader();
+
+    // failed in-place update may have changed the version, so it has the next epoch
+    let err = mem
+        .update(|mem| {
+            mem.allocated_mut()[0] = 3;
+            mem.grow_filled(usize::MAX, 0).map(drop)
+        })
+        .unwrap_err();
+    assert!(matches!(err, Error::CapacityOverflow));
+    assert_eq!((mem.epoch(), &*mem.pin()), (2, &[3, 1][..]));
+
+    // readers pin the current version while the new one is updated
+    let pinned = mem.pin();
+    mem.update(|mem| {
+        let other = thread::scope(|s| s.spawn(|| reader.pin().epoch()).join().unwrap());
+        assert_eq!(other, 2);
+        mem.grow_filled(1, 2).map(drop)
+    })?;
+    assert_eq!((pinned.epoch(), &*pinned, mem.epoch(), mem.pin().len()), (2, &[3, 1][..], 3, 3));
+
+    // failed update of a fork discards it
+    let current = mem.pin();
+    assert!(mem.update(|_| Err::<(), _>(Error::CapacityOverflow)).is_err());
+    assert_eq!(mem.epoch(), 3);
+    drop((pinned, current));
+
+    // panicking in-place update still publishes the version
+    let panicked = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        mem.update(|_| -> platform_mem::Result<()> { panic!("update failed") })
+    }));
+    assert!(panicked.is_err());
+    assert_eq!((reader.pin().epoch(), reader.pin().len()), (4, 3));
+    Ok(())
+}
+
+#[test]
+fn shared_ignores_panics_under_guards() -> Result {
+    use {
+        platform_mem::{Global, Shared},
+        std::thread,
+    };
+
+    let shared = Shared::new(Global::<u64>::new());
+    shared.write().grow_filled(2, 1)?;
+    let other = shared.clone();
+    let panicked = thread::spawn(move || unsafe {
+        other
+            .write()
+            .grow(3, |_, (_, uninit)| {
+                uninit[0].write(2);
+                panic!("fill failed");
+            })
+            .map(drop)
+    });
+    assert!(panicked.join().is_err());
+
+    assert_eq!(shared.read().allocated(), [1, 1]);
+    shared.write().grow_filled(1, 3)?;
+    assert_eq!(shared.try_unwrap().map_err(drop).unwrap().allocated(), [1, 1, 3]);
+    Ok(())
+}
+
+#[test]
+fn backup_pointer_must_name_a_backup() -> Result {
+    use {
+        platform_mem::{BackupSet, Global},
+        std::{fs, io},
+    };
+
+    let dir = tempfile::tempdir()?;
+    let backups = BackupSet::new(dir.path().join("set"), "links", 2)?;
+    assert_eq!(backups.latest()?, None);
+
+    let mut mem = Global::<u64>::new();
+    mem.grow_filled(1, 7)?;
+    let path = backups.backup(&mem)?;
+    assert_eq!(backups.latest()?, Some(path));
+
+    let pointer = dir.path().join("set/links.latest");
+    for name in ["../links.0", "/etc/passwd", "links.0/..", "other.0", ""] {
+        fs::write(&pointer, name)?;
+        assert_eq!(backups.latest().unwrap_err().kind(), io::ErrorKind::InvalidData, "{name}");
+    }
+    Ok(())
+}
+
+#[test]
+fn kept_place_is_assumed_after_deferred_shrink() -> Result {
+    let mut mem = FileMapped::<u64>::new(tempfile::tempfile()?)?.with_high_water_mark();
+    mem.grow_from_slice(&[1, 2, 3, 4, 5])?;
+    mem.shrink(4)?;
+    assert_eq!(unsafe { mem.grow_assumed(2)? }, [2, 3]);
+
+    // kept tail is followed by zeroed file data
+    mem.shrink(2)?;
+    assert_eq!(unsafe { mem.grow_assumed(5)? }, [2, 3, 4, 5, 0]);
+
+    let mut mem = FileMapped::<u64>::new(tempfile::tempfile()?)?.with_deferred_shrink(2);
+    mem.grow_from_slice(&[1, 2, 3])?;
+    mem.shrink(2)?;
+    unsafe { mem.grow_zeroed_exact(3)? };
+    assert_eq!(mem.allocated(), [1, 2, 3, 0]);
+    Ok(())
+}
+
+#[test]
+fn volatile_accesses_go_through_the_mapping() -> Result {
+    use std::panic;
+
+    let file = tempfile::NamedTempFile::new()?;
+    let mut writer = FileMapped::<u32>::from_path(file.path())?;
+    let mut reader = FileMapped::<u32>::from_path(file.path())?;
+    writer.grow_filled(4, 0)?;
+    unsafe { reader.grow_assumed(4)? };
+
+    for i in 0..4 {
+        writer.write_volatile(i, i as u32 + 1);
+    }
+    assert_eq!((0..4).map(|i| reader.read_volatile(i)).collect::<Vec<_>>(), [1, 2, 3, 4]);
+
+    let out_of_bounds = panic::catch_unwind(panic::AssertUnwindSafe(|| reader.read_volatile(4)));
+    assert!(out_of_bounds.is_err());
+    assert!(panic::catch_unwind(panic::AssertUnwindSafe(|| writer.write_volatile(4, 0))).is_err());
+    Ok(())
+}
+
+#[test]
+fn barrier_after_any_resize() -> Result {
+    use std::fs;
+
+    let file = tempfile::NamedTempFile::new()?;
+    let mut mem = FileMapped::<u64>::from_path(file.path())?;
+    mem.barrier()?; // nothing is mapped yet
+
+    mem.grow_from_slice(&[1, 2, 3])?;
+    mem.barrier()?;
+    assert_eq!(fs::read(file.path())?[..24], *bytemuck::cast_slice::<u64, u8>(&[1, 2, 3]));
+
+    mem.grow_filled(10_000, 4)?;
+    mem.shrink(10_001)?;
+    mem.allocated_mut()[1] = 5;
+    mem.barrier()?;
+    assert_eq!(fs::read(file.path())?[..16], *bytemuck::cast_slice::<u64, u8>(&[1, 5]));
+
+    mem.shrink(2)?;
+    mem.barrier()?;
+    Ok(())
+}
+
+#[test]
+fn shared_splits_only_the_last_handle() -> Result {
+    use {
+        platform_mem::{Global, Shared},
+        std::thread,
+    };
+
+    let shared = Shared::new(Global::<u64>::new());
+    let other = shared.clone();
+    let shared = shared.split().map(drop).unwrap_err();
+    assert_eq!(shared.handles(), 2);
+    drop(other);
+
+    let (writer, reader) = shared.split().map_err(drop).unwrap();
+    assert!(reader.is_empty() && reader.get(0).is_none());
+    let readers: Vec<_> = (0..4).map(|_| writer.reader()).collect();
+    thread::scope(|s| {
+        s.spawn(|| writer.write().grow_filled(100, 1).map(drop).unwrap());
+        for reader in &readers {
+            s.spawn(move || assert!([0, 100].contains(&reader.read().len())));
+        }
+    });
+    assert_eq!((reader.len(), reader.get(99), reader.get(100)), (100, Some(1), None));
+    Ok(())
+}
+
+#[test]
+fn sharded_indexes_skip_empty_shards() -> Result {
+    use platform_mem::{Global, ShardedMem};
+
+    let mut mem = ShardedMem::<Global<u8>>::with_shards(0, Global::new);
+    assert!(mem.is_empty() && mem.locate(0).is_none() && mem.get_mut(0).is_none());
+
+    let mut mem = ShardedMem::with_shards(3, Global::new);
+    mem.shards_mut()[2].grow_from_slice(b"cd")?;
+    mem.shards_mut()[0].grow_from_slice(b"ab")?;
+    assert_eq!((mem.len(), mem.locate(2), mem.locate(4)), (4, Some((2, 0)), None));
+
+    *mem.get_mut(3).unwrap() = b'e';
+    mem.iter_mut().for_each(|x| x.make_ascii_uppercase());
+    assert_eq!(mem.iter().copied().collect::<Vec<_>>(), b"ABCE");
+    assert_eq!(mem.into_shards()[1].allocated(), b"");
+    Ok(())
+}
+
+#[test]
+fn snapshot_restores_into_other_memories() -> Result {
+    use platform_mem::{ErrorKind, Global, Limited};
+
+    let mut mem = Global::<u64>::new();
+    mem.grow_from_slice(&[1, 2, 3])?;
+    let snapshot = mem.snapshot();
+    assert_eq!((snapshot.as_slice(), snapshot.len()), (&[1, 2, 3][..], 3));
+
+    let mut file = FileMapped::<u64>::new(tempfile::tempfile()?)?;
+    file.grow_filled(10, 7)?;
+    file.restore(&snapshot)?; // shrinks
+    assert_eq!(file.allocated(), [1, 2, 3]);
+    file.restore(&Global::<u64>::new().snapshot())?;
+    assert!(file.allocated().is_empty());
+
+    // memory which can't grow to the snapshot keeps only the common part
+    let mut limited = Limited::with_max_bytes(Global::<u64>::new(), 16);
+    limited.grow_filled(1, 0)?;
+    let err = limited.restore(&snapshot).unwrap_err();
+    assert_eq!((err.kind(), limited.allocated()), (ErrorKind::QuotaExceeded, &[1][..]));
+    assert_eq!(snapshot.into_vec(), [1, 2, 3]);
+    Ok(())
+}
+
+#[test]
+fn pinned_detects_resizes() -> Result {
+    use std::panic;
+
+    let mut mem = FileMapped::<u64>::new(tempfile::tempfile()?)?;
+    mem.grow_filled(3, 1)?;
+    let pinned = mem.pin();
+    pinned.get_mut(&mut mem)[0] = 2; // writes don't invalidate the slice
+    assert_eq!((pinned.is_valid(&mem), pinned.get(&mem)), (true, &[2, 1, 1][..]));
+
+    mem.shrink(1)?;
+    assert!(!pinned.is_valid(&mem) && mem.pin().generation() > pinned.generation());
+    assert!(panic::catch_unwind(panic::AssertUnwindSafe(|| pinned.get(&mem).len())).is_err());
+
+    // backends which don't track generations never invalidate pins
+    let mut mem = platform_mem::MockMem::<u8>::new();
+    let pinned = mem.pin();
+    mem.grow_filled(1, 0)?;
+    assert!(pinned.is_valid(&mem));
+    Ok(())
+}
+
+#[test]
+fn save_points_nest_and_discard() -> Result {
+    use platform_mem::{Global, Limited, Pop, SavePoints};
+
+    let mut mem = SavePoints::new(Limited::new(Global::new(), 4));
+    assert!(!mem.pop_state(Pop::Restore)?);
+    mem.grow_from_slice(&[1, 2])?; // not recorded without save points
+
+    mem.push_state();
+    mem.write(0, &[3])?;
+    mem.push_state();
+    mem.grow_filled(2, 4)?;
+    assert!(mem.grow_filled(1, 5).is_err()); // failed grow is not undone
+    assert!(mem.pop_state(Pop::Discard)?);
+    assert_eq!((mem.depth(), mem.allocated()), (1, &[3, 2, 4, 4][..]));
+
+    // outer save point also undoes discarded changes
+    assert!(mem.pop_state(Pop::Restore)?);
+    assert_eq!((mem.depth(), mem.allocated()), (0, &[1, 2][..]));
+
+    mem.push_state();
+    mem.shrink(2)?;
+    mem.pop_state(Pop::Discard)?;
+    mem.push_state();
+    mem.pop_state(Pop::Restore)?;
+    assert!(mem.allocated().is_empty());
+    Ok(())
+}
+
+#[test]
+fn stats_count_failures_and_reset() -> Result {
+    use platform_mem::{Global, Limited, StatsMem};
+
+    let mut mem = StatsMem::new(Limited::new(Global::<u32>::new(), 4));
+    mem.grow_filled(3, 0)?;
+    assert!(mem.grow_filled(2, 0).is_err());
+    mem.shrink(2)?;
+
+    let stats = mem.stats();
+    assert_eq!((stats.grows, stats.shrinks, stats.failures), (1, 1, 1));
+    assert_eq!((stats.bytes, stats.peak_len), (4, 3));
+
+    mem.reset_stats();
+    let stats = mem.stats();
+    assert_eq!((stats.grows, stats.failures, stats.bytes, stats.peak_len), (0, 0, 4, 1));
+
+    // existing items are counted from the start
+    let mut inner = Global::<u32>::new();
+    inner.grow_filled(5, 0)?;
+    assert_eq!(StatsMem::new(inner).stats().peak_len, 5);
+    Ok(())
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn tracing_spans_close_on_failed_resizes() -> Result {
+    use {
+        std::sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc, Mutex,
+        },
+        tracing::{span, Event, Metadata, Subscriber},
+    };
+
+    #[derive(Default)]
+    struct Spans {
+        names: Mutex<Vec<&'static str>>,
+        done: AtomicU64,
+    }
+
+    impl Subscriber for &'static Spans {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+            let mut names = self.names.lock().unwrap();
+            names.push(span.metadata().name());
+            span::Id::from_u64(names.len() as u64)
+        }
+
+        fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+        fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+        fn event(&self, _: &Event<'_>) {
+            self.done.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn enter(&self, _: &span::Id) {}
+
+        fn exit(&self, _: &span::Id) {}
+    }
+
+    let spans: &'static Spans = Box::leak(Box::default());
+    tracing::subscriber::with_default(Arc::new(spans), || -> Result {
+        let mut mem = FileMapped::<u64>::new(tempfile::tempfile()?)?.with_label("traced");
+        mem.grow_filled(10, 0)?;
+        assert!(mem.grow_filled(usize::MAX, 0).is_err());
+        mem.shrink(10)?;
+        Ok(())
+    })?;
+
+    let names = spans.names.lock().unwrap();
+    assert_eq!(names.iter().filter(|&&name| name == "reserve").count(), 2);
+    assert!(names.contains(&"mmap") && names.contains(&"shrink"));
+    // every span reports its time, including the failed one
+    assert_eq!(spans.done.load(Ordering::Relaxed), names.len() as u64);
+    Ok(())
+}
+
+#[test]
+fn observer_sees_events_and_errors() -> Result {
+    use {
+        platform_mem::MemObserver,
+        std::{
+            error::Error,
+            sync::{Arc, Mutex},
+        },
+    };
+
+    #[derive(Clone, Default)]
+    struct Events(Arc<Mutex<Vec<String>>>);
+
+    impl MemObserver for Events {
+        fn on_grow(&self, old: usize, new: usize) {
+            self.0.lock().unwrap().push(format!("grow {old}..{new}"));
+        }
+
+        fn on_shrink(&self, old: usize, new: usize) {
+            self.0.lock().unwrap().push(format!("shrink {old}..{new}"));
+        }
+
+        fn on_flush(&self) {
+            self.0.lock().unwrap().push("flush".into());
+        }
+
+        fn on_error(&self, _: &(dyn Error + 'static)) {
+            self.0.lock().unwrap().push("error".into());
+        }
+    }
+
+    let events = Events::default();
+    let mut mem = FileMapped::<u64>::new(tempfile::tempfile()?)?;
+    mem.set_observer(events.clone());
+    mem.grow_filled(4, 0)?;
+    assert!(mem.grow_filled(usize::MAX, 0).is_err());
+    mem.shrink(1)?;
+    mem.flush()?;
+
+    assert!(mem.take_observer().is_some());
+    mem.grow_filled(1, 0)?;
+    assert_eq!(*events.0.lock().unwrap(), ["grow 0..4", "error", "shrink 4..3", "flush"]);
+    Ok(())
+}
+
+#[test]
+fn history_records_failures_and_zero_capacity() -> Result {
+    use platform_mem::{Call, Global, HistoryMem, Limited};
+
+    let mut mem = HistoryMem::new(Limited::new(Global::<u8>::new(), 2), 8);
+    mem.grow_filled(2, 0)?;
+    assert!(mem.grow_filled(1, 0).is_err());
+    let last = mem.history().last().unwrap();
+    assert_eq!((last.call, last.len, last.result.is_err()), (Call::Grow { addition: 1 }, 2, true));
+
+    mem.clear_history();
+    assert_eq!(mem.history().len(), 0);
+
+    // nothing is kept without capacity, but calls still work
+    let mut mem = HistoryMem::new(Global::<u8>::new(), 0);
+    mem.grow_filled(3, 0)?;
+    mem.shrink(1)?;
+    assert_eq!((mem.history().len(), mem.allocated().len()), (0, 2));
+    Ok(())
+}
+
+#[cfg(feature = "registry")]
+#[test]
+fn registry_tracks_file_mappings() -> Result {
+    use platform_mem::registry;
+
+    let entry = |label: &str| {
+        registry::live().into_iter().find(|entry| entry.label.as_deref() == Some(label))
+    };
+
+    let mut mem = FileMapped::<u64>::new(tempfile::tempfile()?)?.with_label("registry-file");
+    assert_eq!(entry("registry-file"), None); // registered at the first grow
+    mem.grow_filled(4, 0)?;
+    mem.grow_filled(4, 0)?;
+    mem.shrink(2)?;
+
+    let found = entry("registry-file").unwrap();
+    assert_eq!((found.backend, found.len, found.bytes), ("FileMapped", 6, 48));
+    assert_eq!((found.grows, found.shrinks), (2, 1));
+
+    // token is not a live memory
+    let token = mem.into_token();
+    assert_eq!(entry("registry-file"), None);
+    let mem = FileMapped::from_token(token)?;
+    assert_eq!(entry("registry-file").map(|entry| entry.len), Some(6));
+    drop(mem);
+    assert_eq!(entry("registry-file"), None);
+    Ok(())
+}
+
+#[test]
+fn labels_name_errors_and_debug() -> Result {
+    use platform_mem::{Error, ErrorKind, Global};
+
+    let mut mem = FileMapped::<u64>::new(tempfile::tempfile()?)?.with_label("links");
+    let err = mem.grow_filled(usize::MAX, 0).unwrap_err();
+    assert!(matches!(&err, Error::Labeled { label, .. } if label == "links"));
+    assert_eq!(err.kind(), ErrorKind::CapacityOverflow);
+    assert!(err.to_string().starts_with("`links`: "));
+    assert!(format!("{mem:?}").contains("links"));
+
+    // unlabeled memories return plain errors
+    let err = Global::<u64>::new().grow_filled(usize::MAX, 0).unwrap_err();
+    assert!(matches!(err, Error::CapacityOverflow));
+    assert_eq!(Global::<u64>::new().with_label("").label(), Some(""));
+    Ok(())
+}
+
+#[cfg(all(debug_assertions, feature = "watchdog"))]
+#[test]
+fn watchdog_reports_truncated_file() -> Result {
+    use std::panic;
+
+    let file = tempfile::tempfile()?;
+    let mut mem = FileMapped::<u64>::new(file.try_clone()?)?.with_high_water_mark();
+    mem.grow_filled(1024, 0)?;
+
+    // other handle breaks the mapping, items are not touched after it
+    file.set_len(0)?;
+    let panic = panic::catch_unwind(panic::AssertUnwindSafe(|| mem.shrink(1))).unwrap_err();
+    let report = panic.downcast_ref::<String>().unwrap();
+    assert!(report.starts_with("`FileMapped` is broken after `shrink`"), "{report}");
+    assert!(report.contains("file of 0 bytes is shorter than 8192 mapped bytes"), "{report}");
+    Ok(())
+}
+
+#[test]
+fn writer_fills_limited_memory() -> Result {
+    use {
+        platform_mem::{Error, Global, Limited, MemWriter},
+        std::io::{self, Write},
+    };
+
+    let mut mem = Limited::new(Global::<u8>::new(), 4500);
+    let mut writer = MemWriter::new(&mut mem);
+    writer.write_all(&[1; 3000])?;
+    writer.write_all(&[2; 1000])?;
+    // doesn't fit the geometric grow, but fits the limit
+    writer.write_all(&[3; 400])?;
+
+    let err = writer.write_all(&[4; 200]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::OutOfMemory);
+    let err = err.get_ref().and_then(|err| err.downcast_ref::<Error>());
+    assert_eq!(err, Some(&Error::QuotaExceeded { limit: 4500, requested: 4600 }));
+    assert_eq!(writer.written(), 4400);
+    drop(writer); // trims as `finish`
+
+    assert_eq!(mem.allocated().len(), 4400);
+    assert_eq!(mem.allocated()[4399], 3);
+
+    let writer = MemWriter::new(&mut mem);
+    writer.finish()?; // nothing is written
+    assert_eq!(mem.allocated().len(), 4400);
+    Ok(())
+}
+
+#[test]
+fn error_kinds_and_equality() {
+    use {
+        platform_mem::{Error, ErrorKind, Global, Limited},
+        std::io,
+    };
+
+    let mut mem = Limited::new(Global::<u8>::new(), 2);
+    let err = mem.grow_filled(3, 0).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::QuotaExceeded);
+    assert!(err.is_alloc_failure() && !err.is_io());
+    assert_eq!(err, Error::QuotaExceeded { limit: 2, requested: 3 });
+    assert_ne!(err, Error::QuotaExceeded { limit: 2, requested: 4 });
+
+    let labeled = |label: &str| Error::Labeled {
+        label: label.into(),
+        source: Box::new(Error::QuotaExceeded { limit: 2, requested: 3 }),
+    };
+    assert_eq!(labeled("bytes").kind(), ErrorKind::QuotaExceeded);
+    assert!(labeled("bytes").is_alloc_failure());
+    assert_eq!(labeled("bytes"), labeled("bytes"));
+    assert_ne!(labeled("bytes"), labeled("other"));
+
+    let missing = Error::System(io::Error::from_raw_os_error(2));
+    assert_eq!(missing.kind(), ErrorKind::Io(io::ErrorKind::NotFound));
+    assert!(missing.is_io() && !missing.is_alloc_failure());
+    // system errors are equal by kind and OS code, not by message
+    assert_eq!(missing, Error::System(io::Error::from_raw_os_error(2)));
+    assert_ne!(missing, Error::System(io::ErrorKind::NotFound.into()));
+    assert_eq!(io::Error::from(missing).raw_os_error(), Some(2));
+
+    let overflow = io::Error::from(Error::CapacityOverflow);
+    assert_eq!(overflow.kind(), io::ErrorKind::OutOfMemory);
+    let over_grow = Error::OverGrow { to_grow: 1, available: 0 };
+    assert_eq!(over_grow.kind(), ErrorKind::OverGrow);
+    assert_eq!(io::Error::from(over_grow).kind(), io::ErrorKind::Other);
+}
+
+#[test]
+fn limited_keeps_memory_beyond_lowered_limit() -> Result {
+    use platform_mem::{Error, Global, Limited};
+
+    let mut mem = Limited::with_max_bytes(Global::<u64>::new(), 807);
+    assert_eq!(mem.limit(), 100); // only whole items
+    mem.grow_filled(100, 0)?;
+
+    let err = mem.grow_filled(usize::MAX, 0).unwrap_err();
+    assert_eq!(err, Error::QuotaExceeded { limit: 100, requested: usize::MAX });
+
+    mem.set_limit(10);
+    assert_eq!(mem.allocated().len(), 100);
+    assert!(mem.grow_filled(1, 0).is_err());
+    mem.shrink(95)?; // shrinks are never limited
+    mem.grow_filled(5, 1)?;
+    assert_eq!(mem.allocated(), [0, 0, 0, 0, 0, 1, 1, 1, 1, 1]);
+
+    let unit = Limited::with_max_bytes(Global::<()>::new(), 0);
+    assert_eq!(unit.limit(), usize::MAX);
+    Ok(())
+}
+
+#[test]
+fn chain_shrinks_across_memories() -> Result {
+    use {
+        platform_mem::{Chain, Global, Limited},
+        std::panic::{self, AssertUnwindSafe},
+    };
+
+    let mut head = Global::new();
+    head.grow_from_slice(&[1, 2, 3])?;
+    let mut chain = Chain::new(head, Limited::new(Global::new(), 2));
+
+    assert!(chain.grow_from_slice(&[4, 5, 6]).is_err());
+    chain.grow_from_slice(&[4, 5])?;
+    assert_eq!((chain.get(4), chain.get(5)), (Some(&5), None));
+
+    let over = panic::catch_unwind(AssertUnwindSafe(|| chain.shrink(6)));
+    assert!(over.is_err());
+    assert_eq!(chain.len(), 5); // nothing is shrunk
+
+    chain.shrink(3)?;
+    assert_eq!(chain.as_slices(), (&[1, 2][..], &[][..]));
+    chain.grow_filled(1, 7)?;
+    assert_eq!(chain.iter().copied().collect::<Vec<_>>(), [1, 2, 7]);
+
+    let index = panic::catch_unwind(AssertUnwindSafe(|| chain[3])).unwrap_err();
+    let message = index.downcast_ref::<String>().unwrap();
+    assert_eq!(message, "index 3 is out of chain of 3 items");
+    Ok(())
+}
+
+#[test]
+fn matrix_keeps_whole_rows() -> Result {
+    use {
+        platform_mem::{Error, Global, Matrix2D},
+        std::panic::{self, AssertUnwindSafe},
+    };
+
+    let mut mem = Global::new();
+    mem.grow_from_slice(&[1, 2, 3, 4, 5, 6])?;
+    let mut matrix = Matrix2D::new(mem, 3);
+    assert_eq!(matrix.iter_rows().collect::<Vec<_>>(), [[1, 2, 3], [4, 5, 6]]);
+
+    let err = matrix.grow_rows_filled(usize::MAX, 0).unwrap_err();
+    assert_eq!(err, Error::CapacityOverflow);
+    assert_eq!((matrix.rows(), matrix.get(usize::MAX, 0)), (2, None));
+
+    matrix.shrink_rows(1)?;
+    matrix.row_mut(0)[2] = 30;
+    assert_eq!(matrix.inner().allocated(), [1, 2, 30]);
+
+    let mut mem = matrix.into_inner();
+    mem.grow_filled(1, 0)?;
+    assert!(panic::catch_unwind(AssertUnwindSafe(|| Matrix2D::new(mem, 3))).is_err());
+    Ok(())
+}
+
+#[test]
+fn grow_from_mem_checks_range_and_target() -> Result {
+    use {
+        platform_mem::{Error, Global, Limited},
+        std::panic::{self, AssertUnwindSafe},
+    };
+
+    let mut file = FileMapped::new(tempfile::tempfile()?)?;
+    file.grow_from_slice(&[1u64, 2, 3, 4])?;
+
+    let mut mem = Limited::new(Global::new(), 3);
+    assert!(mem.grow_from_mem(&file, 4..)?.is_empty());
+    let over = panic::catch_unwind(AssertUnwindSafe(|| mem.grow_from_mem(&file, 2..5).map(drop)));
+    assert!(over.is_err());
+
+    let err = mem.grow_from_mem(&file, ..).unwrap_err();
+    assert_eq!(err, Error::QuotaExceeded { limit: 3, requested: 4 });
+    assert_eq!(mem.grow_from_mem(&file, ..=2)?, [1, 2, 3]);
+    Ok(())
+}
+
+#[test]
+fn cell_slices_drop_replaced_items() -> Result {
+    use platform_mem::Global;
+
+    let mut mem = Global::<String>::new();
+    assert!(mem.as_cell_slice().is_empty());
+
+    mem.grow_filled(2, "a".into())?;
+    let cells = mem.as_cell_slice();
+    let old = cells[0].replace("b".into());
+    cells[1].swap(&cells[0]);
+    assert_eq!(old, "a");
+
+    mem.grow_filled(1, "c".into())?;
+    assert_eq!(mem.allocated(), ["a", "b", "c"]);
+    Ok(())
+}
+
+#[test]
+fn chunks_leave_the_remainder() -> Result {
+    use {
+        platform_mem::Global,
+        std::panic::{self, AssertUnwindSafe},
+    };
+
+    let mut mem = Global::new();
+    mem.grow_filled(10, 0)?;
+
+    let mut chunks = mem.chunks_exact_mut(4);
+    chunks.by_ref().enumerate().for_each(|(i, chunk)| chunk.fill(i + 1));
+    chunks.into_remainder().fill(9);
+    assert_eq!(mem.allocated(), [1, 1, 1, 1, 2, 2, 2, 2, 9, 9]);
+    assert_eq!(mem.chunks_exact_mut(11).count(), 0);
+    assert!(panic::catch_unwind(AssertUnwindSafe(|| mem.chunks_exact_mut(0).count())).is_err());
+    Ok(())
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn par_chunks_include_the_shorter_last() -> Result {
+    use {
+        platform_mem::{rayon::prelude::*, Global},
+        std::panic::{self, AssertUnwindSafe},
+    };
+
+    let mut mem = Global::new();
+    mem.grow_filled(10, 0)?;
+
+    mem.par_chunks_mut(4).for_each(|chunk| chunk.fill(chunk.len()));
+    assert_eq!(mem.allocated(), [4, 4, 4, 4, 4, 4, 4, 4, 2, 2]);
+    assert!(panic::catch_unwind(AssertUnwindSafe(|| mem.par_chunks_mut(0).count())).is_err());
+    Ok(())
+}
+
+#[test]
+fn high_water_mark_is_kept_until_compact() -> Result {
+    use std::fs;
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("mem");
+    let mut mem = FileMapped::<u64>::from_path(&path)?.with_high_water_mark();
+    mem.compact()?; // nothing is kept yet
+    mem.grow_filled(10_000, 1)?;
+
+    mem.shrink(10_000)?;
+    assert!(mem.allocated().is_empty());
+    assert_eq!(fs::metadata(&path)?.len(), 80_000);
+
+    mem.grow_filled(20_000, 2)?; // beyond the mark it grows as usual
+    mem.shrink(19_999)?;
+    mem.compact()?;
+    mem.compact()?;
+    assert_eq!((mem.allocated(), fs::metadata(&path)?.len()), (&[2][..], 8));
+
+    mem.shrink(1)?;
+    mem.compact()?;
+    assert_eq!(fs::metadata(&path)?.len(), 0);
+    Ok(())
+}
+
+#[test]
+fn mapped_reads_copy_items() -> Result {
+    let mut mem = FileMapped::<u64>::new(tempfile::tempfile()?)?;
+    mem.grow_from_slice(&[1, 2, 3])?;
+    let mut reader = mem.reader()?;
+
+    let items = reader.read()?;
+    assert_eq!((items.get(2), items.get(3)), (Some(3), None));
+    assert_eq!(format!("{items:?}"), "MappedRead([1, 2, 3])");
+    drop(items);
+
+    mem.allocated_mut()[0] = 10; // copies are not references into the mapping
+    assert_eq!(reader.read()?.to_vec(), [10, 2, 3]);
+    drop(mem);
+    assert!(reader.read()?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn truncated_journal_keeps_the_length() -> Result {
+    use platform_mem::Journaled;
+
+    let dir = tempfile::tempdir()?;
+    let (path, wal) = (dir.path().join("mem"), dir.path().join("wal"));
+
+    let mut mem = Journaled::open(FileMapped::<u64>::from_path(&path)?, &wal)?;
+    mem.grow_from_slice(&[1, 2, 3, 4])?;
+    mem.shrink(1)?;
+    mem.inner().flush()?;
+    mem.truncate_log()?;
+    mem.write(0, &[10])?;
+    drop(mem);
+
+    let mem = Journaled::open(FileMapped::<u64>::from_path(&path)?, &wal)?;
+    assert_eq!(mem.allocated(), [10, 2, 3]);
+    drop(mem);
+
+    // replaying the base again changes nothing
+    let mem = Journaled::open(FileMapped::<u64>::from_path(&path)?, &wal)?;
+    assert_eq!(mem.allocated(), [10, 2, 3]);
+    Ok(())
+}
+
+#[test]
+fn signal_wakes_readers_on_grow() -> Result {
+    use {
+        platform_mem::{FileMapped, RawMem, Signal},
+        std::thread,
+    };
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("grow.signal");
+    let file = tempfile::tempfile()?;
+    let mut mem = FileMapped::<u64>::new(file.try_clone()?)?.with_signal(Signal::open(&path)?);
+
+    // another opening of the file is what a reader in another process has
+    let signal = Signal::open(&path)?;
+    let seen = signal.current();
+    let reader = thread::spawn(move || {
+        signal.wait(seen);
+        file.metadata().map(|meta| meta.len())
+    });
+    mem.grow_filled(10, 7)?;
+    assert!(reader.join().unwrap()? >= 10 * 8);
+
+    // already changed counter isn't waited for
+    let signal = mem.signal().unwrap().clone();
+    assert_eq!(signal.wait(seen), seen + 1);
+    mem.shrink(5)?;
+    mem.grow_filled(5, 7)?;
+    assert_eq!(signal.current(), seen + 2);
+
+    // signal is kept by tokens of the mapping
+    let mem = FileMapped::<u64>::from_token(mem.into_token())?;
+    mem.signal().unwrap().notify();
+    assert_eq!(mem.signal().unwrap().current(), signal.current());
+    Ok(())
+}
+
+#[test]
+fn save_points_are_kept_by_failed_restore() -> Result {
+    use platform_mem::{FaultyMem, Global, Pop, SavePoints};
+
+    let mut mem = SavePoints::new(FaultyMem::new(Global::new()).fail_grow_at(1));
+    mem.grow_from_slice(&[1, 2, 3])?;
+
+    mem.push_state();
+    mem.shrink(2)?;
+    mem.write(0, &[4])?;
+    assert!(mem.pop_state(Pop::Restore).is_err()); // grow of removed items fails
+    assert_eq!((mem.depth(), mem.allocated()), (1, &[1][..]));
+
+    assert!(mem.pop_state(Pop::Restore)?);
+    assert_eq!((mem.depth(), mem.allocated()), (0, &[1, 2, 3][..]));
+    Ok(())
+}
+
+#[test]
+fn slot_keys_survive_reopening() -> Result {
+    use platform_mem::{RawMem, Slot, SlotMem};
+
+    let path = tempfile::NamedTempFile::new()?.into_temp_path();
+    let mut slots = SlotMem::new(FileMapped::<Slot<u64>>::from_path(&path)?)?;
+    let keys = [slots.insert(1)?, slots.insert(2)?, slots.insert(3)?];
+    slots.remove(keys[1]);
+    drop(slots);
+
+    let mut mem = FileMapped::<Slot<u64>>::from_path(&path)?;
+    // SAFETY: file is written by `SlotMem<u64>`
+    unsafe { mem.grow_assumed(mem.size_hint().unwrap())? };
+    let mut slots = SlotMem::new(mem)?;
+    assert_eq!((slots.len(), slots.get(keys[0]), slots.get(keys[1])), (2, Some(&1), None));
+    let key = slots.insert(4)?;
+    assert_eq!((key.index, slots.get(keys[2])), (keys[1].index, Some(&3)));
+    Ok(())
+}
+
+#[test]
+fn trim_keeps_contents() -> Result {
+    use platform_mem::{Global, TempFile};
+
+    let mut mem = Global::<u64>::new().with_deferred_shrink(usize::MAX);
+    mem.grow_filled(8192, 1)?;
+    mem.shrink(8000)?;
+    assert!(mem.trim()? >= 32768);
+    assert_eq!(mem.memory_usage().capacity, 65536);
+    mem.grow_filled(8000, 2)?;
+    assert!(mem.allocated()[..192].iter().all(|&item| item == 1));
+
+    let mut mem = TempFile::<u64>::new()?.with_deferred_shrink(usize::MAX);
+    mem.grow_filled(8192, 1)?;
+    mem.shrink(8000)?;
+    mem.trim()?;
+    assert_eq!(mem.allocated(), [1; 192]);
+    Ok(())
+}
+
+#[test]
+fn reservation_survives_resize() -> Result {
+    use platform_mem::TempFile;
+
+    let mut mem = TempFile::<u64>::new()?;
+    mem.grow_filled(4, 1)?;
+    let reservation = mem.begin_grow(1020)?;
+    assert_eq!((mem.allocated().len(), mem.memory_usage().capacity), (4, 8192));
+
+    mem.shrink(2)?; // releases reserved place
+    let grown = unsafe {
+        mem.commit(reservation, |_, (_, uninit)| {
+            uninit.iter_mut().for_each(|item| _ = item.write(2));
+        })?
+    };
+    assert_eq!(grown.len(), 1020);
+    assert_eq!(mem.allocated()[..3], [1, 1, 2]);
+    Ok(())
+}
+
+#[cfg(feature = "testing")]
+#[quickcheck_macros::quickcheck]
+fn random_ops_match_model(ops: Vec<platform_mem::testing::Op>) -> bool {
+    use platform_mem::{testing, Global, TempFile};
+
+    testing::check_ops(&mut Global::<u64>::new(), &ops).is_ok()
+        && testing::check_ops(&mut Global::<u64>::new().with_deferred_shrink(16), &ops).is_ok()
+        && testing::check_ops(&mut TempFile::<u8>::new().unwrap(), &ops).is_ok()
+}
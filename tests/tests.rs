@@ -66,3 +66,28 @@ define_impls! {
         mem::grow_from_slice as grow_from_slice,
     ]
 }
+
+#[cfg(feature = "testing")]
+#[test]
+fn conformance() -> platform_mem::Result<()> {
+    use platform_mem::testing;
+
+    testing::conformance(Global::new)?;
+    testing::conformance(System::new)?;
+    #[cfg(not(miri))]
+    testing::conformance(|| TempFile::new().unwrap())?;
+    testing::conformance(|| Global::new().with_deferred_shrink(4))?;
+    #[cfg(not(miri))]
+    testing::conformance(|| TempFile::new().unwrap().with_deferred_shrink(4))?;
+    testing::conformance(|| {
+        use platform_mem::{Cached, FillPolicy, WritePolicy};
+        Cached::new(Global::new(), Global::new(), FillPolicy::OnDemand, WritePolicy::Discard)
+            .unwrap()
+    })?;
+    // falls back in the middle of checks
+    testing::conformance(|| {
+        platform_mem::Fallback::new(platform_mem::Limited::new(Global::new(), 4), Global::new())
+    })?;
+
+    Ok(())
+}